@@ -119,6 +119,7 @@ pub fn create_benchmark_extrinsic(
 			best_block.saturated_into(),
 		)),
 		frame_system::CheckNonce::<runtime::Runtime>::from(nonce),
+		pallet_template::CheckUsernameNonce::<runtime::Runtime>::new(),
 		frame_system::CheckWeight::<runtime::Runtime>::new(),
 		pallet_transaction_payment::ChargeTransactionPayment::<runtime::Runtime>::from(0),
 		frame_metadata_hash_extension::CheckMetadataHash::<runtime::Runtime>::new(false),
@@ -137,6 +138,7 @@ pub fn create_benchmark_extrinsic(
 			(),
 			(),
 			(),
+			(),
 			None,
 			(),
 		),
@@ -0,0 +1,94 @@
+//! Diagnostics for integrators whose signatures don't verify.
+//!
+//! Only built with the `debug-rpc` feature, so it never ships in a production node.
+
+use jsonrpsee::{core::RpcResult, proc_macros::rpc};
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Sha3_256};
+use sp_core::{hashing::keccak_256, H160};
+use username_primitives::{eth_message_hash, recover_eth_address_prehashed};
+
+use super::username::decode_signature;
+
+/// The address a signature recovers to under each way a client might have hashed the message.
+///
+/// Only `personal_sign` is what the pallet checks. If the expected address shows up under
+/// another field instead, the client hashed the message that way.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DebugRecovery {
+    /// Keccak-256 of the EIP-191 `personal_sign` prefix and the message.
+    pub personal_sign: Option<H160>,
+    /// Keccak-256 of the bare message, without the EIP-191 prefix.
+    pub keccak_256: Option<H160>,
+    /// NIST SHA3-256 of the EIP-191 prefix and the message.
+    pub sha3_256_personal_sign: Option<H160>,
+    /// NIST SHA3-256 of the bare message.
+    pub sha3_256: Option<H160>,
+}
+
+#[rpc(client, server)]
+pub trait UsernameDebugApi {
+    /// Recover the signer of `message` under every hashing in [`DebugRecovery`].
+    #[method(name = "username_debug_recover")]
+    fn debug_recover(&self, message: String, signature: String) -> RpcResult<DebugRecovery>;
+}
+
+/// Serves [`UsernameDebugApiServer`]. It needs no client, as nothing is read from the chain.
+pub struct UsernameDebugRpc;
+
+/// `message` with the EIP-191 `personal_sign` prefix, before hashing.
+fn personal_sign_preimage(message: &[u8]) -> Vec<u8> {
+    let mut preimage = format!("\x19Ethereum Signed Message:\n{}", message.len()).into_bytes();
+    preimage.extend_from_slice(message);
+    preimage
+}
+
+fn sha3_256(data: &[u8]) -> [u8; 32] {
+    Sha3_256::digest(data).into()
+}
+
+impl UsernameDebugApiServer for UsernameDebugRpc {
+    fn debug_recover(&self, message: String, signature: String) -> RpcResult<DebugRecovery> {
+        let signature = decode_signature(&signature)?;
+
+        let message = message.as_bytes();
+        let recover = |hash: [u8; 32]| recover_eth_address_prehashed(&hash, &signature);
+        Ok(DebugRecovery {
+            personal_sign: recover(eth_message_hash(message)),
+            keccak_256: recover(keccak_256(message)),
+            sha3_256_personal_sign: recover(sha3_256(&personal_sign_preimage(message))),
+            sha3_256: recover(sha3_256(message)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sp_core::{ecdsa, Pair};
+    use username_primitives::{ETH_V_OFFSET, SIGNATURE_LEN};
+
+    #[test]
+    fn reports_the_hashing_the_signer_used() {
+        let pair = ecdsa::Pair::from_seed(&[1; 32]);
+        let message = "set_username:42:alice:0";
+        let mut signature = pair
+            .sign_prehashed(&sha3_256(&personal_sign_preimage(message.as_bytes())))
+            .0;
+        signature[SIGNATURE_LEN - 1] += ETH_V_OFFSET;
+
+        let recovery = UsernameDebugRpc
+            .debug_recover(message.into(), format!("0x{}", hex::encode(signature)))
+            .unwrap();
+
+        let expected = recover_eth_address_prehashed(
+            &sha3_256(&personal_sign_preimage(message.as_bytes())),
+            &signature,
+        );
+        assert!(expected.is_some());
+        assert_eq!(recovery.sha3_256_personal_sign, expected);
+        assert_ne!(recovery.personal_sign, expected);
+        assert_ne!(recovery.keccak_256, expected);
+        assert_ne!(recovery.sha3_256, expected);
+    }
+}
@@ -15,6 +15,8 @@ use sp_block_builder::BlockBuilder;
 use sp_blockchain::{Error as BlockChainError, HeaderBackend, HeaderMetadata};
 mod username;
 use username::{UsernameRpc, UsernameApiServer};
+#[cfg(feature = "debug-rpc")]
+mod debug;
 
 /// Full client dependencies.
 pub struct FullDeps<C, P> {
@@ -32,6 +34,7 @@ where
 	C: ProvideRuntimeApi<Block>,
 	C: HeaderBackend<Block> + HeaderMetadata<Block, Error = BlockChainError> + 'static,
 	C: Send + Sync + 'static,
+	C: sc_client_api::BlockchainEvents<Block>,
 	C::Api: substrate_frame_rpc_system::AccountNonceApi<Block, AccountId, Nonce>,
 	C::Api: pallet_transaction_payment_rpc::TransactionPaymentRuntimeApi<Block, Balance>,
 	C::Api: BlockBuilder<Block>,
@@ -47,6 +50,11 @@ where
 	module.merge(System::new(client.clone(), pool).into_rpc())?;
 	module.merge(TransactionPayment::new(client.clone()).into_rpc())?;
   module.merge(UsernameRpc::new(client).into_rpc())?;
+	#[cfg(feature = "debug-rpc")]
+	{
+		use debug::{UsernameDebugApiServer, UsernameDebugRpc};
+		module.merge(UsernameDebugRpc.into_rpc())?;
+	}
 
 	// Extend this RPC with a custom API by using the following syntax.
 	// `YourRpcStruct` should have a reference to a client, which is needed
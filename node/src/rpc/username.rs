@@ -1,17 +1,349 @@
+use futures::{
+    future::{self, Either},
+    StreamExt,
+};
 use jsonrpsee::{
-    core::{async_trait, RpcResult},
+    core::{async_trait, RpcResult, SubscriptionResult},
     proc_macros::rpc,
     types::error::ErrorObjectOwned,
+    PendingSubscriptionSink, SubscriptionMessage,
 };
 
+use pallet_template::ScanError;
+use sc_client_api::BlockchainEvents;
+use serde::{Deserialize, Serialize};
 use solochain_template_runtime::apis::UsernameApi as UsernameRuntimeApi;
-use sp_api::ProvideRuntimeApi;
+use sp_api::{Core, ProvideRuntimeApi};
 use sp_blockchain::HeaderBackend;
 use sp_core::H160;
-use sp_io::hashing::keccak_256;
 use sp_runtime::traits::Block as BlockT;
 use std::sync::Arc;
+use username_primitives::{
+    eth_message_hash, recover_eth_address, verify_eth_signature, EthSignatureError, SIGNATURE_LEN,
+};
+
+/// Log target of the username RPC. Signature details are logged here at `trace` level only.
+const LOG_TARGET: &str = "username-rpc";
+
+/// Maximum number of entries `username_list` returns in one page.
+pub const MAX_LIST_LIMIT: u32 = 500;
+
+/// Maximum number of matches `username_search` returns.
+pub const MAX_SEARCH_LIMIT: u32 = 100;
+
+/// First JSON-RPC error code of the username RPC. [`UsernameRpcError::code`] adds each
+/// variant's offset to it, which keeps the codes clear of the small numbers other custom RPC
+/// namespaces on the same node tend to use.
+pub const ERROR_CODE_BASE: i32 = 9000;
+
+/// Errors returned by the username RPC methods.
+///
+/// Every variant maps to a fixed JSON-RPC error code, [`ERROR_CODE_BASE`] plus the offset
+/// below, so clients can branch on the code instead of parsing messages:
+///
+/// | Offset | Variant |
+/// |--------|---------|
+/// | 1 | [`QueryFailed`](Self::QueryFailed) |
+/// | 2 | [`InvalidSignatureFormat`](Self::InvalidSignatureFormat) |
+/// | 3 | [`SignatureAddressMismatch`](Self::SignatureAddressMismatch) |
+/// | 4 | [`LimitExceeded`](Self::LimitExceeded) |
+/// | 5 | [`InvalidMessageFormat`](Self::InvalidMessageFormat) |
+/// | 6 | [`InvalidNonce`](Self::InvalidNonce) |
+/// | 7 | [`InvalidUsername`](Self::InvalidUsername) |
+/// | 8 | [`SignatureWrongLength`](Self::SignatureWrongLength) |
+/// | 9 | [`UnknownBlock`](Self::UnknownBlock) |
+/// | 10 | [`MessageTooLong`](Self::MessageTooLong) |
+/// | 11 | [`InvalidAddressFormat`](Self::InvalidAddressFormat) |
+/// | 12 | [`AddressWrongLength`](Self::AddressWrongLength) |
+/// | 13 | [`SignatureRecoveryFailed`](Self::SignatureRecoveryFailed) |
+/// | 14 | [`InvalidCursor`](Self::InvalidCursor) |
+/// | 15 | [`ZeroLimit`](Self::ZeroLimit) |
+#[derive(Debug)]
+pub enum UsernameRpcError {
+    /// A runtime API call failed. `what` names what was being queried.
+    QueryFailed { what: &'static str, reason: String },
+    /// The signature is not valid hex. Carries what is wrong with it and where.
+    InvalidSignatureFormat(String),
+    /// The signature decoded to something other than [`SIGNATURE_LEN`] bytes.
+    SignatureWrongLength(usize),
+    /// The signature recovers to `recovered` rather than the expected address, so it was made
+    /// with another key or over another message.
+    SignatureAddressMismatch { recovered: H160 },
+    /// A request asked for more items than the method allows.
+    LimitExceeded { requested: usize, max: usize },
+    /// The signed message does not follow the expected format.
+    InvalidMessageFormat { expected: String },
+    /// The signed message carries a nonce other than the address's current one.
+    InvalidNonce { expected: u64 },
+    /// The username breaks the pallet's length or charset rules.
+    InvalidUsername(String),
+    /// No block with this number is known to the node.
+    UnknownBlock(u32),
+    /// The signed message is longer than [`MAX_SECURE_MESSAGE_LENGTH`] bytes.
+    MessageTooLong(usize),
+    /// The address is not valid hex. Carries what is wrong with it and where.
+    InvalidAddressFormat(String),
+    /// The address decoded to something other than 20 bytes.
+    AddressWrongLength(usize),
+    /// No address can be recovered from the signature: its `v` is out of range, its `s` is
+    /// high, or it does not correspond to any key.
+    SignatureRecoveryFailed,
+    /// The `username_search` cursor is longer than any username, so it can't be one.
+    InvalidCursor,
+    /// A paged method was asked for a page of zero entries.
+    ZeroLimit,
+}
+
+impl UsernameRpcError {
+    /// The stable JSON-RPC error code of this error.
+    pub fn code(&self) -> i32 {
+        ERROR_CODE_BASE + self.offset()
+    }
+
+    /// The offset of this error's code from [`ERROR_CODE_BASE`].
+    fn offset(&self) -> i32 {
+        match self {
+            Self::QueryFailed { .. } => 1,
+            Self::InvalidSignatureFormat(_) => 2,
+            Self::SignatureAddressMismatch { .. } => 3,
+            Self::LimitExceeded { .. } => 4,
+            Self::InvalidMessageFormat { .. } => 5,
+            Self::InvalidNonce { .. } => 6,
+            Self::InvalidUsername(_) => 7,
+            Self::SignatureWrongLength(_) => 8,
+            Self::UnknownBlock(_) => 9,
+            Self::MessageTooLong(_) => 10,
+            Self::InvalidAddressFormat(_) => 11,
+            Self::AddressWrongLength(_) => 12,
+            Self::SignatureRecoveryFailed => 13,
+            Self::InvalidCursor => 14,
+            Self::ZeroLimit => 15,
+        }
+    }
+
+    /// A runtime API error while querying `what`.
+    fn query_failed(what: &'static str, error: impl std::fmt::Debug) -> Self {
+        Self::QueryFailed {
+            what,
+            reason: format!("{:?}", error),
+        }
+    }
+}
+
+impl From<UsernameRpcError> for ErrorObjectOwned {
+    fn from(error: UsernameRpcError) -> Self {
+        let code = error.code();
+        match error {
+            UsernameRpcError::QueryFailed { what, reason } => {
+                ErrorObjectOwned::owned(code, format!("Unable to query {}", what), Some(reason))
+            }
+            UsernameRpcError::InvalidSignatureFormat(reason) => {
+                ErrorObjectOwned::owned(code, "Signature is not valid hex", Some(reason))
+            }
+            UsernameRpcError::SignatureWrongLength(len) => ErrorObjectOwned::owned(
+                code,
+                format!(
+                    "Signature has the wrong length (got {}, expected {})",
+                    len, SIGNATURE_LEN
+                ),
+                None::<()>,
+            ),
+            UsernameRpcError::SignatureAddressMismatch { recovered } => ErrorObjectOwned::owned(
+                code,
+                "Signature does not match the address",
+                Some(format!("signed by {:#x}", recovered)),
+            ),
+            UsernameRpcError::SignatureRecoveryFailed => {
+                ErrorObjectOwned::owned(code, "Signature recovery failed", None::<()>)
+            }
+            UsernameRpcError::LimitExceeded { requested, max } => ErrorObjectOwned::owned(
+                code,
+                "Limit exceeds maximum",
+                Some(format!("{} is above {}", requested, max)),
+            ),
+            UsernameRpcError::InvalidMessageFormat { expected } => ErrorObjectOwned::owned(
+                code,
+                "Invalid message format",
+                Some(format!("expected {}", expected)),
+            ),
+            UsernameRpcError::InvalidNonce { expected } => ErrorObjectOwned::owned(
+                code,
+                "Invalid nonce",
+                Some(format!("expected nonce {}", expected)),
+            ),
+            UsernameRpcError::InvalidUsername(reason) => {
+                ErrorObjectOwned::owned(code, "Invalid username", Some(reason))
+            }
+            UsernameRpcError::UnknownBlock(number) => ErrorObjectOwned::owned(
+                code,
+                "Unknown block",
+                Some(format!("no block #{} on this node", number)),
+            ),
+            UsernameRpcError::MessageTooLong(len) => ErrorObjectOwned::owned(
+                code,
+                "Message too long",
+                Some(format!(
+                    "{} bytes is above {}",
+                    len, MAX_SECURE_MESSAGE_LENGTH
+                )),
+            ),
+            UsernameRpcError::InvalidAddressFormat(reason) => {
+                ErrorObjectOwned::owned(code, "Address is not valid hex", Some(reason))
+            }
+            UsernameRpcError::AddressWrongLength(len) => ErrorObjectOwned::owned(
+                code,
+                format!(
+                    "Address has the wrong length (got {}, expected {})",
+                    len,
+                    H160::len_bytes()
+                ),
+                None::<()>,
+            ),
+            UsernameRpcError::InvalidCursor => {
+                ErrorObjectOwned::owned(code, "Invalid cursor", None::<()>)
+            }
+            UsernameRpcError::ZeroLimit => {
+                ErrorObjectOwned::owned(code, "Limit must be at least 1", None::<()>)
+            }
+        }
+    }
+}
+
+impl From<ScanError> for UsernameRpcError {
+    fn from(error: ScanError) -> Self {
+        match error {
+            ScanError::LimitTooHigh { requested, max } => Self::LimitExceeded {
+                requested: requested as usize,
+                max: max as usize,
+            },
+            ScanError::InvalidCursor => Self::InvalidCursor,
+            ScanError::ZeroLimit => Self::ZeroLimit,
+        }
+    }
+}
+
+/// Maximum length in bytes of the message signed for `username_get_secure`. A well-formed
+/// `get_username:{eth_address}:{nonce}` message is at most 76 bytes.
+pub const MAX_SECURE_MESSAGE_LENGTH: usize = 256;
+
+/// Maximum number of addresses `username_get_batch` resolves in one call.
+pub const MAX_BATCH_SIZE: usize = 100;
+
+/// The result of resolving one address in `username_get_batch`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct UsernameLookup {
+    pub eth_address: H160,
+    pub username: Option<String>,
+}
+
+/// The result of `username_verify_signature`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SignatureCheck {
+    /// Whether the signature recovers to the given address.
+    pub valid: bool,
+    /// The address the signature recovers to, if it recovers at all.
+    pub recovered: Option<H160>,
+}
+
+/// A registered username and the address that owns it.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct UsernameEntry {
+    pub eth_address: H160,
+    pub username: String,
+}
 
+/// What `username_info` reports about the runtime serving the username RPC.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ServiceInfo {
+    /// `spec_version` of the runtime at the best block.
+    pub spec_version: u32,
+    pub max_username_length: u32,
+    pub username_count: u32,
+}
+
+/// The username rules of the runtime, as reported by `username_get_config`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct UsernameConfig {
+    /// Maximum username length, in bytes.
+    pub max_length: u32,
+    /// Minimum username length, in bytes.
+    pub min_length: u32,
+    /// The runtime's description of the characters a username may contain.
+    pub allowed_chars: String,
+    /// Blocks an address has to wait between two username changes.
+    pub change_cooldown: u32,
+}
+
+/// One page of `username_list` results.
+///
+/// Entries follow the storage map's key order, not insertion order. `next_cursor` is `None`
+/// once the last page has been returned.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct UsernamePage {
+    pub entries: Vec<UsernameEntry>,
+    pub next_cursor: Option<H160>,
+}
+
+/// One page of `username_search` results.
+///
+/// Each call scans a bounded number of registrations, so a page may hold fewer than `limit`
+/// matches, or none, and still have a `next_cursor`. Keep passing it back until it is `None`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct UsernameSearchPage {
+    pub entries: Vec<UsernameEntry>,
+    /// The normalized username the scan stopped at.
+    pub next_cursor: Option<String>,
+}
+
+/// One entry of `username_get_history`: the username assigned at `block`, or `None` if the
+/// username was removed then.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub username: Option<String>,
+    pub block: u32,
+}
+
+/// Profile metadata attached to a username, decoded as (lossy) UTF-8.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ProfileMetadata {
+    pub display_name: String,
+    pub avatar_uri: String,
+    pub bio: String,
+}
+
+/// Everything `username_get_profile` knows about one address, read at a single block.
+///
+/// An unregistered address has no `username`, `registered_at` or `metadata`, but still reports
+/// its nonce.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Profile {
+    pub username: Option<String>,
+    pub nonce: u64,
+    pub registered_at: Option<u32>,
+    pub metadata: Option<ProfileMetadata>,
+}
+
+/// Whether a [`UsernameChange`] assigned or removed a username.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Set,
+    Cleared,
+}
+
+/// A username change pushed by `username_subscribe_changes`.
+///
+/// `username` is `None` for [`ChangeKind::Cleared`], which also covers expiry and Root removal.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct UsernameChange {
+    pub eth_address: H160,
+    pub username: Option<String>,
+    pub kind: ChangeKind,
+}
+
+/// Every method that reads chain state takes an optional `at` block hash as its last parameter
+/// and reads the state of that block, so explorers can query historical state the same way
+/// everywhere. Without it, the current best block is used.
 #[rpc(client, server)]
 pub trait UsernameApi<BlockHash> {
     #[method(name = "username_get_nonce")]
@@ -20,14 +352,106 @@ pub trait UsernameApi<BlockHash> {
     #[method(name = "username_get")]
     fn get_username(&self, eth_address: H160, at: Option<BlockHash>) -> RpcResult<Option<String>>;
 
+    /// Like `username_get`, but at the best-chain block with the given number.
+    #[method(name = "username_get_at_number")]
+    fn get_username_at_number(&self, eth_address: H160, number: u32) -> RpcResult<Option<String>>;
+
+    /// Recent username changes of `eth_address`, oldest first.
+    #[method(name = "username_get_history")]
+    fn get_username_history(
+        &self,
+        eth_address: H160,
+        at: Option<BlockHash>,
+    ) -> RpcResult<Vec<HistoryEntry>>;
+
+    /// The block at which `eth_address` registered its current username. Renames keep it; a
+    /// cleared username loses it.
+    #[method(name = "username_get_registration_block")]
+    fn get_registration_block(
+        &self,
+        eth_address: H160,
+        at: Option<BlockHash>,
+    ) -> RpcResult<Option<u32>>;
+
+    /// The username, nonce, registration block and `set_profile` metadata of `eth_address`.
+    #[method(name = "username_get_profile")]
+    fn get_profile(&self, eth_address: H160, at: Option<BlockHash>) -> RpcResult<Profile>;
+
+    /// Resolve up to `MAX_BATCH_SIZE` addresses at once, in the order given.
+    #[method(name = "username_get_batch")]
+    fn get_usernames(
+        &self,
+        addresses: Vec<String>,
+        at: Option<BlockHash>,
+    ) -> RpcResult<Vec<UsernameLookup>>;
+
+    #[method(name = "username_get_address")]
+    fn get_address(&self, username: String, at: Option<BlockHash>) -> RpcResult<Option<H160>>;
+
+    /// One cursor-paged, capped page of `(address, username)` pairs. Also served as
+    /// `username_get_owners_page`, the name explorers look for when rendering owners in bulk.
+    #[method(name = "username_list", aliases = ["username_get_owners_page"])]
+    fn list_usernames(
+        &self,
+        cursor: Option<H160>,
+        limit: u32,
+        at: Option<BlockHash>,
+    ) -> RpcResult<UsernamePage>;
+
+    /// Usernames starting with `prefix`, ignoring ASCII case, continuing from `cursor`. Each
+    /// call scans at most `pallet_template::MAX_RPC_SCAN` registrations.
+    #[method(name = "username_search")]
+    fn search_usernames(
+        &self,
+        prefix: String,
+        limit: u32,
+        cursor: Option<String>,
+        at: Option<BlockHash>,
+    ) -> RpcResult<UsernameSearchPage>;
+
+    #[method(name = "username_count")]
+    fn username_count(&self, at: Option<BlockHash>) -> RpcResult<u32>;
+
+    /// The length, charset and cooldown rules of the runtime, so frontends can validate a name
+    /// before asking the user to sign it.
+    #[method(name = "username_get_config")]
+    fn get_config(&self, at: Option<BlockHash>) -> RpcResult<UsernameConfig>;
+
+    /// Health check: the runtime version and username limits at the best block.
+    #[method(name = "username_info")]
+    fn info(&self) -> RpcResult<ServiceInfo>;
+
+    /// Whether `username` can be registered. Fails with error code 9007 if the name breaks the
+    /// length or charset rules, so the caller learns why.
+    #[method(name = "username_is_available")]
+    fn is_username_available(&self, username: String, at: Option<BlockHash>) -> RpcResult<bool>;
+
     #[method(name = "username_get_secure")]
     fn get_username_secure(
         &self,
-        eth_address: H160,
+        eth_address: String,
         signature: String,
         message: String,
         at: Option<BlockHash>,
     ) -> RpcResult<Option<String>>;
+
+    /// Check an EIP-191 signature of `message` by `eth_address` without reading the chain.
+    /// Malformed input is reported as `valid: false` rather than as an error.
+    #[method(name = "username_verify_signature")]
+    fn verify_signature(
+        &self,
+        eth_address: String,
+        message: String,
+        signature: String,
+    ) -> RpcResult<SignatureCheck>;
+
+    /// Stream username changes from each new best block, in event order.
+    #[subscription(
+        name = "username_subscribe_changes" => "username_change",
+        unsubscribe = "username_unsubscribe_changes",
+        item = UsernameChange
+    )]
+    async fn subscribe_changes(&self) -> SubscriptionResult;
 }
 
 pub struct UsernameRpc<C, Block> {
@@ -44,20 +468,35 @@ impl<C, Block> UsernameRpc<C, Block> {
     }
 }
 
+impl<C, Block> UsernameRpc<C, Block>
+where
+    Block: BlockT,
+    C: HeaderBackend<Block>,
+{
+    /// The block a query runs against: `at` if given, otherwise the current best block.
+    fn at_or_best(&self, at: Option<<Block as BlockT>::Hash>) -> <Block as BlockT>::Hash {
+        at.unwrap_or_else(|| self.client.info().best_hash)
+    }
+}
+
 #[async_trait]
 impl<C, Block> UsernameApiServer<<Block as BlockT>::Hash> for UsernameRpc<C, Block>
 where
     Block: BlockT,
-    C: Send + Sync + 'static + ProvideRuntimeApi<Block> + HeaderBackend<Block>,
+    C: Send
+        + Sync
+        + 'static
+        + ProvideRuntimeApi<Block>
+        + HeaderBackend<Block>
+        + BlockchainEvents<Block>,
     C::Api: UsernameRuntimeApi<Block>,
 {
     fn get_nonce(&self, eth_address: H160, at: Option<<Block as BlockT>::Hash>) -> RpcResult<u64> {
         let api = self.client.runtime_api();
-        let at_hash = at.unwrap_or_else(|| self.client.info().best_hash);
+        let at_hash = self.at_or_best(at);
 
-        api.get_nonce(at_hash, eth_address).map_err(|e| {
-            ErrorObjectOwned::owned(1, "Unable to query nonce", Some(format!("{:?}", e)))
-        })
+        api.get_nonce(at_hash, eth_address)
+            .map_err(|e| UsernameRpcError::query_failed("nonce", e).into())
     }
 
     fn get_username(
@@ -65,85 +504,789 @@ where
         eth_address: H160,
         at: Option<<Block as BlockT>::Hash>,
     ) -> RpcResult<Option<String>> {
-        let at_hash = at.unwrap_or_else(|| self.client.info().best_hash);
+        let at_hash = self.at_or_best(at);
 
         let api = self.client.runtime_api();
 
         api.get_username(at_hash, eth_address)
             .map(|opt| opt.map(|bytes| String::from_utf8_lossy(&bytes[..]).to_string()))
+            .map_err(|e| UsernameRpcError::query_failed("username", e).into())
+    }
+
+    fn get_username_at_number(&self, eth_address: H160, number: u32) -> RpcResult<Option<String>> {
+        let at_hash = self
+            .client
+            .hash(number.into())
+            .map_err(|e| ErrorObjectOwned::from(UsernameRpcError::query_failed("block hash", e)))?
+            .ok_or(UsernameRpcError::UnknownBlock(number))?;
+
+        self.get_username(eth_address, Some(at_hash))
+    }
+
+    fn get_username_history(
+        &self,
+        eth_address: H160,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> RpcResult<Vec<HistoryEntry>> {
+        let at_hash = self.at_or_best(at);
+
+        let api = self.client.runtime_api();
+
+        let history = api
+            .get_username_history(at_hash, eth_address)
             .map_err(|e| {
-                ErrorObjectOwned::owned(1, "Unable to query username", Some(format!("{:?}", e)))
+                ErrorObjectOwned::from(UsernameRpcError::query_failed("username history", e))
+            })?;
+
+        Ok(history
+            .into_iter()
+            .map(|(username, block)| HistoryEntry {
+                username: (!username.is_empty())
+                    .then(|| String::from_utf8_lossy(&username).to_string()),
+                block,
             })
+            .collect())
     }
 
-    fn get_username_secure(
+    fn get_registration_block(
         &self,
         eth_address: H160,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> RpcResult<Option<u32>> {
+        let at_hash = self.at_or_best(at);
+
+        let api = self.client.runtime_api();
+
+        api.get_registration_block(at_hash, eth_address)
+            .map_err(|e| UsernameRpcError::query_failed("registration block", e).into())
+    }
+
+    fn get_profile(
+        &self,
+        eth_address: H160,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> RpcResult<Profile> {
+        let at_hash = self.at_or_best(at);
+
+        let api = self.client.runtime_api();
+
+        api.get_profile(at_hash, eth_address)
+            .map(|profile| Profile {
+                username: profile
+                    .username
+                    .map(|username| String::from_utf8_lossy(&username).to_string()),
+                nonce: profile.nonce,
+                registered_at: profile.registered_at,
+                metadata: profile.metadata.map(|metadata| ProfileMetadata {
+                    display_name: String::from_utf8_lossy(&metadata.display_name).to_string(),
+                    avatar_uri: String::from_utf8_lossy(&metadata.avatar_uri).to_string(),
+                    bio: String::from_utf8_lossy(&metadata.bio).to_string(),
+                }),
+            })
+            .map_err(|e| UsernameRpcError::query_failed("profile", e).into())
+    }
+
+    fn get_usernames(
+        &self,
+        addresses: Vec<String>,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> RpcResult<Vec<UsernameLookup>> {
+        if addresses.len() > MAX_BATCH_SIZE {
+            return Err(UsernameRpcError::LimitExceeded {
+                requested: addresses.len(),
+                max: MAX_BATCH_SIZE,
+            }
+            .into());
+        }
+        let addresses = addresses
+            .iter()
+            .map(|address| parse_eth_address(address))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let at_hash = self.at_or_best(at);
+
+        let api = self.client.runtime_api();
+
+        let results = api
+            .get_usernames(at_hash, addresses)
+            .map_err(|e| ErrorObjectOwned::from(UsernameRpcError::query_failed("usernames", e)))?;
+
+        Ok(results
+            .into_iter()
+            .map(|(eth_address, username)| UsernameLookup {
+                eth_address,
+                username: username.map(|bytes| String::from_utf8_lossy(&bytes).to_string()),
+            })
+            .collect())
+    }
+
+    fn get_address(
+        &self,
+        username: String,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> RpcResult<Option<H160>> {
+        let username = normalize_username(&username);
+
+        let at_hash = self.at_or_best(at);
+
+        let api = self.client.runtime_api();
+
+        api.get_address(at_hash, username)
+            .map_err(|e| UsernameRpcError::query_failed("address", e).into())
+    }
+
+    fn list_usernames(
+        &self,
+        cursor: Option<H160>,
+        limit: u32,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> RpcResult<UsernamePage> {
+        if limit > MAX_LIST_LIMIT {
+            return Err(UsernameRpcError::LimitExceeded {
+                requested: limit as usize,
+                max: MAX_LIST_LIMIT as usize,
+            }
+            .into());
+        }
+
+        let at_hash = self.at_or_best(at);
+
+        let api = self.client.runtime_api();
+
+        let page = api
+            .list_usernames(at_hash, cursor, limit)
+            .map_err(|e| UsernameRpcError::query_failed("usernames", e))?
+            .map_err(UsernameRpcError::from)?;
+
+        Ok(UsernamePage {
+            entries: username_entries(page.entries),
+            next_cursor: page.next_cursor,
+        })
+    }
+
+    fn search_usernames(
+        &self,
+        prefix: String,
+        limit: u32,
+        cursor: Option<String>,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> RpcResult<UsernameSearchPage> {
+        if prefix.is_empty() {
+            return Err(
+                UsernameRpcError::InvalidUsername("prefix must not be empty".into()).into(),
+            );
+        }
+        if limit > MAX_SEARCH_LIMIT {
+            return Err(UsernameRpcError::LimitExceeded {
+                requested: limit as usize,
+                max: MAX_SEARCH_LIMIT as usize,
+            }
+            .into());
+        }
+
+        let at_hash = self.at_or_best(at);
+
+        let api = self.client.runtime_api();
+
+        let page = api
+            .search_usernames(
+                at_hash,
+                prefix.into_bytes(),
+                cursor.map(String::into_bytes),
+                limit,
+            )
+            .map_err(|e| UsernameRpcError::query_failed("usernames", e))?
+            .map_err(UsernameRpcError::from)?;
+
+        Ok(UsernameSearchPage {
+            entries: username_entries(page.entries),
+            next_cursor: page
+                .next_cursor
+                .map(|name| String::from_utf8_lossy(&name).to_string()),
+        })
+    }
+
+    fn username_count(&self, at: Option<<Block as BlockT>::Hash>) -> RpcResult<u32> {
+        let at_hash = self.at_or_best(at);
+
+        let api = self.client.runtime_api();
+
+        api.username_count(at_hash)
+            .map_err(|e| UsernameRpcError::query_failed("username count", e).into())
+    }
+
+    fn info(&self) -> RpcResult<ServiceInfo> {
+        let at_hash = self.client.info().best_hash;
+
+        let api = self.client.runtime_api();
+
+        let query_failed =
+            |e| ErrorObjectOwned::from(UsernameRpcError::query_failed("service info", e));
+
+        let version = api.version(at_hash).map_err(query_failed)?;
+        let (_, max_username_length) = api.username_length_bounds(at_hash).map_err(query_failed)?;
+        let username_count = api.username_count(at_hash).map_err(query_failed)?;
+
+        Ok(ServiceInfo {
+            spec_version: version.spec_version,
+            max_username_length,
+            username_count,
+        })
+    }
+
+    fn get_config(&self, at: Option<<Block as BlockT>::Hash>) -> RpcResult<UsernameConfig> {
+        let at_hash = self.at_or_best(at);
+
+        self.client
+            .runtime_api()
+            .username_config(at_hash)
+            .map(|config| UsernameConfig {
+                max_length: config.max_length,
+                min_length: config.min_length,
+                allowed_chars: String::from_utf8_lossy(&config.allowed_chars).to_string(),
+                change_cooldown: config.change_cooldown,
+            })
+            .map_err(|e| UsernameRpcError::query_failed("username config", e).into())
+    }
+
+    fn is_username_available(
+        &self,
+        username: String,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> RpcResult<bool> {
+        let at_hash = self.at_or_best(at);
+
+        let api = self.client.runtime_api();
+
+        let query_failed =
+            |e| ErrorObjectOwned::from(UsernameRpcError::query_failed("username availability", e));
+
+        let (min, max) = api.username_length_bounds(at_hash).map_err(query_failed)?;
+        let len = username.len() as u32;
+        if len > max {
+            return Err(UsernameRpcError::InvalidUsername(format!(
+                "too long: at most {} bytes",
+                max
+            ))
+            .into());
+        }
+        if len < min {
+            return Err(UsernameRpcError::InvalidUsername(format!(
+                "too short: at least {} bytes",
+                min
+            ))
+            .into());
+        }
+        let username = username.into_bytes();
+        if !api
+            .is_valid_username(at_hash, username.clone())
+            .map_err(query_failed)?
+        {
+            return Err(UsernameRpcError::InvalidUsername(
+                "contains characters this chain does not allow".into(),
+            )
+            .into());
+        }
+
+        api.is_username_available(at_hash, username)
+            .map_err(query_failed)
+    }
+
+    fn get_username_secure(
+        &self,
+        eth_address: String,
         signature: String,
         message: String,
         at: Option<<Block as BlockT>::Hash>,
     ) -> RpcResult<Option<String>> {
-        let sig_bytes = hex::decode(signature.trim_start_matches("0x"))
-            .map_err(|_| ErrorObjectOwned::owned(2, "Invalid signature format", None::<()>))?;
+        let eth_address = parse_eth_address(&eth_address)?;
+        // Bound the message before anything hashes it.
+        let nonce = check_secure_message(&message, &eth_address)?;
+        let sig_bytes = decode_signature(&signature)?;
 
-        if sig_bytes.len() != 65 {
-            return Err(ErrorObjectOwned::owned(
-                2,
-                "Signature must be 65 bytes",
-                None::<()>,
-            ));
+        // The nonce is only read here, never consumed: a signature stays valid until the
+        // address's next on-chain action bumps it.
+        let expected_nonce = self.get_nonce(eth_address, at)?;
+        if nonce != expected_nonce {
+            return Err(UsernameRpcError::InvalidNonce {
+                expected: expected_nonce,
+            }
+            .into());
         }
 
-        let message_bytes = message.as_bytes();
+        verify_ethereum_signature(&eth_address, message.as_bytes(), &sig_bytes)?;
+
+        self.get_username(eth_address, at)
+    }
 
-        if !verify_ethereum_signature(&eth_address, message_bytes, &sig_bytes) {
-            return Err(ErrorObjectOwned::owned(3, "Invalid signature", None::<()>));
+    fn verify_signature(
+        &self,
+        eth_address: String,
+        message: String,
+        signature: String,
+    ) -> RpcResult<SignatureCheck> {
+        Ok(check_signature(&eth_address, &message, &signature))
+    }
+
+    async fn subscribe_changes(&self, pending: PendingSubscriptionSink) -> SubscriptionResult {
+        let mut imports = self.client.import_notification_stream();
+        let sink = pending.accept().await?;
+
+        loop {
+            // Stop as soon as the client goes away rather than on the next failed send.
+            let closed = sink.closed();
+            futures::pin_mut!(closed);
+            let notification = match future::select(closed, imports.next()).await {
+                Either::Right((Some(notification), _)) => notification,
+                Either::Left(_) | Either::Right((None, _)) => break,
+            };
+
+            if !notification.is_new_best {
+                continue;
+            }
+
+            let changes = match self
+                .client
+                .runtime_api()
+                .username_changes(notification.hash)
+            {
+                Ok(changes) => changes,
+                Err(e) => {
+                    log::warn!(
+                        target: LOG_TARGET,
+                        "Unable to read username changes at {:?}: {:?}",
+                        notification.hash,
+                        e
+                    );
+                    continue;
+                }
+            };
+
+            for (eth_address, username) in changes {
+                let change = UsernameChange {
+                    eth_address,
+                    kind: if username.is_some() {
+                        ChangeKind::Set
+                    } else {
+                        ChangeKind::Cleared
+                    },
+                    username: username.map(|bytes| String::from_utf8_lossy(&bytes).to_string()),
+                };
+                if sink
+                    .send(SubscriptionMessage::from_json(&change)?)
+                    .await
+                    .is_err()
+                {
+                    return Ok(());
+                }
+            }
         }
 
-        self.get_username(eth_address, at)
+        Ok(())
     }
 }
 
-fn verify_ethereum_signature(eth_address: &H160, message: &[u8], signature: &[u8]) -> bool {
-    log::info!("=== Debug Signature Verification ===");
-    log::info!("Expected address: {:?}", eth_address);
-    log::info!("Message: {:?}", String::from_utf8_lossy(message));
-    log::info!("Signature length: {}", signature.len());
+/// Decode a `0x`-prefixed (or bare) hex signature of exactly [`SIGNATURE_LEN`] bytes.
+pub(super) fn decode_signature(signature: &str) -> Result<Vec<u8>, UsernameRpcError> {
+    let bytes = hex::decode(signature.trim_start_matches("0x"))
+        .map_err(|e| UsernameRpcError::InvalidSignatureFormat(e.to_string()))?;
+    if bytes.len() != SIGNATURE_LEN {
+        return Err(UsernameRpcError::SignatureWrongLength(bytes.len()));
+    }
+    Ok(bytes)
+}
 
-    // Ethereum signed message format
-    let prefix = format!("\x19Ethereum Signed Message:\n{}", message.len());
-    let mut eth_message = prefix.as_bytes().to_vec();
-    eth_message.extend_from_slice(message);
+/// Parse a `0x`-prefixed (or bare) hex address of exactly 20 bytes.
+///
+/// Addresses are taken as strings rather than [`H160`] so a malformed one gets a specific
+/// error instead of jsonrpsee's generic "invalid params".
+fn parse_eth_address(address: &str) -> Result<H160, UsernameRpcError> {
+    let bytes = hex::decode(address.trim_start_matches("0x"))
+        .map_err(|e| UsernameRpcError::InvalidAddressFormat(e.to_string()))?;
+    if bytes.len() != H160::len_bytes() {
+        return Err(UsernameRpcError::AddressWrongLength(bytes.len()));
+    }
+    Ok(H160::from_slice(&bytes))
+}
+
+/// Check the length and format of a `username_get_secure` message and return its nonce.
+fn check_secure_message(message: &str, eth_address: &H160) -> Result<u64, UsernameRpcError> {
+    if message.len() > MAX_SECURE_MESSAGE_LENGTH {
+        return Err(UsernameRpcError::MessageTooLong(message.len()));
+    }
+    parse_secure_message(message, eth_address).ok_or_else(|| {
+        UsernameRpcError::InvalidMessageFormat {
+            expected: format!("get_username:{:#x}:{{nonce}}", eth_address),
+        }
+    })
+}
+
+/// Parse a `get_username:{eth_address}:{nonce}` message and return its nonce.
+///
+/// The address must match `eth_address` (hex is compared case-insensitively) and the nonce must
+/// be a plain decimal number.
+fn parse_secure_message(message: &str, eth_address: &H160) -> Option<u64> {
+    let (address, nonce) = message.strip_prefix("get_username:")?.split_once(':')?;
+    if !address.eq_ignore_ascii_case(&format!("{:#x}", eth_address)) {
+        return None;
+    }
+    if nonce.is_empty() || !nonce.bytes().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    nonce.parse().ok()
+}
+
+/// Recover the signer of `message` and compare it with `eth_address`, for
+/// `username_verify_signature`.
+///
+/// Every input is taken as the client sent it, so a bad address, bad hex or a message over
+/// [`MAX_SECURE_MESSAGE_LENGTH`] makes the check fail instead of erroring.
+fn check_signature(eth_address: &str, message: &str, signature: &str) -> SignatureCheck {
+    let recovered = decode_signature(signature)
+        .ok()
+        .filter(|_| message.len() <= MAX_SECURE_MESSAGE_LENGTH)
+        .and_then(|signature| recover_eth_address(message.as_bytes(), &signature));
+    let expected = parse_eth_address(eth_address).ok();
+    SignatureCheck {
+        valid: recovered.is_some() && recovered == expected,
+        recovered,
+    }
+}
+
+/// `(address, username)` pairs from the runtime as [`UsernameEntry`]s.
+fn username_entries(entries: Vec<(H160, Vec<u8>)>) -> Vec<UsernameEntry> {
+    entries
+        .into_iter()
+        .map(|(eth_address, username)| UsernameEntry {
+            eth_address,
+            username: String::from_utf8_lossy(&username).to_string(),
+        })
+        .collect()
+}
+
+/// Normalize a username the way the pallet keys its reverse index (ASCII-lowercased).
+///
+/// The character rules are up to the runtime, so names are not checked here; a name the
+/// runtime rejects simply has no owner.
+fn normalize_username(username: &str) -> Vec<u8> {
+    username.as_bytes().to_ascii_lowercase()
+}
+
+/// Checks that `signature` over `message` recovers to `eth_address`.
+///
+/// Request contents are only logged at `trace` level under [`LOG_TARGET`], so they stay out of
+/// production logs unless the node runs with `-l username-rpc=trace`.
+fn verify_ethereum_signature(
+    eth_address: &H160,
+    message: &[u8],
+    signature: &[u8],
+) -> Result<(), UsernameRpcError> {
+    log::trace!(
+        target: LOG_TARGET,
+        "Verifying signature for {:?}: message {:?}, hash 0x{}, {} signature bytes",
+        eth_address,
+        String::from_utf8_lossy(message),
+        hex::encode(eth_message_hash(message)),
+        signature.len(),
+    );
+
+    match verify_eth_signature(eth_address, message, signature) {
+        Ok(()) => {
+            log::trace!(target: LOG_TARGET, "Recovered {:?}, match: true", eth_address);
+            Ok(())
+        }
+        Err(EthSignatureError::AddressMismatch { recovered }) => {
+            log::trace!(target: LOG_TARGET, "Recovered {:?}, match: false", recovered);
+            log::info!(target: LOG_TARGET, "Signature does not match {:?}", eth_address);
+            Err(UsernameRpcError::SignatureAddressMismatch { recovered })
+        }
+        Err(EthSignatureError::RecoveryFailed) => {
+            log::info!(target: LOG_TARGET, "Signature recovery failed for {:?}", eth_address);
+            Err(UsernameRpcError::SignatureRecoveryFailed)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{benchmarking::create_benchmark_extrinsic, service::FullClient};
+    use sc_block_builder::BlockBuilderBuilder;
+    use sc_consensus::{
+        BlockImport, BlockImportParams, ForkChoiceStrategy, StateAction, StorageChanges,
+    };
+    use sc_executor::WasmExecutor;
+    use sc_service::GenesisBlockBuilder;
+    use solochain_template_runtime::{self as runtime, opaque::Block};
+    use sp_consensus::BlockOrigin;
+    use sp_consensus_aura::{Slot, AURA_ENGINE_ID};
+    use sp_core::{testing::TaskExecutor, Encode};
+    use sp_inherents::{InherentData, InherentDataProvider};
+    use sp_keyring::Sr25519Keyring;
+    use sp_runtime::{traits::Header as _, Digest, DigestItem};
+    use sp_timestamp::Timestamp;
+    use username_primitives::set_username_message;
+
+    type Hash = <Block as BlockT>::Hash;
+
+    const ADDRESS: H160 = H160::repeat_byte(0x11);
+
+    #[test]
+    fn secure_message_returns_nonce() {
+        let message = format!("get_username:{:#x}:7", ADDRESS);
+        assert_eq!(check_secure_message(&message, &ADDRESS).unwrap(), 7);
+    }
+
+    #[test]
+    fn oversized_secure_message_is_rejected() {
+        let message = format!("get_username:{:#x}:{}", ADDRESS, "1".repeat(300));
+        let error = check_secure_message(&message, &ADDRESS).unwrap_err();
+        assert!(matches!(error, UsernameRpcError::MessageTooLong(len) if len == message.len()));
+        assert_eq!(error.code(), ERROR_CODE_BASE + 10);
+    }
+
+    #[test]
+    fn secure_message_with_wrong_prefix_is_rejected() {
+        let message = format!("set_username:{:#x}:7", ADDRESS);
+        let error = check_secure_message(&message, &ADDRESS).unwrap_err();
+        assert!(matches!(
+            error,
+            UsernameRpcError::InvalidMessageFormat { .. }
+        ));
+        assert_eq!(error.code(), ERROR_CODE_BASE + 5);
+    }
 
-    let hash = keccak_256(&eth_message);
-    log::info!("Message hash: 0x{}", hex::encode(&hash));
+    fn signed(message: &str) -> (H160, String) {
+        use sp_core::{ecdsa, Pair};
 
-    // Convert recovery ID
-    let mut sig_array = [0u8; 65];
-    sig_array.copy_from_slice(&signature[..65]);
-    log::info!("Recovery ID (before): {}", sig_array[64]);
+        let pair = ecdsa::Pair::from_seed(&[7; 32]);
+        let mut signature = pair.sign_prehashed(&eth_message_hash(message.as_bytes())).0;
+        signature[SIGNATURE_LEN - 1] += username_primitives::ETH_V_OFFSET;
+        let address = recover_eth_address(message.as_bytes(), &signature).unwrap();
+        (address, format!("0x{}", hex::encode(signature)))
+    }
+
+    #[test]
+    fn check_signature_reports_the_signer() {
+        let (address, signature) = signed("set_username:42:alice:0");
+
+        let check = check_signature(
+            &format!("{:#x}", address),
+            "set_username:42:alice:0",
+            &signature,
+        );
+        assert!(check.valid);
+        assert_eq!(check.recovered, Some(address));
+
+        let check = check_signature(
+            &format!("{:#x}", ADDRESS),
+            "set_username:42:alice:0",
+            &signature,
+        );
+        assert!(!check.valid);
+        assert_eq!(check.recovered, Some(address));
+    }
+
+    #[test]
+    fn check_signature_never_errors_on_bad_input() {
+        let (address, signature) = signed("set_username:42:alice:0");
+        let address = format!("{:#x}", address);
+
+        let check = check_signature(&address, "set_username:42:alice:0", "0xnot-hex");
+        assert!(!check.valid);
+        assert_eq!(check.recovered, None);
+
+        let check = check_signature(&address, "set_username:42:alice:0", &signature[..64]);
+        assert!(!check.valid);
+        assert_eq!(check.recovered, None);
+
+        let check = check_signature("0x1234", "set_username:42:alice:0", &signature);
+        assert!(!check.valid);
+        assert!(check.recovered.is_some());
+    }
+
+    #[test]
+    fn signature_errors_tell_recovery_from_mismatch() {
+        let message = "get_username:0x1111111111111111111111111111111111111111:0";
+        let (address, signature) = signed(message);
+        let signature = decode_signature(&signature).unwrap();
+
+        assert!(verify_ethereum_signature(&address, message.as_bytes(), &signature).is_ok());
+
+        let error =
+            verify_ethereum_signature(&ADDRESS, message.as_bytes(), &signature).unwrap_err();
+        assert!(matches!(
+            error,
+            UsernameRpcError::SignatureAddressMismatch { recovered } if recovered == address
+        ));
+        assert_eq!(error.code(), ERROR_CODE_BASE + 3);
+
+        let mut bad_v = signature;
+        bad_v[SIGNATURE_LEN - 1] = 4;
+        let error = verify_ethereum_signature(&address, message.as_bytes(), &bad_v).unwrap_err();
+        assert!(matches!(error, UsernameRpcError::SignatureRecoveryFailed));
+        assert_eq!(error.code(), ERROR_CODE_BASE + 13);
+    }
+
+    #[test]
+    fn scan_errors_map_to_rpc_codes() {
+        let error = UsernameRpcError::from(ScanError::LimitTooHigh {
+            requested: 1_001,
+            max: 1_000,
+        });
+        assert!(matches!(
+            error,
+            UsernameRpcError::LimitExceeded {
+                requested: 1_001,
+                max: 1_000
+            }
+        ));
+        assert_eq!(error.code(), ERROR_CODE_BASE + 4);
+
+        let error = UsernameRpcError::from(ScanError::InvalidCursor);
+        assert_eq!(error.code(), ERROR_CODE_BASE + 14);
 
-    if sig_array[64] >= 27 {
-        sig_array[64] -= 27;
+        let error = UsernameRpcError::from(ScanError::ZeroLimit);
+        assert_eq!(error.code(), ERROR_CODE_BASE + 15);
     }
-    log::info!("Recovery ID (after): {}", sig_array[64]);
 
-    // Try to recover public key
-    match sp_io::crypto::secp256k1_ecdsa_recover(&sig_array, &hash) {
-        Ok(pubkey) => {
-            log::info!("Public key recovered: 0x{}", hex::encode(&pubkey));
+    #[test]
+    fn signature_that_is_not_hex_is_rejected() {
+        let error = decode_signature("0x12zz").unwrap_err();
+        assert!(matches!(
+            &error,
+            UsernameRpcError::InvalidSignatureFormat(reason) if reason.contains("position 2")
+        ));
+        assert_eq!(error.code(), ERROR_CODE_BASE + 2);
 
-            let recovered_addr_hash = keccak_256(&pubkey[1..]);
-            let recovered_eth_addr = H160::from_slice(&recovered_addr_hash[12..32]);
+        let error = decode_signature("0x123").unwrap_err();
+        assert!(matches!(error, UsernameRpcError::InvalidSignatureFormat(_)));
+    }
+
+    #[test]
+    fn signature_of_wrong_length_is_rejected() {
+        let error = decode_signature(&format!("0x{}", "ab".repeat(64))).unwrap_err();
+        assert!(matches!(error, UsernameRpcError::SignatureWrongLength(64)));
+        assert_eq!(error.code(), ERROR_CODE_BASE + 8);
+        let message = ErrorObjectOwned::from(error).message().to_string();
+        assert!(message.contains("got 64, expected 65"), "{}", message);
 
-            log::info!("Recovered address: {:?}", recovered_eth_addr);
-            log::info!("Match: {}", &recovered_eth_addr == eth_address);
+        assert_eq!(
+            decode_signature(&"ab".repeat(SIGNATURE_LEN)).unwrap().len(),
+            SIGNATURE_LEN
+        );
+    }
+
+    #[test]
+    fn address_that_is_not_hex_is_rejected() {
+        let error = parse_eth_address(&format!("0x{}", "g".repeat(40))).unwrap_err();
+        assert!(matches!(
+            &error,
+            UsernameRpcError::InvalidAddressFormat(reason) if reason.contains("position 0")
+        ));
+        assert_eq!(error.code(), ERROR_CODE_BASE + 11);
+    }
 
-            &recovered_eth_addr == eth_address
+    #[test]
+    fn address_of_wrong_length_is_rejected() {
+        let error = parse_eth_address("0x1111").unwrap_err();
+        assert!(matches!(error, UsernameRpcError::AddressWrongLength(2)));
+        assert_eq!(error.code(), ERROR_CODE_BASE + 12);
+        let message = ErrorObjectOwned::from(error).message().to_string();
+        assert!(message.contains("got 2, expected 20"), "{}", message);
+
+        assert_eq!(
+            parse_eth_address(&format!("{:#x}", ADDRESS)).unwrap(),
+            ADDRESS
+        );
+        assert_eq!(parse_eth_address(&"11".repeat(20)).unwrap(), ADDRESS);
+    }
+
+    /// A client over a fresh in-memory database, started from the development chain spec.
+    fn dev_client() -> Arc<FullClient> {
+        let chain_spec = crate::chain_spec::development_chain_spec().unwrap();
+        let backend = Arc::new(sc_client_db::Backend::new_test(1_000, 0));
+        let executor = WasmExecutor::<sp_io::SubstrateHostFunctions>::builder().build();
+        let genesis_block_builder =
+            GenesisBlockBuilder::new(&chain_spec, true, backend.clone(), executor.clone()).unwrap();
+        let client = sc_service::new_client(
+            backend,
+            executor,
+            genesis_block_builder,
+            None,
+            None,
+            Box::new(TaskExecutor::new()),
+            None,
+            None,
+            Default::default(),
+        )
+        .unwrap();
+        Arc::new(client)
+    }
+
+    /// Build a block with `extrinsics` on top of the best block and import it as the new best,
+    /// returning its hash.
+    fn import_block(client: &FullClient, extrinsics: Vec<runtime::UncheckedExtrinsic>) -> Hash {
+        let info = client.info();
+        let slot = Slot::from(info.best_number as u64 + 1);
+        let mut inherent_data = InherentData::new();
+        futures::executor::block_on(
+            sp_timestamp::InherentDataProvider::new(Timestamp::new(*slot * runtime::SLOT_DURATION))
+                .provide_inherent_data(&mut inherent_data),
+        )
+        .unwrap();
+        let digest = Digest {
+            logs: vec![DigestItem::PreRuntime(AURA_ENGINE_ID, slot.encode())],
+        };
+
+        let mut builder = BlockBuilderBuilder::new(client)
+            .on_parent_block(info.best_hash)
+            .with_parent_block_number(info.best_number)
+            .with_inherent_digests(digest)
+            .build()
+            .unwrap();
+        for inherent in builder.create_inherents(inherent_data).unwrap() {
+            builder.push(inherent).unwrap();
         }
-        Err(_e) => {
-            log::error!("Recovery failed");
-            false
+        for extrinsic in extrinsics {
+            builder.push(extrinsic.into()).unwrap();
         }
+        let built = builder.build().unwrap();
+
+        let (header, body) = built.block.deconstruct();
+        let hash = header.hash();
+        let mut params = BlockImportParams::new(BlockOrigin::Own, header);
+        params.body = Some(body);
+        params.state_action =
+            StateAction::ApplyChanges(StorageChanges::Changes(built.storage_changes));
+        params.fork_choice = Some(ForkChoiceStrategy::LongestChain);
+        futures::executor::block_on(client.import_block(params)).unwrap();
+        hash
+    }
+
+    #[test]
+    fn nonce_at_an_old_block_is_the_old_nonce() {
+        let client = dev_client();
+        let rpc = UsernameRpc::<_, Block>::new(client.clone());
+
+        let message = set_username_message(42, b"alice", 0);
+        let (eth_address, eth_signature) = signed(&String::from_utf8(message).unwrap());
+        let call = runtime::RuntimeCall::Template(pallet_template::Call::set_username {
+            eth_address,
+            username: b"alice".to_vec(),
+            nonce: 0,
+            eth_signature: decode_signature(&eth_signature).unwrap(),
+        });
+        let registration =
+            create_benchmark_extrinsic(&client, Sr25519Keyring::Alice.pair(), call, 0);
+
+        let before = client.info().best_hash;
+        let registered = import_block(&client, vec![registration]);
+        import_block(&client, Vec::new());
+
+        assert_eq!(rpc.get_nonce(eth_address, Some(before)).unwrap(), 0);
+        assert_eq!(rpc.get_nonce(eth_address, Some(registered)).unwrap(), 1);
+        assert_eq!(rpc.get_username(eth_address, Some(before)).unwrap(), None);
+        assert_eq!(rpc.get_nonce(eth_address, None).unwrap(), 1);
     }
 }
@@ -5,30 +5,364 @@ use super::*;
 #[allow(unused)]
 use crate::Pallet as Template;
 use frame_benchmarking::v2::*;
+use frame_support::traits::Get;
 use frame_system::RawOrigin;
+use sp_core::{crypto::KeyTypeId, ecdsa};
+use username_primitives::{
+	eth_address, eth_message_hash, set_username_message, ETH_V_OFFSET, SIGNATURE_LEN,
+};
+
+/// Key type used to generate throwaway ECDSA keys in the benchmark keystore.
+const KEY_TYPE: KeyTypeId = KeyTypeId(*b"user");
+
+/// Generate a fresh ECDSA key and return it with the Ethereum address the pallet recovers for it.
+fn eth_account() -> (ecdsa::Public, H160) {
+	let public = sp_io::crypto::ecdsa_generate(KEY_TYPE, None);
+	let hash = [0u8; 32];
+	let signature = sp_io::crypto::ecdsa_sign_prehashed(KEY_TYPE, &public, &hash)
+		.expect("key was just generated; qed");
+	let pubkey = sp_io::crypto::secp256k1_ecdsa_recover(&signature.0, &hash)
+		.expect("signature was just produced; qed");
+	(public, eth_address(&pubkey))
+}
+
+/// Sign `message` with `public` using the Ethereum `personal_sign` format.
+fn eth_sign(public: &ecdsa::Public, message: &[u8]) -> Vec<u8> {
+	let hash = eth_message_hash(message);
+	let signature = sp_io::crypto::ecdsa_sign_prehashed(KEY_TYPE, public, &hash)
+		.expect("key is in the keystore; qed");
+	let mut signature = signature.0.to_vec();
+	signature[SIGNATURE_LEN - 1] += ETH_V_OFFSET;
+	signature
+}
+
+/// A vector of `S` bytes, the longest `S` allows.
+fn full_bounded<S: Get<u32>>() -> BoundedVec<u8, S> {
+	let mut bytes = Vec::new();
+	bytes.resize(S::get() as usize, b'a');
+	bytes.try_into().expect("exactly the bound; qed")
+}
+
+/// Register a maximum-length username for a fresh address, give it a full profile and link `a`
+/// aliases to it, which is the worst case for removing or moving the name. Returns the paying
+/// account, the key and the address.
+fn registered_account<T: Config>(
+	a: u32,
+) -> Result<(T::AccountId, ecdsa::Public, H160), BenchmarkError> {
+	let caller: T::AccountId = whitelisted_caller();
+	let deposit = T::UsernameDeposit::get();
+	T::Currency::make_free_balance_be(&caller, deposit + deposit + T::Currency::minimum_balance());
+	let (public, eth_address) = eth_account();
+	// Spell the address out in the letters `a..=p`, so several accounts get distinct names.
+	let mut username: Vec<u8> = eth_address
+		.as_bytes()
+		.iter()
+		.flat_map(|byte| [b'a' + (byte >> 4), b'a' + (byte & 0x0f)])
+		.collect();
+	username.resize(T::MaxUsernameLength::get() as usize, b'a');
+	let signature = eth_sign(&public, &set_username_message(T::ChainId::get(), &username, 0));
+	Pallet::<T>::set_username(
+		RawOrigin::Signed(caller.clone()).into(),
+		eth_address,
+		username,
+		0,
+		signature,
+	)?;
+
+	Profiles::<T>::insert(
+		eth_address,
+		ProfileMetadata {
+			display_name: full_bounded(),
+			avatar_uri: full_bounded(),
+			bio: full_bounded(),
+		},
+	);
+	for i in 0..a {
+		let alias = H160::from_slice(&sp_io::hashing::blake2_256(&(eth_address, i).encode())[..20]);
+		Aliases::<T>::try_mutate(eth_address, |aliases| aliases.try_push(alias))
+			.map_err(|_| BenchmarkError::Stop("a is at most MaxAliases"))?;
+		AliasOf::<T>::insert(alias, eth_address);
+	}
+	Ok((caller, public, eth_address))
+}
 
 #[benchmarks]
 mod benchmarks {
 	use super::*;
 
 	#[benchmark]
-	fn do_something() {
-		let value = 100u32;
+	fn set_username(l: Linear<{ T::MinUsernameLength::get() }, { T::MaxUsernameLength::get() }>) {
 		let caller: T::AccountId = whitelisted_caller();
+		let deposit = T::UsernameDeposit::get();
+		T::Currency::make_free_balance_be(
+			&caller,
+			deposit + deposit + T::Currency::minimum_balance(),
+		);
+		let (public, eth_address) = eth_account();
+		let mut username = Vec::new();
+		username.resize(l as usize, b'a');
+		let message = set_username_message(T::ChainId::get(), &username, 0);
+		let signature = eth_sign(&public, &message);
+
+		#[extrinsic_call]
+		set_username(RawOrigin::Signed(caller), eth_address, username.clone(), 0, signature);
+
+		assert_eq!(
+			Usernames::<T>::get(eth_address).map(|info| info.username.into_inner()),
+			Some(username)
+		);
+		assert_eq!(Nonces::<T>::get(eth_address), 1);
+		assert_eq!(T::Currency::reserved_balance(&caller), deposit);
+	}
+
+	#[benchmark]
+	fn clear_username(a: Linear<0, { T::MaxAliases::get() }>) -> Result<(), BenchmarkError> {
+		let (caller, public, eth_address) = registered_account::<T>(a)?;
+		let signature = eth_sign(&public, &Pallet::<T>::clear_message(&eth_address, 1));
+
+		#[extrinsic_call]
+		clear_username(RawOrigin::Signed(caller.clone()), eth_address, 1, signature);
+
+		assert!(!Usernames::<T>::contains_key(eth_address));
+		assert!(Aliases::<T>::get(eth_address).is_empty());
+		assert_eq!(Nonces::<T>::get(eth_address), 2);
+		assert_eq!(T::Currency::reserved_balance(&caller), 0u32.into());
+		Ok(())
+	}
+
+	#[benchmark]
+	fn transfer_username(a: Linear<0, { T::MaxAliases::get() }>) -> Result<(), BenchmarkError> {
+		let (caller, public, from) = registered_account::<T>(a)?;
+		let (_, to) = eth_account();
+		let signature = eth_sign(&public, &Pallet::<T>::transfer_message(&from, &to, 1));
+
+		#[extrinsic_call]
+		transfer_username(RawOrigin::Signed(caller), from, to, 1, signature);
+
+		assert!(!Usernames::<T>::contains_key(from));
+		assert!(Usernames::<T>::contains_key(to));
+		assert!(Profiles::<T>::contains_key(to));
+		assert!(Aliases::<T>::get(from).is_empty());
+		assert_eq!(Nonces::<T>::get(from), 2);
+		Ok(())
+	}
+
+	#[benchmark]
+	fn force_clear_username(a: Linear<0, { T::MaxAliases::get() }>) -> Result<(), BenchmarkError> {
+		let (caller, _, eth_address) = registered_account::<T>(a)?;
+
+		#[extrinsic_call]
+		force_clear_username(RawOrigin::Root, eth_address);
+
+		assert!(!Usernames::<T>::contains_key(eth_address));
+		assert!(Aliases::<T>::get(eth_address).is_empty());
+		assert_eq!(T::Currency::reserved_balance(&caller), 0u32.into());
+		Ok(())
+	}
+
+	/// `a` aliases are already linked, so the new one is pushed onto the longest list it fits.
+	#[benchmark]
+	fn link_alias(
+		a: Linear<0, { T::MaxAliases::get().saturating_sub(1) }>,
+	) -> Result<(), BenchmarkError> {
+		let (caller, public, primary) = registered_account::<T>(a)?;
+		let (alias_public, alias) = eth_account();
+		let message = Pallet::<T>::alias_message("link_alias", &primary, &alias, 1);
+		let primary_signature = eth_sign(&public, &message);
+		let alias_signature = eth_sign(&alias_public, &message);
+
+		#[extrinsic_call]
+		link_alias(
+			RawOrigin::Signed(caller),
+			primary,
+			alias,
+			1,
+			primary_signature,
+			alias_signature,
+		);
+
+		assert_eq!(AliasOf::<T>::get(alias), Some(primary));
+		assert_eq!(Aliases::<T>::get(primary).len() as u32, a + 1);
+		Ok(())
+	}
+
+	/// The alias signs, so the call first fails to match the signature against the primary and
+	/// recovers it a second time for the alias.
+	#[benchmark]
+	fn unlink_alias(a: Linear<1, { T::MaxAliases::get() }>) -> Result<(), BenchmarkError> {
+		let (caller, _, primary) = registered_account::<T>(a - 1)?;
+		let (alias_public, alias) = eth_account();
+		Aliases::<T>::try_mutate(primary, |aliases| aliases.try_push(alias))
+			.map_err(|_| BenchmarkError::Stop("a is at most MaxAliases"))?;
+		AliasOf::<T>::insert(alias, primary);
+		let message = Pallet::<T>::alias_message("unlink_alias", &primary, &alias, 1);
+		let signature = eth_sign(&alias_public, &message);
+
+		#[extrinsic_call]
+		unlink_alias(RawOrigin::Signed(caller), primary, alias, 1, signature);
+
+		assert!(!AliasOf::<T>::contains_key(alias));
+		assert_eq!(Aliases::<T>::get(primary).len() as u32, a - 1);
+		Ok(())
+	}
+
+	/// Every field of the metadata is as long as it may be.
+	#[benchmark]
+	fn set_profile() -> Result<(), BenchmarkError> {
+		let (caller, public, eth_address) = registered_account::<T>(0)?;
+		let metadata = ProfileMetadata {
+			display_name: full_bounded(),
+			avatar_uri: full_bounded(),
+			bio: full_bounded(),
+		};
+		let signature =
+			eth_sign(&public, &Pallet::<T>::set_profile_message(&eth_address, &metadata, 1));
+
+		#[extrinsic_call]
+		set_profile(RawOrigin::Signed(caller), eth_address, metadata.clone(), 1, signature);
+
+		assert_eq!(Profiles::<T>::get(eth_address), Some(metadata));
+		assert_eq!(Nonces::<T>::get(eth_address), 2);
+		Ok(())
+	}
+
+	#[benchmark]
+	fn reserve_username() {
+		let username = full_bounded::<T::MaxUsernameLength>();
+
 		#[extrinsic_call]
-		do_something(RawOrigin::Signed(caller), value);
+		reserve_username(RawOrigin::Root, username.to_vec());
 
-		assert_eq!(Something::<T>::get(), Some(value));
+		assert!(ReservedUsernames::<T>::contains_key(Pallet::<T>::owner_key(&username)));
 	}
 
 	#[benchmark]
-	fn cause_error() {
-		Something::<T>::put(100u32);
+	fn unreserve_username() {
+		let username = full_bounded::<T::MaxUsernameLength>();
+		ReservedUsernames::<T>::insert(Pallet::<T>::owner_key(&username), ());
+
+		#[extrinsic_call]
+		unreserve_username(RawOrigin::Root, username.to_vec());
+
+		assert!(!ReservedUsernames::<T>::contains_key(Pallet::<T>::owner_key(&username)));
+	}
+
+	#[benchmark]
+	fn set_paused() {
+		#[extrinsic_call]
+		set_paused(RawOrigin::Root, true);
+
+		assert!(Paused::<T>::get());
+	}
+
+	#[benchmark]
+	fn add_to_allowlist() {
+		let (_, eth_address) = eth_account();
+
+		#[extrinsic_call]
+		add_to_allowlist(RawOrigin::Root, eth_address);
+
+		assert!(Allowlist::<T>::contains_key(eth_address));
+	}
+
+	#[benchmark]
+	fn remove_from_allowlist() {
+		let (_, eth_address) = eth_account();
+		Allowlist::<T>::insert(eth_address, ());
+
+		#[extrinsic_call]
+		remove_from_allowlist(RawOrigin::Root, eth_address);
+
+		assert!(!Allowlist::<T>::contains_key(eth_address));
+	}
+
+	#[benchmark]
+	fn reset_nonce() {
+		let (_, eth_address) = eth_account();
+		Nonces::<T>::insert(eth_address, 5);
+
+		#[extrinsic_call]
+		reset_nonce(RawOrigin::Root, eth_address, 0);
+
+		assert_eq!(Nonces::<T>::get(eth_address), 0);
+	}
+
+	#[benchmark]
+	fn block_address() {
+		let (_, eth_address) = eth_account();
+
+		#[extrinsic_call]
+		block_address(RawOrigin::Root, eth_address);
+
+		assert!(Blocklist::<T>::contains_key(eth_address));
+	}
+
+	#[benchmark]
+	fn unblock_address() {
+		let (_, eth_address) = eth_account();
+		Blocklist::<T>::insert(eth_address, ());
+
+		#[extrinsic_call]
+		unblock_address(RawOrigin::Root, eth_address);
+
+		assert!(!Blocklist::<T>::contains_key(eth_address));
+	}
+
+	/// The account already holds a name, so the call also releases it.
+	#[benchmark]
+	fn set_substrate_username(
+		l: Linear<{ T::MinUsernameLength::get() }, { T::MaxUsernameLength::get() }>,
+	) {
 		let caller: T::AccountId = whitelisted_caller();
+		let previous = full_bounded::<T::MaxUsernameLength>();
+		SubstrateUsernameOwners::<T>::insert(Pallet::<T>::owner_key(&previous), caller.clone());
+		SubstrateUsernames::<T>::insert(&caller, previous.clone());
+		let mut username = Vec::new();
+		username.resize(l as usize, b'b');
+
 		#[extrinsic_call]
-		cause_error(RawOrigin::Signed(caller));
+		set_substrate_username(RawOrigin::Signed(caller.clone()), username.clone());
+
+		assert_eq!(
+			SubstrateUsernames::<T>::get(&caller).map(|username| username.into_inner()),
+			Some(username)
+		);
+		assert!(!SubstrateUsernameOwners::<T>::contains_key(Pallet::<T>::owner_key(&previous)));
+	}
+
+	/// Every username is registered with the maximum number of aliases, so each one removed
+	/// costs as much as it can.
+	#[benchmark]
+	fn force_expire_all(n: Linear<0, 100>) -> Result<(), BenchmarkError> {
+		for _ in 0..n {
+			registered_account::<T>(T::MaxAliases::get())?;
+			// The registrations are set up in one block; let them past the per-block cap.
+			RegistrationsThisBlock::<T>::kill();
+		}
+
+		#[extrinsic_call]
+		force_expire_all(RawOrigin::Root, n);
+
+		assert_eq!(Usernames::<T>::iter().count(), 0);
+		assert_eq!(UsernameCount::<T>::get(), 0);
+		Ok(())
+	}
+
+	/// The removal `on_initialize` makes for each expired username, which is charged on top of
+	/// the expiry queue bookkeeping.
+	#[benchmark]
+	fn expire_username(a: Linear<0, { T::MaxAliases::get() }>) -> Result<(), BenchmarkError> {
+		let (caller, _, eth_address) = registered_account::<T>(a)?;
+
+		#[block]
+		{
+			Pallet::<T>::remove_username(&eth_address);
+		}
 
-		assert_eq!(Something::<T>::get(), Some(101u32));
+		assert!(!Usernames::<T>::contains_key(eth_address));
+		assert!(Aliases::<T>::get(eth_address).is_empty());
+		assert_eq!(T::Currency::reserved_balance(&caller), 0u32.into());
+		Ok(())
 	}
 
 	impl_benchmark_test_suite!(Template, crate::mock::new_test_ext(), crate::mock::Test);
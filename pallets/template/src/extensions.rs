@@ -0,0 +1,134 @@
+//! Transaction extension that checks username nonces in the transaction pool.
+
+use crate::{Call, Config, Nonces};
+use codec::{Decode, DecodeWithMemTracking, Encode};
+use core::marker::PhantomData;
+use frame_support::sp_runtime::{
+    impl_tx_ext_default,
+    traits::{DispatchInfoOf, Dispatchable, Implication, TransactionExtension, ValidateResult},
+    transaction_validity::{InvalidTransaction, ValidTransaction},
+};
+use frame_support::{
+    pallet_prelude::{TransactionSource, TypeInfo, Weight},
+    traits::{Get, IsSubType},
+};
+use scale_info::prelude::vec;
+use sp_core::H160;
+
+/// Rejects signed username calls whose nonce is already used before they enter a block.
+///
+/// The pallet's nonces are per `H160` address and travel as call arguments, not in the
+/// transaction envelope like the account nonce checked by `frame_system::CheckNonce`. This
+/// extension therefore decodes the call itself: [`Self::signed_nonce`] lists every dispatchable
+/// that takes an address and nonce, and must be kept in step with the pallet's calls.
+///
+/// A nonce below the address's current one is [`InvalidTransaction::Stale`]. A nonce above it
+/// is kept in the pool's future queue until the calls with the preceding nonces are included,
+/// so several calls for one address are ordered by nonce rather than failing in dispatch. With
+/// [`Config::AllowNonceSkip`] it is valid right away instead.
+#[derive(Encode, Decode, DecodeWithMemTracking, Clone, Eq, PartialEq, TypeInfo)]
+#[scale_info(skip_type_params(T))]
+pub struct CheckUsernameNonce<T>(PhantomData<T>);
+
+impl<T> CheckUsernameNonce<T> {
+    pub fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<T> Default for CheckUsernameNonce<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> core::fmt::Debug for CheckUsernameNonce<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "CheckUsernameNonce")
+    }
+}
+
+impl<T: Config> CheckUsernameNonce<T>
+where
+    T::RuntimeCall: IsSubType<Call<T>>,
+{
+    /// The address and nonce a username call is signed for, or `None` for any other call.
+    pub fn signed_nonce(call: &T::RuntimeCall) -> Option<(H160, u64)> {
+        match call.is_sub_type()? {
+            Call::set_username {
+                eth_address, nonce, ..
+            }
+            | Call::set_username_with_scheme {
+                eth_address, nonce, ..
+            }
+            | Call::clear_username {
+                eth_address, nonce, ..
+            }
+            | Call::set_profile {
+                eth_address, nonce, ..
+            } => Some((*eth_address, *nonce)),
+            Call::link_alias { primary, nonce, .. } | Call::unlink_alias { primary, nonce, .. } => {
+                Some((*primary, *nonce))
+            }
+            Call::transfer_username { from, nonce, .. } => Some((*from, *nonce)),
+            _ => None,
+        }
+    }
+}
+
+/// Pool tag for `nonce` of `eth_address`.
+fn nonce_tag(eth_address: &H160, nonce: u64) -> vec::Vec<u8> {
+    (b"username_nonce", eth_address, nonce).encode()
+}
+
+impl<T: Config + Send + Sync> TransactionExtension<T::RuntimeCall> for CheckUsernameNonce<T>
+where
+    T::RuntimeCall: IsSubType<Call<T>> + Dispatchable,
+{
+    const IDENTIFIER: &'static str = "CheckUsernameNonce";
+    type Implicit = ();
+    type Val = ();
+    type Pre = ();
+
+    fn weight(&self, call: &T::RuntimeCall) -> Weight {
+        match Self::signed_nonce(call) {
+            Some(_) => T::DbWeight::get().reads(1),
+            None => Weight::zero(),
+        }
+    }
+
+    fn validate(
+        &self,
+        origin: <T::RuntimeCall as Dispatchable>::RuntimeOrigin,
+        call: &T::RuntimeCall,
+        _info: &DispatchInfoOf<T::RuntimeCall>,
+        _len: usize,
+        _self_implicit: Self::Implicit,
+        _inherited_implication: &impl Implication,
+        _source: TransactionSource,
+    ) -> ValidateResult<Self::Val, T::RuntimeCall> {
+        let Some((eth_address, nonce)) = Self::signed_nonce(call) else {
+            return Ok((ValidTransaction::default(), (), origin));
+        };
+
+        let expected = Nonces::<T>::get(eth_address);
+        if nonce < expected {
+            return Err(InvalidTransaction::Stale.into());
+        }
+
+        // With nonce skipping, a later nonce is valid on its own and needs no predecessor.
+        let requires = if nonce > expected && !T::AllowNonceSkip::get() {
+            vec![nonce_tag(&eth_address, nonce - 1)]
+        } else {
+            vec![]
+        };
+        let validity = ValidTransaction {
+            requires,
+            provides: vec![nonce_tag(&eth_address, nonce)],
+            ..Default::default()
+        };
+        Ok((validity, (), origin))
+    }
+
+    impl_tx_ext_default!(T::RuntimeCall; prepare);
+}
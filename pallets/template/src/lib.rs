@@ -18,14 +18,189 @@ mod tests;
 // for each dispatchable and generates this pallet's weight.rs file. Learn more about benchmarking here: https://docs.substrate.io/test/benchmark/
 #[cfg(feature = "runtime-benchmarks")]
 mod benchmarking;
+pub mod extensions;
+pub mod migrations;
 pub mod weights;
 pub use weights::*;
 
-use codec::alloc::string::String;
+pub use extensions::CheckUsernameNonce;
+
+use codec::DecodeWithMemTracking;
+#[cfg(any(feature = "try-runtime", test))]
+use frame_support::sp_runtime::TryRuntimeError;
+use frame_support::sp_runtime::{
+    offchain::storage::StorageValueRef,
+    traits::{Saturating, Zero},
+    DispatchError, DispatchResult,
+};
+use frame_support::{
+    dispatch::{DispatchResultWithPostInfo, WithPostDispatchInfo},
+    ensure,
+    pallet_prelude::{ConstU32, Decode, Encode, MaxEncodedLen, RuntimeDebug, TypeInfo, Weight},
+    traits::{Currency, ReservableCurrency},
+    BoundedVec,
+};
+use frame_system::pallet_prelude::BlockNumberFor;
 use scale_info::prelude::format;
-use scale_info::prelude::vec::Vec;
-use sp_core::H160;
-use sp_io::hashing::keccak_256;
+use scale_info::prelude::{vec, vec::Vec};
+use sp_core::{ed25519, sr25519, H160, H256};
+use username_primitives::{
+    clear_username_message, eip712_set_username_hash, eth_message_hash, set_username_message,
+    substrate_key_address, verify_eth_signature, verify_eth_signature_prehashed, EthSignatureError,
+};
+
+/// The balance type of [`Config::Currency`].
+pub type BalanceOf<T> =
+    <<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
+
+/// A registered username together with the block at which it expires, if it does.
+#[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct UsernameInfo<Username, BlockNumber> {
+    pub username: Username,
+    pub expires_at: Option<BlockNumber>,
+}
+
+/// How the owner of an address authorizes a username change.
+///
+/// The Substrate schemes carry the signer's 32-byte public key, because it can't be recovered
+/// from the signature. The address owned by such a key is its
+/// [`substrate_key_address`]: the last 20 bytes of the keccak-256 hash of the key.
+#[derive(
+    Encode,
+    Decode,
+    DecodeWithMemTracking,
+    MaxEncodedLen,
+    TypeInfo,
+    Clone,
+    PartialEq,
+    Eq,
+    RuntimeDebug,
+    Default,
+)]
+pub enum SigScheme {
+    /// Ethereum `personal_sign`, recovered with secp256k1.
+    #[default]
+    EthEcdsa,
+    /// Raw ed25519 signature by the given public key.
+    Ed25519([u8; 32]),
+    /// Raw sr25519 signature by the given public key.
+    Sr25519([u8; 32]),
+    /// Ethereum EIP-712 typed-data signature over
+    /// [`username_primitives::eip712_set_username_hash`], recovered with secp256k1.
+    EthEip712,
+}
+
+/// The character rules a username must satisfy, on top of the length bounds.
+pub trait ValidateUsername {
+    /// Whether `name` may be registered. `name` is never empty.
+    fn is_valid(name: &[u8]) -> bool;
+
+    /// The rules of [`Self::is_valid`] in a sentence, for frontends to show next to the input.
+    fn description() -> &'static str {
+        "runtime-specific rules"
+    }
+}
+
+/// The default [`ValidateUsername`] policy: ASCII alphanumerics and `_`, with at least one
+/// alphanumeric byte and no leading digit.
+pub struct AlphanumericUnderscore;
+
+impl ValidateUsername for AlphanumericUnderscore {
+    fn is_valid(name: &[u8]) -> bool {
+        // Names are meant to read as handles: `___` is not one, and a leading digit makes a
+        // name too easy to confuse with a number or an id.
+        name.iter().all(|&c| c.is_ascii_alphanumeric() || c == b'_')
+            && name.iter().any(|c| c.is_ascii_alphanumeric())
+            && name.first().is_some_and(|c| !c.is_ascii_digit())
+    }
+
+    fn description() -> &'static str {
+        "ASCII letters, digits and `_`, with at least one letter or digit and no leading digit"
+    }
+}
+
+/// Maximum length of [`ProfileMetadata::display_name`], in bytes.
+pub const MAX_DISPLAY_NAME_LENGTH: u32 = 64;
+/// Maximum length of [`ProfileMetadata::avatar_uri`], in bytes.
+pub const MAX_AVATAR_URI_LENGTH: u32 = 256;
+/// Maximum length of [`ProfileMetadata::bio`], in bytes.
+pub const MAX_BIO_LENGTH: u32 = 280;
+
+/// Most storage entries [`Pallet::list_usernames`] or [`Pallet::search_usernames`] visit in one
+/// call, however large the map. A scan that stops here returns a cursor to continue from.
+pub const MAX_RPC_SCAN: u32 = 1_000;
+
+/// Optional profile details an address attaches to its username with `set_profile`.
+///
+/// The fields are free-form bytes; the pallet only bounds their length.
+#[derive(
+    Encode,
+    Decode,
+    DecodeWithMemTracking,
+    MaxEncodedLen,
+    TypeInfo,
+    Clone,
+    PartialEq,
+    Eq,
+    RuntimeDebug,
+    Default,
+)]
+pub struct ProfileMetadata {
+    pub display_name: BoundedVec<u8, ConstU32<MAX_DISPLAY_NAME_LENGTH>>,
+    pub avatar_uri: BoundedVec<u8, ConstU32<MAX_AVATAR_URI_LENGTH>>,
+    pub bio: BoundedVec<u8, ConstU32<MAX_BIO_LENGTH>>,
+}
+
+/// Everything the pallet knows about one address, read in one go by
+/// [`Pallet::get_address_profile`].
+#[derive(Encode, Decode, TypeInfo, Clone, PartialEq, Eq, RuntimeDebug, Default)]
+pub struct AddressProfile<BlockNumber> {
+    /// The username, resolved through the primary address for a linked alias.
+    pub username: Option<Vec<u8>>,
+    /// The address's own nonce, to sign its next message with.
+    pub nonce: u64,
+    /// The block at which the username was registered.
+    pub registered_at: Option<BlockNumber>,
+    /// The metadata attached with `set_profile`.
+    pub metadata: Option<ProfileMetadata>,
+}
+
+/// The username rules a runtime is configured with, read by [`Pallet::username_config`].
+#[derive(Encode, Decode, TypeInfo, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct UsernameConfig<BlockNumber> {
+    /// [`Config::MaxUsernameLength`], in bytes.
+    pub max_length: u32,
+    /// [`Config::MinUsernameLength`], in bytes.
+    pub min_length: u32,
+    /// [`ValidateUsername::description`] of [`Config::UsernameValidator`].
+    pub allowed_chars: Vec<u8>,
+    /// [`Config::ChangeCooldown`], in blocks.
+    pub change_cooldown: BlockNumber,
+}
+
+/// One page of a bounded scan over a storage map, as `(address, username)` pairs.
+#[derive(Encode, Decode, TypeInfo, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct ScanPage<Cursor> {
+    pub entries: Vec<(H160, Vec<u8>)>,
+    /// The key to pass back to continue the scan, or `None` once the map is exhausted.
+    pub next_cursor: Option<Cursor>,
+}
+
+/// Why [`Pallet::list_usernames`] or [`Pallet::search_usernames`] refused to scan.
+#[derive(Encode, Decode, TypeInfo, Clone, PartialEq, Eq, RuntimeDebug)]
+pub enum ScanError {
+    /// `limit` is above [`MAX_RPC_SCAN`]. Larger limits are refused rather than clamped, so a
+    /// client never mistakes a clamped page for the end of the map.
+    LimitTooHigh { requested: u32, max: u32 },
+    /// The cursor is longer than any username, so it can't be a key of [`UsernameOwners`].
+    InvalidCursor,
+    /// `limit` is zero. An empty page would carry no cursor and so read as the end of the map.
+    ZeroLimit,
+}
+
+/// The [`UsernameInfo`] stored in [`Usernames`].
+pub type UsernameInfoOf<T> =
+    UsernameInfo<BoundedVec<u8, <T as Config>::MaxUsernameLength>, BlockNumberFor<T>>;
 
 // All pallet logic is defined in its own module and must be annotated by the `pallet` attribute.
 #[frame_support::pallet]
@@ -38,9 +213,17 @@ pub mod pallet {
     };
     use frame_system::pallet_prelude::*;
 
+    /// The in-code storage version.
+    ///
+    /// Earlier releases declared no storage version, so chains running them report version 0
+    /// and still carry the unused `Something` value; see [`crate::migrations`] for the upgrade
+    /// path.
+    const STORAGE_VERSION: StorageVersion = StorageVersion::new(2);
+
     // The `Pallet` struct serves as a placeholder to implement traits, methods and dispatchables
     // (`Call`s) in this pallet.
     #[pallet::pallet]
+    #[pallet::storage_version(STORAGE_VERSION)]
     pub struct Pallet<T>(_);
 
     /// The pallet's configuration trait.
@@ -51,18 +234,94 @@ pub mod pallet {
     #[pallet::config]
     pub trait Config: frame_system::Config {
         /// The overarching runtime event type.
-        type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+        type RuntimeEvent: From<Event<Self>>
+            + IsType<<Self as frame_system::Config>::RuntimeEvent>
+            + TryInto<Event<Self>>;
 
         #[pallet::constant]
         type MaxUsernameLength: Get<u32>;
-    }
 
-    /// A storage item for this pallet.
-    ///
-    /// In this template, we are declaring a storage item called `Something` that stores a single
-    /// `u32` value. Learn more about runtime storage here: <https://docs.substrate.io/build/runtime-storage/>
-    #[pallet::storage]
-    pub type Something<T> = StorageValue<_, u32>;
+        /// The minimum length of a username, in bytes.
+        ///
+        /// This is checked independently of the character set rule: a username must be long
+        /// enough *and* satisfy [`Config::UsernameValidator`].
+        #[pallet::constant]
+        type MinUsernameLength: Get<u32>;
+
+        /// The character rules for usernames. [`AlphanumericUnderscore`] is the default policy.
+        type UsernameValidator: ValidateUsername;
+
+        /// Domain separator included in every signed message, so a signature produced for one
+        /// chain cannot be replayed on another chain running this pallet.
+        #[pallet::constant]
+        type ChainId: Get<u64>;
+
+        /// Number of blocks an address has to wait after changing its username with
+        /// `set_username` before it can change it again.
+        #[pallet::constant]
+        type ChangeCooldown: Get<BlockNumberFor<Self>>;
+
+        /// How long a username registered through `set_username` lasts, in blocks. `None`
+        /// means names never expire. Genesis and force-set names never expire either way.
+        #[pallet::constant]
+        type UsernameTtl: Get<Option<BlockNumberFor<Self>>>;
+
+        /// Maximum number of expired usernames removed in a single block's `on_initialize`.
+        /// Names past their expiry beyond this cap are removed in the following blocks.
+        #[pallet::constant]
+        type MaxExpiriesPerBlock: Get<u32>;
+
+        /// The currency used to hold username deposits.
+        type Currency: ReservableCurrency<Self::AccountId>;
+
+        /// Amount reserved when an address first gets a username through `set_username`.
+        ///
+        /// Usernames belong to `H160` addresses, which hold no balance, so the deposit is taken
+        /// from the Substrate account that signs the `set_username` extrinsic. That account is
+        /// recorded in [`UsernameDeposits`] and gets the deposit back when the username is
+        /// removed, whoever submits the removal. Changing an existing username keeps the
+        /// original deposit and payer.
+        #[pallet::constant]
+        type UsernameDeposit: Get<BalanceOf<Self>>;
+
+        /// Maximum number of alias addresses a primary address can link with `link_alias`.
+        #[pallet::constant]
+        type MaxAliases: Get<u32>;
+
+        /// Whether only addresses in [`Allowlist`] may register through `set_username`.
+        #[pallet::constant]
+        type RequireAllowlist: Get<bool>;
+
+        /// Number of entries kept per address in [`UsernameHistory`]. The oldest entry is
+        /// dropped once it is full.
+        #[pallet::constant]
+        type MaxHistory: Get<u32>;
+
+        /// Whether signed calls may skip ahead: any nonce at or above the current one is then
+        /// accepted, and the stored nonce moves past it. Otherwise the nonce must match exactly.
+        #[pallet::constant]
+        type AllowNonceSkip: Get<bool>;
+
+        /// Whether the offchain worker mirrors [`UsernameOwners`] into the node's offchain local
+        /// storage. It still only runs on nodes started with offchain workers enabled.
+        #[pallet::constant]
+        type OffchainIndexing: Get<bool>;
+
+        /// Maximum number of successful `set_username` and `set_username_with_scheme` calls in
+        /// one block. Further calls fail with [`Error::TooManyRegistrationsThisBlock`] until the
+        /// next block.
+        #[pallet::constant]
+        type MaxRegistrationsPerBlock: Get<u32>;
+
+        /// Whether [`Event::UsernameSet`] carries the `message_hash` an Ethereum signature was
+        /// checked against, so indexers can verify it from the event alone. Costs 32 bytes per
+        /// event.
+        #[pallet::constant]
+        type EmitMessageHash: Get<bool>;
+
+        /// A type representing the weights required by the dispatchables of this pallet.
+        type WeightInfo: WeightInfo;
+    }
 
     #[pallet::storage]
     #[pallet::getter(fn nonces)]
@@ -71,8 +330,165 @@ pub mod pallet {
     #[pallet::storage]
     #[pallet::getter(fn usernames)]
     pub type Usernames<T: Config> =
-        StorageMap<_, Blake2_128Concat, H160, BoundedVec<u8, T::MaxUsernameLength>, OptionQuery>;
-    //
+        StorageMap<_, Blake2_128Concat, H160, UsernameInfoOf<T>, OptionQuery>;
+
+    /// Reverse index of [`Usernames`], mapping each registered username to the address that
+    /// owns it.
+    ///
+    /// Keys are the normalized (ASCII-lowercased) form of the username so that uniqueness is
+    /// case-insensitive, while [`Usernames`] keeps the original casing for display.
+    #[pallet::storage]
+    #[pallet::getter(fn username_owners)]
+    pub type UsernameOwners<T: Config> =
+        StorageMap<_, Blake2_128Concat, BoundedVec<u8, T::MaxUsernameLength>, H160, OptionQuery>;
+
+    /// Block at which each address registered its current username, before any later renames.
+    /// A cleared or expired username loses this, so registering again starts afresh.
+    #[pallet::storage]
+    pub type RegisteredAt<T: Config> =
+        StorageMap<_, Blake2_128Concat, H160, BlockNumberFor<T>, OptionQuery>;
+
+    /// Block at which each address last changed its username via `set_username`, used to
+    /// enforce [`Config::ChangeCooldown`].
+    #[pallet::storage]
+    pub type LastUsernameChange<T: Config> =
+        StorageMap<_, Blake2_128Concat, H160, BlockNumberFor<T>, OptionQuery>;
+
+    /// Usernames that `set_username` refuses to assign, keyed by their normalized form.
+    ///
+    /// Managed by Root through `reserve_username` / `unreserve_username`. The force paths ignore
+    /// reservations.
+    #[pallet::storage]
+    pub type ReservedUsernames<T: Config> =
+        StorageMap<_, Blake2_128Concat, BoundedVec<u8, T::MaxUsernameLength>, (), OptionQuery>;
+
+    /// Deposit held for each address's username, together with the account that paid it.
+    #[pallet::storage]
+    pub type UsernameDeposits<T: Config> =
+        StorageMap<_, Blake2_128Concat, H160, (T::AccountId, BalanceOf<T>), OptionQuery>;
+
+    /// Addresses whose username expires at a given block, consumed by `on_initialize`.
+    ///
+    /// An entry is only acted on if the address's [`UsernameInfo::expires_at`] still matches,
+    /// so leftovers from a changed or cleared name are harmless.
+    #[pallet::storage]
+    pub type ExpiryQueue<T: Config> = StorageDoubleMap<
+        _,
+        Twox64Concat,
+        BlockNumberFor<T>,
+        Blake2_128Concat,
+        H160,
+        (),
+        OptionQuery,
+    >;
+
+    /// The earliest block whose [`ExpiryQueue`] entries may not have been processed yet.
+    ///
+    /// Unset until the first sweep, which then starts from the current block.
+    #[pallet::storage]
+    pub type ExpiryCursor<T: Config> = StorageValue<_, BlockNumberFor<T>, OptionQuery>;
+
+    /// Profile metadata of each address, removed together with its username.
+    #[pallet::storage]
+    pub type Profiles<T: Config> =
+        StorageMap<_, Blake2_128Concat, H160, ProfileMetadata, OptionQuery>;
+
+    /// Recent username changes of each address, oldest first, as `(username, block)` pairs.
+    ///
+    /// Every assignment appends the new username; every removal (clear, Root removal or expiry)
+    /// appends an empty username. Entries outlive the username itself so past ownership can be
+    /// audited.
+    #[pallet::storage]
+    pub type UsernameHistory<T: Config> = StorageMap<
+        _,
+        Blake2_128Concat,
+        H160,
+        BoundedVec<(BoundedVec<u8, T::MaxUsernameLength>, BlockNumberFor<T>), T::MaxHistory>,
+        ValueQuery,
+    >;
+
+    /// Alias addresses linked to each primary address, in link order.
+    #[pallet::storage]
+    pub type Aliases<T: Config> =
+        StorageMap<_, Blake2_128Concat, H160, BoundedVec<H160, T::MaxAliases>, ValueQuery>;
+
+    /// Reverse index of [`Aliases`], mapping each alias to its primary address.
+    #[pallet::storage]
+    pub type AliasOf<T: Config> = StorageMap<_, Blake2_128Concat, H160, H160, OptionQuery>;
+
+    /// Addresses Root approved for registration while [`Config::RequireAllowlist`] is set.
+    #[pallet::storage]
+    pub type Allowlist<T> = StorageMap<_, Blake2_128Concat, H160, (), OptionQuery>;
+
+    /// Usernames claimed by Substrate accounts with `set_substrate_username`.
+    #[pallet::storage]
+    pub type SubstrateUsernames<T: Config> = StorageMap<
+        _,
+        Blake2_128Concat,
+        T::AccountId,
+        BoundedVec<u8, T::MaxUsernameLength>,
+        OptionQuery,
+    >;
+
+    /// Reverse index of [`SubstrateUsernames`], keyed by normalized username like
+    /// [`UsernameOwners`].
+    #[pallet::storage]
+    pub type SubstrateUsernameOwners<T: Config> = StorageMap<
+        _,
+        Blake2_128Concat,
+        BoundedVec<u8, T::MaxUsernameLength>,
+        T::AccountId,
+        OptionQuery,
+    >;
+
+    /// Addresses Root banned from registering, renaming or receiving a username, and from
+    /// linking aliases on either side.
+    #[pallet::storage]
+    pub type Blocklist<T> = StorageMap<_, Blake2_128Concat, H160, (), OptionQuery>;
+
+    /// Number of usernames set in the current block, cleared in `on_initialize`.
+    #[pallet::storage]
+    pub type RegistrationsThisBlock<T> = StorageValue<_, u32, ValueQuery>;
+
+    /// Whether Root has paused `set_username`, `set_username_with_scheme`, `clear_username`,
+    /// `transfer_username`, `link_alias` and `set_substrate_username`.
+    #[pallet::storage]
+    pub type Paused<T> = StorageValue<_, bool, ValueQuery>;
+
+    /// Number of addresses that currently have a username registered.
+    #[pallet::storage]
+    pub type UsernameCount<T> = StorageValue<_, u32, ValueQuery>;
+
+    #[pallet::genesis_config]
+    #[derive(frame_support::DefaultNoBound)]
+    pub struct GenesisConfig<T: Config> {
+        /// Usernames registered at genesis, as `(eth_address, username)` pairs.
+        pub usernames: Vec<(H160, Vec<u8>)>,
+        #[serde(skip)]
+        pub _config: core::marker::PhantomData<T>,
+    }
+
+    #[pallet::genesis_build]
+    impl<T: Config> BuildGenesisConfig for GenesisConfig<T> {
+        fn build(&self) {
+            for (eth_address, username) in &self.usernames {
+                let bounded_username: BoundedVec<u8, T::MaxUsernameLength> = username
+                    .clone()
+                    .try_into()
+                    .expect("genesis username exceeds MaxUsernameLength");
+                assert!(
+                    !UsernameOwners::<T>::contains_key(Pallet::<T>::owner_key(&bounded_username)),
+                    "duplicate username in genesis config"
+                );
+                assert!(
+                    !Usernames::<T>::contains_key(eth_address),
+                    "duplicate address in genesis config"
+                );
+                Pallet::<T>::insert_username(eth_address, bounded_username, None);
+            }
+        }
+    }
+
     /// Events that functions in this pallet can emit.
     ///
     /// Events are a simple means of indicating to the outside world (such as dApps, chain explorers
@@ -86,9 +502,86 @@ pub mod pallet {
     #[pallet::event]
     #[pallet::generate_deposit(pub(super) fn deposit_event)]
     pub enum Event<T: Config> {
+        /// `eth_address` set `username` in an extrinsic signed by `submitter`. `deposit` is the
+        /// amount reserved from the submitter by this call; it is zero when the address already
+        /// had a deposit held.
+        ///
+        /// With [`Config::EmitMessageHash`], `message_hash` is the Keccak-256 digest the
+        /// Ethereum signature was recovered from: the EIP-191 `personal_sign` hash of the message,
+        /// or the EIP-712 digest. It is `None` otherwise and for ed25519 and sr25519 signatures,
+        /// which sign the message itself.
         UsernameSet {
             eth_address: H160,
             username: BoundedVec<u8, T::MaxUsernameLength>,
+            submitter: T::AccountId,
+            deposit: BalanceOf<T>,
+            message_hash: Option<[u8; 32]>,
+        },
+        /// The username of `eth_address` changed. `old_username` is `None` on first registration.
+        UsernameUpdated {
+            eth_address: H160,
+            old_username: Option<BoundedVec<u8, T::MaxUsernameLength>>,
+            new_username: BoundedVec<u8, T::MaxUsernameLength>,
+        },
+        /// The username registered for `eth_address` was removed.
+        UsernameCleared { eth_address: H160 },
+        /// Root assigned `username` to `eth_address`.
+        UsernameForceSet {
+            eth_address: H160,
+            username: BoundedVec<u8, T::MaxUsernameLength>,
+        },
+        /// Root removed the username registered for `eth_address`.
+        UsernameForceCleared { eth_address: H160 },
+        /// Root removed `count` usernames with `force_expire_all`. Each also has its own
+        /// `UsernameForceCleared`.
+        UsernamesForceExpired { count: u32 },
+        /// The username of `eth_address` reached its expiry block and was removed.
+        UsernameExpired {
+            eth_address: H160,
+            username: BoundedVec<u8, T::MaxUsernameLength>,
+        },
+        /// Root reserved `username` (normalized) so it can no longer be registered.
+        UsernameReserved {
+            username: BoundedVec<u8, T::MaxUsernameLength>,
+        },
+        /// Root lifted the reservation on `username` (normalized).
+        UsernameUnreserved {
+            username: BoundedVec<u8, T::MaxUsernameLength>,
+        },
+        /// `alias` now resolves to the username of `primary`.
+        AliasLinked { primary: H160, alias: H160 },
+        /// `alias` no longer resolves to the username of `primary`.
+        AliasUnlinked { primary: H160, alias: H160 },
+        /// All `count` aliases of `primary` were unlinked because its username was removed or
+        /// transferred.
+        AliasesCleared { primary: H160, count: u32 },
+        /// Root added `eth_address` to the allowlist.
+        AllowlistAdded { eth_address: H160 },
+        /// Root removed `eth_address` from the allowlist.
+        AllowlistRemoved { eth_address: H160 },
+        /// Root added `eth_address` to the blocklist.
+        AddressBlocked { eth_address: H160 },
+        /// Root removed `eth_address` from the blocklist.
+        AddressUnblocked { eth_address: H160 },
+        /// Root paused or resumed username changes.
+        PauseToggled { paused: bool },
+        /// `eth_address` replaced its profile metadata.
+        ProfileSet {
+            eth_address: H160,
+            metadata: ProfileMetadata,
+        },
+        /// The Substrate account `who` claimed `username`.
+        SubstrateUsernameSet {
+            who: T::AccountId,
+            username: BoundedVec<u8, T::MaxUsernameLength>,
+        },
+        /// Root set the nonce of `eth_address` to `new_nonce`.
+        NonceReset { eth_address: H160, new_nonce: u64 },
+        /// `username` moved from the address `from` to the address `to`.
+        UsernameTransferred {
+            from: H160,
+            to: H160,
+            username: BoundedVec<u8, T::MaxUsernameLength>,
         },
     }
 
@@ -103,9 +596,97 @@ pub mod pallet {
     #[pallet::error]
     pub enum Error<T> {
         UsernameTooLong,
+        /// The username is shorter than `MinUsernameLength`.
+        UsernameTooShort,
+        /// The username breaks the rules of [`Config::UsernameValidator`].
         InvalidUsername,
         InvalidNonce,
-        InvalidEthereumSignature,
+        /// An Ethereum signature recovers to a different address than the one it is checked
+        /// against, so it was made with the wrong key or over a different message.
+        SignatureAddressMismatch,
+        /// The username is already owned by a different address.
+        UsernameTaken,
+        /// No username is registered for the address.
+        UsernameNotFound,
+        /// The address has used up every nonce and cannot sign any further actions.
+        NonceOverflow,
+        /// The address changed its username less than `ChangeCooldown` blocks ago.
+        ChangeTooSoon,
+        /// The username is reserved and can only be assigned through `force_set_username`.
+        UsernameReserved,
+        /// The username is not in the reserved list.
+        UsernameNotReserved,
+        /// An ed25519 or sr25519 signature is malformed, does not match the message, or its
+        /// public key does not derive to the given address.
+        InvalidSignature,
+        /// The zero address cannot own a username.
+        InvalidAddress,
+        /// The alias is the zero address, the primary itself, or owns a username of its own.
+        InvalidAlias,
+        /// The address is already linked as an alias, so it cannot be linked again, have a
+        /// username of its own, or act as a primary.
+        AliasAlreadyLinked,
+        /// The address is not an alias of the given primary.
+        AliasNotFound,
+        /// The primary already has `MaxAliases` aliases.
+        TooManyAliases,
+        /// Username changes are paused by Root.
+        PalletPaused,
+        /// The address is not on the allowlist.
+        NotAllowed,
+        /// The recipient of a transfer already has a username.
+        RecipientHasUsername,
+        /// The address is on the blocklist.
+        AddressBlocked,
+        /// The address is not on the blocklist.
+        AddressNotBlocked,
+        /// `MaxRegistrationsPerBlock` usernames were already set in this block.
+        TooManyRegistrationsThisBlock,
+        /// No address can be recovered from an Ethereum signature: it has the wrong length, an
+        /// unsupported `v`, a high `s`, or doesn't correspond to any key.
+        SignatureRecoveryFailed,
+    }
+
+    impl<T> From<EthSignatureError> for Error<T> {
+        fn from(error: EthSignatureError) -> Self {
+            match error {
+                EthSignatureError::RecoveryFailed => Error::SignatureRecoveryFailed,
+                EthSignatureError::AddressMismatch { .. } => Error::SignatureAddressMismatch,
+            }
+        }
+    }
+
+    #[pallet::hooks]
+    impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+        /// Remove up to `MaxExpiriesPerBlock` usernames whose expiry block has been reached.
+        fn on_initialize(now: BlockNumberFor<T>) -> Weight {
+            RegistrationsThisBlock::<T>::kill();
+            Self::expire_usernames(now).saturating_add(T::DbWeight::get().writes(1))
+        }
+
+        /// Update the offchain username index with the changes made in block `_n`.
+        fn offchain_worker(_n: BlockNumberFor<T>) {
+            if T::OffchainIndexing::get() {
+                Self::index_usernames_offchain();
+            }
+        }
+
+        /// Reject length bounds under which no username could ever be registered.
+        fn integrity_test() {
+            assert!(
+                T::MaxUsernameLength::get() > 0,
+                "MaxUsernameLength must be greater than zero"
+            );
+            assert!(
+                T::MinUsernameLength::get() <= T::MaxUsernameLength::get(),
+                "MinUsernameLength must not exceed MaxUsernameLength"
+            );
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn try_state(_n: BlockNumberFor<T>) -> Result<(), TryRuntimeError> {
+            Self::do_try_state()
+        }
     }
 
     /// The pallet's dispatchable functions ([`Call`]s).
@@ -128,93 +709,1316 @@ pub mod pallet {
         /// It checks that the _origin_ for this call is _Signed_ and returns a dispatch
         /// error if it isn't. Learn more about origins here: <https://docs.substrate.io/build/origins/>
         #[pallet::call_index(0)]
-        #[pallet::weight(1000)]
+        #[pallet::weight(T::WeightInfo::set_username(username.len() as u32))]
         pub fn set_username(
             origin: OriginFor<T>,
             eth_address: H160,
             username: Vec<u8>,
             nonce: u64,
             eth_signature: Vec<u8>,
+        ) -> DispatchResultWithPostInfo {
+            // Check that the extrinsic was signed and get the signer, who pays the deposit.
+            let who = ensure_signed(origin)?;
+            Self::do_set_username(
+                who,
+                eth_address,
+                username,
+                nonce,
+                SigScheme::EthEcdsa,
+                eth_signature,
+            )
+        }
+
+        /// Like `set_username`, but authorized with any [`SigScheme`].
+        ///
+        /// `signature` signs the same `set_username:{chain_id}:{username}:{nonce}` message. For
+        /// the Substrate schemes it is a raw signature over the message bytes, and
+        /// `eth_address` must be the [`username_primitives::substrate_key_address`] of the
+        /// scheme's public key.
+        #[pallet::call_index(6)]
+        #[pallet::weight(T::WeightInfo::set_username(username.len() as u32))]
+        pub fn set_username_with_scheme(
+            origin: OriginFor<T>,
+            eth_address: H160,
+            username: Vec<u8>,
+            nonce: u64,
+            scheme: SigScheme,
+            signature: Vec<u8>,
+        ) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
+            Self::do_set_username(who, eth_address, username, nonce, scheme, signature)
+        }
+
+        /// Remove the username registered for `eth_address`.
+        ///
+        /// The owner authorizes the removal by signing
+        /// `clear_username:{chain_id}:{eth_address}:{nonce}`, where the address is `0x`-prefixed
+        /// lowercase hex. The nonce is checked and bumped
+        /// exactly like in `set_username`, so a replayed clear is rejected.
+        #[pallet::call_index(1)]
+        #[pallet::weight(T::WeightInfo::clear_username(T::MaxAliases::get()))]
+        pub fn clear_username(
+            origin: OriginFor<T>,
+            eth_address: H160,
+            nonce: u64,
+            eth_signature: Vec<u8>,
         ) -> DispatchResult {
-            // Check that the extrinsic was signed and get the signer.
             let _ = ensure_signed(origin)?;
+            ensure!(!Paused::<T>::get(), Error::<T>::PalletPaused);
 
-            let expected_nonce = Nonces::<T>::get(&eth_address);
-            ensure!(nonce == expected_nonce, Error::<T>::InvalidNonce);
-
-            let bounded_username: BoundedVec<u8, T::MaxUsernameLength> = username
-                .clone()
-                .try_into()
-                .map_err(|_| Error::<T>::UsernameTooLong)?;
+            let next_nonce = Self::check_nonce(&eth_address, nonce)?;
 
             ensure!(
-                bounded_username
-                    .iter()
-                    .all(|&c| c.is_ascii_alphanumeric() || c == b'_'),
-                Error::<T>::InvalidUsername
+                Usernames::<T>::contains_key(&eth_address),
+                Error::<T>::UsernameNotFound
             );
 
-            let message = format!(
-                "set_username:{}:{}",
-                String::from_utf8_lossy(&username.clone()),
-                &nonce
-            );
+            let message = Self::clear_message(&eth_address, nonce);
+            Self::verify_ethereum_signature(&eth_address, &message, &eth_signature)?;
+
+            Nonces::<T>::insert(&eth_address, next_nonce);
+            Self::remove_username(&eth_address);
+
+            Self::deposit_event(Event::UsernameCleared { eth_address });
+
+            Ok(())
+        }
+
+        /// Assign `username` to `eth_address` without an Ethereum signature.
+        ///
+        /// This is an administrative correction and requires the _Root_ origin. The nonce is left
+        /// untouched, but the address rules and the length, character set and uniqueness rules
+        /// still apply.
+        #[pallet::call_index(2)]
+        #[pallet::weight(T::WeightInfo::set_username(username.len() as u32))]
+        pub fn force_set_username(
+            origin: OriginFor<T>,
+            eth_address: H160,
+            username: Vec<u8>,
+        ) -> DispatchResult {
+            ensure_root(origin)?;
 
+            ensure!(!eth_address.is_zero(), Error::<T>::InvalidAddress);
             ensure!(
-                Self::verify_ethereum_signature(&eth_address, message.as_bytes(), &eth_signature),
-                Error::<T>::InvalidEthereumSignature
+                !Blocklist::<T>::contains_key(eth_address),
+                Error::<T>::AddressBlocked
+            );
+            ensure!(
+                !AliasOf::<T>::contains_key(eth_address),
+                Error::<T>::AliasAlreadyLinked
             );
 
-            // Store
-            Nonces::<T>::insert(&eth_address, nonce + 1);
-            Usernames::<T>::insert(&eth_address, bounded_username.clone());
+            let bounded_username = Self::validate_username(username)?;
+            Self::ensure_available(&eth_address, &bounded_username)?;
 
-            Self::deposit_event(Event::UsernameSet {
+            Self::insert_username(&eth_address, bounded_username.clone(), None);
+
+            Self::deposit_event(Event::UsernameForceSet {
                 eth_address,
                 username: bounded_username,
             });
 
             Ok(())
         }
-    }
-}
 
-impl<T: Config> Pallet<T> {
-    pub fn get_nonce(eth_address: H160) -> u64 {
-        Nonces::<T>::get(eth_address)
-    }
+        /// Remove the username of `eth_address` without an Ethereum signature.
+        ///
+        /// Requires the _Root_ origin. The nonce is left untouched.
+        #[pallet::call_index(3)]
+        #[pallet::weight(T::WeightInfo::force_clear_username(T::MaxAliases::get()))]
+        pub fn force_clear_username(origin: OriginFor<T>, eth_address: H160) -> DispatchResult {
+            ensure_root(origin)?;
 
-    pub fn get_username(eth_address: H160) -> Option<Vec<u8>> {
-        Usernames::<T>::get(eth_address).map(|b| b.into_inner())
-    }
+            ensure!(
+                Usernames::<T>::contains_key(&eth_address),
+                Error::<T>::UsernameNotFound
+            );
 
-    pub fn verify_ethereum_signature(eth_address: &H160, message: &[u8], signature: &[u8]) -> bool {
-        // TODO, make 65, 30, 27 as constants
-        if signature.len() != 65 {
-            return false;
+            Self::remove_username(&eth_address);
+
+            Self::deposit_event(Event::UsernameForceCleared { eth_address });
+
+            Ok(())
         }
 
-        let prefix = format!("\x19Ethereum Signed Message:\n{}", message.len());
-        let mut eth_message = prefix.as_bytes().to_vec();
-        eth_message.extend_from_slice(message);
+        /// Prevent `username` from being registered through `set_username`.
+        ///
+        /// Requires the _Root_ origin. The reservation is case-insensitive and does not affect an
+        /// address that already holds the name.
+        #[pallet::call_index(4)]
+        #[pallet::weight(T::WeightInfo::reserve_username())]
+        pub fn reserve_username(origin: OriginFor<T>, username: Vec<u8>) -> DispatchResult {
+            ensure_root(origin)?;
+
+            let bounded_username: BoundedVec<u8, T::MaxUsernameLength> = username
+                .try_into()
+                .map_err(|_| Error::<T>::UsernameTooLong)?;
+            let key = Self::owner_key(&bounded_username);
 
-        let hash = keccak_256(&eth_message);
+            ReservedUsernames::<T>::insert(&key, ());
 
-        let mut sig_array = [0u8; 65];
-        sig_array.copy_from_slice(&signature[..65]);
+            Self::deposit_event(Event::UsernameReserved { username: key });
 
-        if sig_array[64] < 27 || sig_array[64] > 30 {
-            return false;
+            Ok(())
         }
-        sig_array[64] -= 27;
 
-        match sp_io::crypto::secp256k1_ecdsa_recover(&sig_array, &hash) {
-            Ok(pubkey) => {
-                let recovered_addr_hash = keccak_256(&pubkey[1..]);
-                let recovered_eth_addr = H160::from_slice(&recovered_addr_hash[12..32]);
-                &recovered_eth_addr == eth_address
-            }
-            Err(_) => false,
+        /// Lift a reservation made with `reserve_username`.
+        ///
+        /// Requires the _Root_ origin.
+        #[pallet::call_index(5)]
+        #[pallet::weight(T::WeightInfo::unreserve_username())]
+        pub fn unreserve_username(origin: OriginFor<T>, username: Vec<u8>) -> DispatchResult {
+            ensure_root(origin)?;
+
+            let bounded_username: BoundedVec<u8, T::MaxUsernameLength> = username
+                .try_into()
+                .map_err(|_| Error::<T>::UsernameNotReserved)?;
+            let key = Self::owner_key(&bounded_username);
+
+            ensure!(
+                ReservedUsernames::<T>::take(&key).is_some(),
+                Error::<T>::UsernameNotReserved
+            );
+
+            Self::deposit_event(Event::UsernameUnreserved { username: key });
+
+            Ok(())
+        }
+
+        /// Link `alias` to `primary`, so that username lookups of `alias` resolve to the username
+        /// of `primary`.
+        ///
+        /// Both addresses prove control by signing
+        /// `link_alias:{chain_id}:{primary}:{alias}:{nonce}`, with the addresses as `0x`-prefixed
+        /// lowercase hex and `nonce` the current nonce of `primary`. `primary` must have a
+        /// username; `alias` must not. Neither may be on the blocklist, and linking is refused
+        /// while the pallet is paused.
+        #[pallet::call_index(8)]
+        #[pallet::weight(T::WeightInfo::link_alias(T::MaxAliases::get()))]
+        pub fn link_alias(
+            origin: OriginFor<T>,
+            primary: H160,
+            alias: H160,
+            nonce: u64,
+            primary_signature: Vec<u8>,
+            alias_signature: Vec<u8>,
+        ) -> DispatchResult {
+            let _ = ensure_signed(origin)?;
+            ensure!(!Paused::<T>::get(), Error::<T>::PalletPaused);
+            ensure!(
+                !Blocklist::<T>::contains_key(&primary) && !Blocklist::<T>::contains_key(&alias),
+                Error::<T>::AddressBlocked
+            );
+
+            let next_nonce = Self::check_nonce(&primary, nonce)?;
+
+            ensure!(
+                Usernames::<T>::contains_key(&primary),
+                Error::<T>::UsernameNotFound
+            );
+            ensure!(
+                !AliasOf::<T>::contains_key(&primary) && !AliasOf::<T>::contains_key(&alias),
+                Error::<T>::AliasAlreadyLinked
+            );
+            ensure!(
+                !alias.is_zero() && alias != primary && !Usernames::<T>::contains_key(&alias),
+                Error::<T>::InvalidAlias
+            );
+
+            let message = Self::alias_message("link_alias", &primary, &alias, nonce);
+            Self::verify_ethereum_signature(&primary, &message, &primary_signature)?;
+            Self::verify_ethereum_signature(&alias, &message, &alias_signature)?;
+
+            Aliases::<T>::try_mutate(&primary, |aliases| aliases.try_push(alias))
+                .map_err(|_| Error::<T>::TooManyAliases)?;
+            AliasOf::<T>::insert(&alias, primary);
+            Nonces::<T>::insert(&primary, next_nonce);
+
+            Self::deposit_event(Event::AliasLinked { primary, alias });
+
+            Ok(())
+        }
+
+        /// Unlink `alias` from `primary`.
+        ///
+        /// Either address can authorize this by signing
+        /// `unlink_alias:{chain_id}:{primary}:{alias}:{nonce}`, where `nonce` is the current
+        /// nonce of `primary`.
+        #[pallet::call_index(9)]
+        #[pallet::weight(T::WeightInfo::unlink_alias(T::MaxAliases::get()))]
+        pub fn unlink_alias(
+            origin: OriginFor<T>,
+            primary: H160,
+            alias: H160,
+            nonce: u64,
+            eth_signature: Vec<u8>,
+        ) -> DispatchResult {
+            let _ = ensure_signed(origin)?;
+
+            let next_nonce = Self::check_nonce(&primary, nonce)?;
+
+            ensure!(
+                AliasOf::<T>::get(&alias) == Some(primary),
+                Error::<T>::AliasNotFound
+            );
+
+            let message = Self::alias_message("unlink_alias", &primary, &alias, nonce);
+            Self::verify_ethereum_signature(&primary, &message, &eth_signature)
+                .or_else(|_| Self::verify_ethereum_signature(&alias, &message, &eth_signature))?;
+
+            AliasOf::<T>::remove(&alias);
+            Aliases::<T>::mutate(&primary, |aliases| {
+                aliases.retain(|linked| *linked != alias)
+            });
+            Nonces::<T>::insert(&primary, next_nonce);
+
+            Self::deposit_event(Event::AliasUnlinked { primary, alias });
+
+            Ok(())
         }
+
+        /// Replace the profile metadata of `eth_address`, which must have a username.
+        ///
+        /// The owner signs `set_profile:{chain_id}:{eth_address}:{nonce}:{metadata_hash}`, where
+        /// the address is `0x`-prefixed lowercase hex and `metadata_hash` is the `0x`-prefixed
+        /// keccak-256 hash of the SCALE-encoded `metadata`. The nonce is shared with
+        /// `set_username`.
+        #[pallet::call_index(7)]
+        #[pallet::weight(T::WeightInfo::set_profile())]
+        pub fn set_profile(
+            origin: OriginFor<T>,
+            eth_address: H160,
+            metadata: ProfileMetadata,
+            nonce: u64,
+            eth_signature: Vec<u8>,
+        ) -> DispatchResult {
+            let _ = ensure_signed(origin)?;
+
+            let next_nonce = Self::check_nonce(&eth_address, nonce)?;
+
+            ensure!(
+                Usernames::<T>::contains_key(&eth_address),
+                Error::<T>::UsernameNotFound
+            );
+
+            let message = Self::set_profile_message(&eth_address, &metadata, nonce);
+            Self::verify_ethereum_signature(&eth_address, &message, &eth_signature)?;
+
+            Nonces::<T>::insert(&eth_address, next_nonce);
+            Profiles::<T>::insert(&eth_address, metadata.clone());
+
+            Self::deposit_event(Event::ProfileSet {
+                eth_address,
+                metadata,
+            });
+
+            Ok(())
+        }
+
+        /// Pause or resume `set_username`, `set_username_with_scheme`, `clear_username`,
+        /// `transfer_username`, `link_alias` and `set_substrate_username`.
+        ///
+        /// Requires the _Root_ origin. The force paths keep working while paused.
+        #[pallet::call_index(10)]
+        #[pallet::weight(T::WeightInfo::set_paused())]
+        pub fn set_paused(origin: OriginFor<T>, paused: bool) -> DispatchResult {
+            ensure_root(origin)?;
+
+            Paused::<T>::put(paused);
+            Self::deposit_event(Event::PauseToggled { paused });
+
+            Ok(())
+        }
+
+        /// Allow `eth_address` to register while [`Config::RequireAllowlist`] is set.
+        ///
+        /// Requires the _Root_ origin.
+        #[pallet::call_index(11)]
+        #[pallet::weight(T::WeightInfo::add_to_allowlist())]
+        pub fn add_to_allowlist(origin: OriginFor<T>, eth_address: H160) -> DispatchResult {
+            ensure_root(origin)?;
+
+            Allowlist::<T>::insert(eth_address, ());
+            Self::deposit_event(Event::AllowlistAdded { eth_address });
+
+            Ok(())
+        }
+
+        /// Remove `eth_address` from the allowlist. A username it already registered is kept.
+        ///
+        /// Requires the _Root_ origin.
+        #[pallet::call_index(12)]
+        #[pallet::weight(T::WeightInfo::remove_from_allowlist())]
+        pub fn remove_from_allowlist(origin: OriginFor<T>, eth_address: H160) -> DispatchResult {
+            ensure_root(origin)?;
+
+            ensure!(
+                Allowlist::<T>::take(eth_address).is_some(),
+                Error::<T>::NotAllowed
+            );
+            Self::deposit_event(Event::AllowlistRemoved { eth_address });
+
+            Ok(())
+        }
+
+        /// Move the username of `from` to `to`, which must not have one yet.
+        ///
+        /// The owner of `from` signs `transfer_username:{chain_id}:{from}:{to}:{nonce}`, where
+        /// both addresses are `0x`-prefixed lowercase hex and `nonce` is the current nonce of
+        /// `from`. The name never becomes available in between: its deposit, expiry,
+        /// registration block and profile move along with it, while the aliases of `from` are
+        /// unlinked. `to` must pass the same allowlist and change cooldown checks as a
+        /// `set_username` for it would.
+        #[pallet::call_index(13)]
+        #[pallet::weight(T::WeightInfo::transfer_username(T::MaxAliases::get()))]
+        pub fn transfer_username(
+            origin: OriginFor<T>,
+            from: H160,
+            to: H160,
+            nonce: u64,
+            eth_signature: Vec<u8>,
+        ) -> DispatchResult {
+            let _ = ensure_signed(origin)?;
+            ensure!(!Paused::<T>::get(), Error::<T>::PalletPaused);
+            ensure!(!to.is_zero() && to != from, Error::<T>::InvalidAddress);
+            ensure!(
+                !Blocklist::<T>::contains_key(&from) && !Blocklist::<T>::contains_key(&to),
+                Error::<T>::AddressBlocked
+            );
+            ensure!(
+                !T::RequireAllowlist::get() || Allowlist::<T>::contains_key(&to),
+                Error::<T>::NotAllowed
+            );
+
+            let next_nonce = Self::check_nonce(&from, nonce)?;
+
+            ensure!(
+                Usernames::<T>::contains_key(&from),
+                Error::<T>::UsernameNotFound
+            );
+            ensure!(
+                !Usernames::<T>::contains_key(&to),
+                Error::<T>::RecipientHasUsername
+            );
+            ensure!(
+                !AliasOf::<T>::contains_key(&to),
+                Error::<T>::AliasAlreadyLinked
+            );
+            Self::ensure_cooldown_passed(&to, frame_system::Pallet::<T>::block_number())?;
+
+            let message = Self::transfer_message(&from, &to, nonce);
+            Self::verify_ethereum_signature(&from, &message, &eth_signature)?;
+
+            Nonces::<T>::insert(&from, next_nonce);
+            if let Some(username) = Self::move_username(&from, &to) {
+                Self::deposit_event(Event::UsernameTransferred { from, to, username });
+            }
+
+            Ok(())
+        }
+
+        /// Set the nonce of `eth_address` to `new_nonce`.
+        ///
+        /// An administrative escape hatch for owners whose signer lost track of the chain nonce;
+        /// it requires the _Root_ origin. Use it carefully: the nonce is what stops signed
+        /// messages from being replayed, so moving it back to a used value makes every message
+        /// already signed for that nonce valid again.
+        #[pallet::call_index(14)]
+        #[pallet::weight(T::WeightInfo::reset_nonce())]
+        pub fn reset_nonce(
+            origin: OriginFor<T>,
+            eth_address: H160,
+            new_nonce: u64,
+        ) -> DispatchResult {
+            ensure_root(origin)?;
+
+            Nonces::<T>::insert(&eth_address, new_nonce);
+            Self::deposit_event(Event::NonceReset {
+                eth_address,
+                new_nonce,
+            });
+
+            Ok(())
+        }
+
+        /// Ban `eth_address` from registering, renaming or receiving a username, and from being
+        /// linked as a primary or an alias. A username it already holds is kept, and it can still
+        /// clear it or unlink its aliases.
+        ///
+        /// Requires the _Root_ origin.
+        #[pallet::call_index(15)]
+        #[pallet::weight(T::WeightInfo::block_address())]
+        pub fn block_address(origin: OriginFor<T>, eth_address: H160) -> DispatchResult {
+            ensure_root(origin)?;
+
+            Blocklist::<T>::insert(eth_address, ());
+            Self::deposit_event(Event::AddressBlocked { eth_address });
+
+            Ok(())
+        }
+
+        /// Lift the ban on `eth_address`.
+        ///
+        /// Requires the _Root_ origin.
+        #[pallet::call_index(16)]
+        #[pallet::weight(T::WeightInfo::unblock_address())]
+        pub fn unblock_address(origin: OriginFor<T>, eth_address: H160) -> DispatchResult {
+            ensure_root(origin)?;
+
+            ensure!(
+                Blocklist::<T>::take(eth_address).is_some(),
+                Error::<T>::AddressNotBlocked
+            );
+            Self::deposit_event(Event::AddressUnblocked { eth_address });
+
+            Ok(())
+        }
+
+        /// Claim `username` for the signing Substrate account, without any Ethereum key.
+        ///
+        /// The length, charset and reservation rules are those of `set_username`. Both paths
+        /// share one namespace: a name an Ethereum address holds can't be claimed here and the
+        /// other way round, compared case-insensitively. Calling again renames the account and
+        /// releases its previous name. No deposit is held.
+        #[pallet::call_index(17)]
+        #[pallet::weight(T::WeightInfo::set_substrate_username(username.len() as u32))]
+        pub fn set_substrate_username(origin: OriginFor<T>, username: Vec<u8>) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            ensure!(!Paused::<T>::get(), Error::<T>::PalletPaused);
+
+            let bounded_username = Self::validate_username(username)?;
+            let key = Self::owner_key(&bounded_username);
+            ensure!(
+                !ReservedUsernames::<T>::contains_key(&key),
+                Error::<T>::UsernameReserved
+            );
+            ensure!(
+                !UsernameOwners::<T>::contains_key(&key),
+                Error::<T>::UsernameTaken
+            );
+            if let Some(owner) = SubstrateUsernameOwners::<T>::get(&key) {
+                ensure!(owner == who, Error::<T>::UsernameTaken);
+            }
+
+            if let Some(previous) = SubstrateUsernames::<T>::get(&who) {
+                SubstrateUsernameOwners::<T>::remove(Self::owner_key(&previous));
+            }
+            SubstrateUsernameOwners::<T>::insert(key, who.clone());
+            SubstrateUsernames::<T>::insert(&who, bounded_username.clone());
+
+            Self::deposit_event(Event::SubstrateUsernameSet {
+                who,
+                username: bounded_username,
+            });
+
+            Ok(())
+        }
+
+        /// Remove up to `limit` usernames at once, e.g. for an end-of-season reset, without
+        /// waiting for them to expire.
+        ///
+        /// Requires the _Root_ origin. Call it again until it reports a `count` of zero to
+        /// clear every username. Nonces are left untouched, and only the names actually removed
+        /// are charged for.
+        #[pallet::call_index(18)]
+        #[pallet::weight(T::WeightInfo::force_expire_all(*limit))]
+        pub fn force_expire_all(origin: OriginFor<T>, limit: u32) -> DispatchResultWithPostInfo {
+            ensure_root(origin)?;
+
+            let addresses: Vec<H160> = Usernames::<T>::iter_keys().take(limit as usize).collect();
+            for eth_address in &addresses {
+                Self::remove_username(eth_address);
+                Self::deposit_event(Event::UsernameForceCleared {
+                    eth_address: *eth_address,
+                });
+            }
+
+            let count = addresses.len() as u32;
+            Self::deposit_event(Event::UsernamesForceExpired { count });
+
+            Ok(Some(T::WeightInfo::force_expire_all(count)).into())
+        }
+    }
+}
+
+/// Prefix of the offchain local storage keys that map a normalized username to its owner.
+pub const OFFCHAIN_OWNER_PREFIX: &[u8] = b"username::owner::";
+
+/// Prefix of the offchain local storage keys that map an address to its normalized username.
+const OFFCHAIN_NAME_PREFIX: &[u8] = b"username::name::";
+
+/// Offchain local storage key set once the offchain worker has indexed every existing username.
+const OFFCHAIN_BACKFILLED_KEY: &[u8] = b"username::backfilled";
+
+impl<T: Config> Pallet<T> {
+    pub fn get_nonce(eth_address: H160) -> u64 {
+        Nonces::<T>::get(eth_address)
+    }
+
+    /// The username the Substrate account `who` claimed with `set_substrate_username`.
+    pub fn get_substrate_username(who: T::AccountId) -> Option<Vec<u8>> {
+        SubstrateUsernames::<T>::get(who).map(|username| username.into_inner())
+    }
+
+    /// The username of `eth_address`, or of its primary address if it is a linked alias.
+    pub fn get_username(eth_address: H160) -> Option<Vec<u8>> {
+        let owner = AliasOf::<T>::get(eth_address).unwrap_or(eth_address);
+        Usernames::<T>::get(owner).map(|info| info.username.into_inner())
+    }
+
+    /// The recent username changes of `eth_address`, oldest first. An empty username marks a
+    /// removal.
+    pub fn get_username_history(eth_address: H160) -> Vec<(Vec<u8>, BlockNumberFor<T>)> {
+        UsernameHistory::<T>::get(eth_address)
+            .into_iter()
+            .map(|(username, block)| (username.into_inner(), block))
+            .collect()
+    }
+
+    /// The primary address `eth_address` is linked to as an alias, if any.
+    pub fn get_primary(eth_address: H160) -> Option<H160> {
+        AliasOf::<T>::get(eth_address)
+    }
+
+    /// The message `link_alias` or `unlink_alias` (per `action`) expects to be signed.
+    pub fn alias_message(action: &str, primary: &H160, alias: &H160, nonce: u64) -> Vec<u8> {
+        format!(
+            "{}:{}:{:#x}:{:#x}:{}",
+            action,
+            T::ChainId::get(),
+            primary,
+            alias,
+            nonce
+        )
+        .into_bytes()
+    }
+
+    /// The message `clear_username` expects the owner of `eth_address` to sign.
+    pub fn clear_message(eth_address: &H160, nonce: u64) -> Vec<u8> {
+        clear_username_message(T::ChainId::get(), eth_address, nonce)
+    }
+
+    /// The message `transfer_username` expects the owner of `from` to sign.
+    pub fn transfer_message(from: &H160, to: &H160, nonce: u64) -> Vec<u8> {
+        format!(
+            "transfer_username:{}:{:#x}:{:#x}:{}",
+            T::ChainId::get(),
+            from,
+            to,
+            nonce
+        )
+        .into_bytes()
+    }
+
+    /// The block at which `eth_address` registered its current username.
+    pub fn get_registration_block(eth_address: H160) -> Option<BlockNumberFor<T>> {
+        RegisteredAt::<T>::get(eth_address)
+    }
+
+    pub fn get_profile(eth_address: H160) -> Option<ProfileMetadata> {
+        Profiles::<T>::get(eth_address)
+    }
+
+    /// The username, nonce, registration block and metadata of `eth_address`. An
+    /// unregistered address gets `None` for all but its nonce.
+    pub fn get_address_profile(eth_address: H160) -> AddressProfile<BlockNumberFor<T>> {
+        let owner = AliasOf::<T>::get(eth_address).unwrap_or(eth_address);
+        AddressProfile {
+            username: Usernames::<T>::get(owner).map(|info| info.username.into_inner()),
+            nonce: Nonces::<T>::get(eth_address),
+            registered_at: RegisteredAt::<T>::get(owner),
+            metadata: Profiles::<T>::get(owner),
+        }
+    }
+
+    /// The message `set_profile` expects to be signed for `metadata` at `nonce`.
+    pub fn set_profile_message(
+        eth_address: &H160,
+        metadata: &ProfileMetadata,
+        nonce: u64,
+    ) -> Vec<u8> {
+        let metadata_hash = H256(sp_io::hashing::keccak_256(&metadata.encode()));
+        format!(
+            "set_profile:{}:{:#x}:{}:{:#x}",
+            T::ChainId::get(),
+            eth_address,
+            nonce,
+            metadata_hash
+        )
+        .into_bytes()
+    }
+
+    /// Look up the username of every address in `addresses`, keeping their order.
+    pub fn get_usernames(addresses: Vec<H160>) -> Vec<(H160, Option<Vec<u8>>)> {
+        addresses
+            .into_iter()
+            .map(|eth_address| (eth_address, Self::get_username(eth_address)))
+            .collect()
+    }
+
+    pub fn username_count() -> u32 {
+        UsernameCount::<T>::get()
+    }
+
+    pub fn get_owner(username: Vec<u8>) -> Option<H160> {
+        let bounded_username: BoundedVec<u8, T::MaxUsernameLength> =
+            Self::normalize(&username).try_into().ok()?;
+        UsernameOwners::<T>::get(bounded_username)
+    }
+
+    /// Whether `set_username` could assign `username` to a new address right now: it passes the
+    /// length and charset rules, is not reserved, and no address owns it (in any casing).
+    pub fn is_username_available(username: Vec<u8>) -> bool {
+        let Ok(bounded_username) = Self::validate_username(username) else {
+            return false;
+        };
+        let key = Self::owner_key(&bounded_username);
+        !UsernameOwners::<T>::contains_key(&key)
+            && !SubstrateUsernameOwners::<T>::contains_key(&key)
+            && !ReservedUsernames::<T>::contains_key(&key)
+    }
+
+    /// Whether `username` satisfies [`Config::UsernameValidator`], ignoring its length.
+    pub fn is_valid_username(username: Vec<u8>) -> bool {
+        !username.is_empty() && T::UsernameValidator::is_valid(&username)
+    }
+
+    /// The `(MinUsernameLength, MaxUsernameLength)` bounds, in bytes.
+    pub fn username_length_bounds() -> (u32, u32) {
+        (T::MinUsernameLength::get(), T::MaxUsernameLength::get())
+    }
+
+    /// The length, charset and cooldown rules usernames are checked against.
+    pub fn username_config() -> UsernameConfig<BlockNumberFor<T>> {
+        UsernameConfig {
+            max_length: T::MaxUsernameLength::get(),
+            min_length: T::MinUsernameLength::get(),
+            allowed_chars: T::UsernameValidator::description().as_bytes().to_vec(),
+            change_cooldown: T::ChangeCooldown::get(),
+        }
+    }
+
+    /// List up to `limit` registered usernames, starting after the address `start_key`.
+    ///
+    /// Entries are returned in the storage key order of [`Usernames`], which follows the hashed
+    /// keys and not insertion order. A full page carries its last address as the cursor to pass
+    /// as `start_key` next; the page after the last full one may then be empty.
+    pub fn list_usernames(
+        start_key: Option<H160>,
+        limit: u32,
+    ) -> Result<ScanPage<H160>, ScanError> {
+        Self::check_scan_limit(limit)?;
+        let iter = match start_key {
+            Some(key) => Usernames::<T>::iter_from(Usernames::<T>::hashed_key_for(key)),
+            None => Usernames::<T>::iter(),
+        };
+        let entries: Vec<_> = iter
+            .take(limit as usize)
+            .map(|(eth_address, info)| (eth_address, info.username.into_inner()))
+            .collect();
+        let next_cursor = match entries.last() {
+            Some((eth_address, _)) if entries.len() == limit as usize => Some(*eth_address),
+            _ => None,
+        };
+        Ok(ScanPage {
+            entries,
+            next_cursor,
+        })
+    }
+
+    /// Up to `limit` registered usernames that start with `prefix`, compared case-insensitively,
+    /// continuing after the normalized username `start_key`.
+    ///
+    /// This walks [`UsernameOwners`] in storage key order, so it costs O(n) in the number of
+    /// registrations. Each call visits at most [`MAX_RPC_SCAN`] keys: if it stops before the
+    /// end of the map, because it found `limit` matches or hit that cap, the last visited name
+    /// is returned as the cursor, even on a page with no matches. An empty `prefix` matches
+    /// nothing.
+    pub fn search_usernames(
+        prefix: Vec<u8>,
+        start_key: Option<Vec<u8>>,
+        limit: u32,
+    ) -> Result<ScanPage<Vec<u8>>, ScanError> {
+        Self::check_scan_limit(limit)?;
+        let iter = match start_key {
+            Some(key) => {
+                let key: BoundedVec<u8, T::MaxUsernameLength> =
+                    key.try_into().map_err(|_| ScanError::InvalidCursor)?;
+                UsernameOwners::<T>::iter_from(UsernameOwners::<T>::hashed_key_for(key))
+            }
+            None => UsernameOwners::<T>::iter(),
+        };
+        let mut page = ScanPage {
+            entries: Vec::new(),
+            next_cursor: None,
+        };
+        if prefix.is_empty() {
+            return Ok(page);
+        }
+
+        let prefix = Self::normalize(&prefix);
+        for (visited, (name, eth_address)) in (1..=MAX_RPC_SCAN).zip(iter) {
+            if name.starts_with(&prefix) {
+                if let Some(info) = Usernames::<T>::get(eth_address) {
+                    page.entries.push((eth_address, info.username.into_inner()));
+                }
+            }
+            if page.entries.len() == limit as usize || visited == MAX_RPC_SCAN {
+                page.next_cursor = Some(name.into_inner());
+                break;
+            }
+        }
+        Ok(page)
+    }
+
+    /// Refuse a scan `limit` of zero or above [`MAX_RPC_SCAN`].
+    fn check_scan_limit(limit: u32) -> Result<(), ScanError> {
+        if limit == 0 {
+            return Err(ScanError::ZeroLimit);
+        }
+        if limit > MAX_RPC_SCAN {
+            return Err(ScanError::LimitTooHigh {
+                requested: limit,
+                max: MAX_RPC_SCAN,
+            });
+        }
+        Ok(())
+    }
+
+    /// Check `username` against the length and character set rules.
+    fn validate_username(
+        username: Vec<u8>,
+    ) -> Result<BoundedVec<u8, T::MaxUsernameLength>, Error<T>> {
+        let bounded_username: BoundedVec<u8, T::MaxUsernameLength> = username
+            .try_into()
+            .map_err(|_| Error::<T>::UsernameTooLong)?;
+
+        ensure!(
+            bounded_username.len() >= T::MinUsernameLength::get() as usize,
+            Error::<T>::UsernameTooShort
+        );
+
+        ensure!(
+            !bounded_username.is_empty() && T::UsernameValidator::is_valid(&bounded_username),
+            Error::<T>::InvalidUsername
+        );
+
+        Ok(bounded_username)
+    }
+
+    /// Ensure `username` is either unclaimed or already owned by `eth_address`.
+    fn ensure_available(
+        eth_address: &H160,
+        username: &BoundedVec<u8, T::MaxUsernameLength>,
+    ) -> Result<(), Error<T>> {
+        let key = Self::owner_key(username);
+        if let Some(owner) = UsernameOwners::<T>::get(&key) {
+            ensure!(&owner == eth_address, Error::<T>::UsernameTaken);
+        }
+        // Substrate accounts claim names from the same namespace.
+        ensure!(
+            !SubstrateUsernameOwners::<T>::contains_key(&key),
+            Error::<T>::UsernameTaken
+        );
+        Ok(())
+    }
+
+    /// Store `username` for `eth_address`, keeping the [`UsernameOwners`] reverse index and the
+    /// [`ExpiryQueue`] in sync.
+    ///
+    /// Any username previously held by `eth_address` is released from the reverse index and
+    /// returned. [`UsernameCount`] only grows when `eth_address` had no username before.
+    fn insert_username(
+        eth_address: &H160,
+        username: BoundedVec<u8, T::MaxUsernameLength>,
+        expires_at: Option<BlockNumberFor<T>>,
+    ) -> Option<BoundedVec<u8, T::MaxUsernameLength>> {
+        let previous = Usernames::<T>::get(eth_address);
+        match &previous {
+            Some(previous) => {
+                UsernameOwners::<T>::remove(Self::owner_key(&previous.username));
+                if let Some(block) = previous.expires_at {
+                    ExpiryQueue::<T>::remove(block, eth_address);
+                }
+            }
+            None => {
+                UsernameCount::<T>::mutate(|count| *count = count.saturating_add(1));
+                RegisteredAt::<T>::insert(eth_address, frame_system::Pallet::<T>::block_number());
+            }
+        }
+        UsernameOwners::<T>::insert(Self::owner_key(&username), eth_address);
+        if let Some(block) = expires_at {
+            ExpiryQueue::<T>::insert(block, eth_address, ());
+        }
+        Self::record_history(eth_address, username.clone());
+        Usernames::<T>::insert(
+            eth_address,
+            UsernameInfo {
+                username,
+                expires_at,
+            },
+        );
+        previous.map(|previous| previous.username)
+    }
+
+    /// Remove the username held by `eth_address` together with its reverse index and expiry
+    /// entries, keeping [`UsernameCount`] in sync and returning any deposit to the account that
+    /// paid it. Returns the removed username.
+    fn remove_username(eth_address: &H160) -> Option<BoundedVec<u8, T::MaxUsernameLength>> {
+        let removed = Usernames::<T>::take(eth_address).map(|info| {
+            UsernameOwners::<T>::remove(Self::owner_key(&info.username));
+            if let Some(block) = info.expires_at {
+                ExpiryQueue::<T>::remove(block, eth_address);
+            }
+            UsernameCount::<T>::mutate(|count| *count = count.saturating_sub(1));
+            info.username
+        });
+        if let Some((depositor, deposit)) = UsernameDeposits::<T>::take(eth_address) {
+            T::Currency::unreserve(&depositor, deposit);
+        }
+        Profiles::<T>::remove(eth_address);
+        RegisteredAt::<T>::remove(eth_address);
+        Self::unlink_aliases(eth_address);
+        if removed.is_some() {
+            Self::record_history(eth_address, BoundedVec::new());
+        }
+        removed
+    }
+
+    /// Unlink every alias of `primary` along with its [`AliasOf`] entry. Aliases are signed for
+    /// a specific primary, so they are dropped rather than carried along when its username
+    /// goes away or moves.
+    fn unlink_aliases(primary: &H160) {
+        let aliases = Aliases::<T>::take(primary);
+        for alias in &aliases {
+            AliasOf::<T>::remove(alias);
+        }
+        if !aliases.is_empty() {
+            Self::deposit_event(Event::AliasesCleared {
+                primary: *primary,
+                count: aliases.len() as u32,
+            });
+        }
+    }
+
+    /// Check that `nonce` may be used for `eth_address` and return the nonce to store after
+    /// the call succeeds.
+    ///
+    /// `nonce` must equal the current nonce, or with [`Config::AllowNonceSkip`] be at least
+    /// it. Storing `nonce + 1` also retires every skipped value.
+    fn check_nonce(eth_address: &H160, nonce: u64) -> Result<u64, Error<T>> {
+        let expected_nonce = Nonces::<T>::get(eth_address);
+        if T::AllowNonceSkip::get() {
+            ensure!(nonce >= expected_nonce, Error::<T>::InvalidNonce);
+        } else {
+            ensure!(nonce == expected_nonce, Error::<T>::InvalidNonce);
+        }
+        nonce.checked_add(1).ok_or(Error::<T>::NonceOverflow)
+    }
+
+    /// Move the username of `from`, with everything attached to it, to `to`. The caller checks
+    /// that `to` has no username. Returns the moved username.
+    fn move_username(from: &H160, to: &H160) -> Option<BoundedVec<u8, T::MaxUsernameLength>> {
+        let info = Usernames::<T>::take(from)?;
+        UsernameOwners::<T>::insert(Self::owner_key(&info.username), to);
+        if let Some(block) = info.expires_at {
+            ExpiryQueue::<T>::remove(block, from);
+            ExpiryQueue::<T>::insert(block, to, ());
+        }
+        if let Some(deposit) = UsernameDeposits::<T>::take(from) {
+            UsernameDeposits::<T>::insert(to, deposit);
+        }
+        if let Some(block) = RegisteredAt::<T>::take(from) {
+            RegisteredAt::<T>::insert(to, block);
+        }
+        if let Some(profile) = Profiles::<T>::take(from) {
+            Profiles::<T>::insert(to, profile);
+        }
+        Self::unlink_aliases(from);
+        // The recipient starts its own cooldown, so a transfer can't be used to skip one.
+        LastUsernameChange::<T>::insert(to, frame_system::Pallet::<T>::block_number());
+
+        let username = info.username.clone();
+        Self::record_history(from, BoundedVec::new());
+        Self::record_history(to, username.clone());
+        Usernames::<T>::insert(to, info);
+        Some(username)
+    }
+
+    /// Append `(username, now)` to the history of `eth_address`, dropping the oldest entry if
+    /// the history is full.
+    fn record_history(eth_address: &H160, username: BoundedVec<u8, T::MaxUsernameLength>) {
+        let now = frame_system::Pallet::<T>::block_number();
+        UsernameHistory::<T>::mutate(eth_address, |history| {
+            if history.is_full() && !history.is_empty() {
+                history.remove(0);
+            }
+            // Only fails for a `MaxHistory` of zero, which keeps no history.
+            let _ = history.try_push((username, now));
+        });
+    }
+
+    /// Remove usernames whose expiry block is at or before `now`, walking [`ExpiryQueue`] from
+    /// [`ExpiryCursor`] and spending at most `MaxExpiriesPerBlock` units of work. Every block
+    /// visited costs one unit, so a lagging cursor cannot make a single block unbounded.
+    ///
+    /// Each removal is charged [`WeightInfo::expire_username`] for the aliases it unlinks, on
+    /// top of the queue reads and writes counted here.
+    fn expire_usernames(now: BlockNumberFor<T>) -> Weight {
+        let mut budget = T::MaxExpiriesPerBlock::get();
+        let mut cursor = ExpiryCursor::<T>::get().unwrap_or(now);
+        let (mut reads, mut writes) = (1u64, 0u64);
+        let mut removals = Weight::zero();
+
+        while cursor <= now && budget > 0 {
+            let due: Vec<H160> = ExpiryQueue::<T>::iter_key_prefix(cursor)
+                .take(budget as usize)
+                .collect();
+            reads += 1;
+            budget = budget.saturating_sub((due.len() as u32).max(1));
+
+            for eth_address in &due {
+                ExpiryQueue::<T>::remove(cursor, eth_address);
+                reads += 1;
+                writes += 1;
+                let expired = Usernames::<T>::get(eth_address)
+                    .is_some_and(|info| info.expires_at == Some(cursor));
+                if expired {
+                    let aliases = Aliases::<T>::decode_len(eth_address).unwrap_or(0) as u32;
+                    if let Some(username) = Self::remove_username(eth_address) {
+                        removals.saturating_accrue(T::WeightInfo::expire_username(aliases));
+                        Self::deposit_event(Event::UsernameExpired {
+                            eth_address: *eth_address,
+                            username,
+                        });
+                    }
+                }
+            }
+
+            // Only move on once this block's queue is known to be empty.
+            if budget > 0 || due.is_empty() {
+                cursor = cursor.saturating_add(1u32.into());
+            }
+        }
+
+        ExpiryCursor::<T>::put(cursor);
+        writes += 1;
+        T::DbWeight::get()
+            .reads_writes(reads, writes)
+            .saturating_add(removals)
+    }
+
+    /// Normalize a username for uniqueness checks by lowercasing ASCII letters.
+    fn normalize(name: &[u8]) -> Vec<u8> {
+        name.to_ascii_lowercase()
+    }
+
+    /// The [`UsernameOwners`] key for `username`.
+    fn owner_key(
+        username: &BoundedVec<u8, T::MaxUsernameLength>,
+    ) -> BoundedVec<u8, T::MaxUsernameLength> {
+        // Lowercasing preserves the length, so this never truncates.
+        BoundedVec::truncate_from(Self::normalize(username))
+    }
+
+    /// Shared implementation of `set_username` and `set_username_with_scheme`. `who` is the
+    /// extrinsic signer, who pays the deposit.
+    fn do_set_username(
+        who: T::AccountId,
+        eth_address: H160,
+        username: Vec<u8>,
+        nonce: u64,
+        scheme: SigScheme,
+        signature: Vec<u8>,
+    ) -> DispatchResultWithPostInfo {
+        // A call rejected here never reaches the signature recovery, so it is only charged
+        // for the reads of the checks.
+        let (next_nonce, current_block, bounded_username) =
+            Self::check_set_username(&eth_address, &username, nonce)
+                .map_err(|error| error.with_weight(Self::set_username_rejected_weight()))?;
+
+        // Build the signed message from the raw bytes so it matches what gets stored.
+        let message = set_username_message(T::ChainId::get(), &username, nonce);
+        if scheme == SigScheme::EthEip712 {
+            Self::verify_ethereum_signature_712(&eth_address, &username, nonce, &signature)?;
+        } else {
+            Self::verify_signature(&scheme, &eth_address, &message, &signature)?;
+        }
+
+        let deposit = if UsernameDeposits::<T>::contains_key(&eth_address) {
+            Zero::zero()
+        } else {
+            let deposit = T::UsernameDeposit::get();
+            T::Currency::reserve(&who, deposit)?;
+            UsernameDeposits::<T>::insert(&eth_address, (who.clone(), deposit));
+            deposit
+        };
+
+        // Store
+        RegistrationsThisBlock::<T>::mutate(|count| *count = count.saturating_add(1));
+        Nonces::<T>::insert(&eth_address, next_nonce);
+        LastUsernameChange::<T>::insert(&eth_address, current_block);
+        // A name always lives for at least one block, so it expires after the sweep that
+        // already ran for `current_block`.
+        let expires_at =
+            T::UsernameTtl::get().map(|ttl| current_block.saturating_add(ttl.max(1u32.into())));
+        let old_username =
+            Self::insert_username(&eth_address, bounded_username.clone(), expires_at);
+
+        let message_hash = match scheme {
+            _ if !T::EmitMessageHash::get() => None,
+            SigScheme::EthEcdsa => Some(eth_message_hash(&message)),
+            SigScheme::EthEip712 => Some(eip712_set_username_hash(
+                T::ChainId::get(),
+                &eth_address,
+                &username,
+                nonce,
+            )),
+            SigScheme::Ed25519(_) | SigScheme::Sr25519(_) => None,
+        };
+        Self::deposit_event(Event::UsernameSet {
+            eth_address,
+            username: bounded_username.clone(),
+            submitter: who,
+            deposit,
+            message_hash,
+        });
+        Self::deposit_event(Event::UsernameUpdated {
+            eth_address,
+            old_username,
+            new_username: bounded_username,
+        });
+
+        Ok(().into())
+    }
+
+    /// The weight charged for a `set_username` rejected by [`Self::check_set_username`],
+    /// before its signature is checked.
+    fn set_username_rejected_weight() -> Weight {
+        // Paused, RegistrationsThisBlock, Blocklist, Allowlist, AliasOf, Nonces,
+        // LastUsernameChange, UsernameOwners, SubstrateUsernameOwners and ReservedUsernames.
+        T::DbWeight::get().reads(10)
+    }
+
+    /// The checks of `set_username` that run before the signature is verified. Returns the
+    /// next nonce, the current block and the bounded username.
+    fn check_set_username(
+        eth_address: &H160,
+        username: &[u8],
+        nonce: u64,
+    ) -> Result<(u64, BlockNumberFor<T>, BoundedVec<u8, T::MaxUsernameLength>), DispatchError> {
+        ensure!(!Paused::<T>::get(), Error::<T>::PalletPaused);
+        ensure!(
+            RegistrationsThisBlock::<T>::get() < T::MaxRegistrationsPerBlock::get(),
+            Error::<T>::TooManyRegistrationsThisBlock
+        );
+        ensure!(!eth_address.is_zero(), Error::<T>::InvalidAddress);
+        ensure!(
+            !Blocklist::<T>::contains_key(eth_address),
+            Error::<T>::AddressBlocked
+        );
+        ensure!(
+            !T::RequireAllowlist::get() || Allowlist::<T>::contains_key(eth_address),
+            Error::<T>::NotAllowed
+        );
+        ensure!(
+            !AliasOf::<T>::contains_key(eth_address),
+            Error::<T>::AliasAlreadyLinked
+        );
+
+        let next_nonce = Self::check_nonce(eth_address, nonce)?;
+
+        let current_block = frame_system::Pallet::<T>::block_number();
+        Self::ensure_cooldown_passed(eth_address, current_block)?;
+
+        let bounded_username = Self::validate_username(username.to_vec())?;
+        Self::ensure_available(eth_address, &bounded_username)?;
+        ensure!(
+            !ReservedUsernames::<T>::contains_key(Self::owner_key(&bounded_username)),
+            Error::<T>::UsernameReserved
+        );
+
+        Ok((next_nonce, current_block, bounded_username))
+    }
+
+    /// Fail with [`Error::ChangeTooSoon`] unless `ChangeCooldown` blocks have passed since the
+    /// username of `eth_address` last changed.
+    fn ensure_cooldown_passed(eth_address: &H160, now: BlockNumberFor<T>) -> DispatchResult {
+        if let Some(last_change) = LastUsernameChange::<T>::get(eth_address) {
+            ensure!(
+                now >= last_change.saturating_add(T::ChangeCooldown::get()),
+                Error::<T>::ChangeTooSoon
+            );
+        }
+        Ok(())
+    }
+
+    /// Check that `signature` over `message` was produced by the owner of `eth_address` under
+    /// `scheme`.
+    ///
+    /// For [`SigScheme::EthEip712`], `message` is the 32-byte typed-data digest itself.
+    ///
+    /// Ethereum schemes fail with [`Error::SignatureRecoveryFailed`] or
+    /// [`Error::SignatureAddressMismatch`], the others with [`Error::InvalidSignature`].
+    pub fn verify_signature(
+        scheme: &SigScheme,
+        eth_address: &H160,
+        message: &[u8],
+        signature: &[u8],
+    ) -> Result<(), Error<T>> {
+        let valid = match scheme {
+            SigScheme::EthEcdsa => {
+                return Self::verify_ethereum_signature(eth_address, message, signature)
+            }
+            SigScheme::EthEip712 => {
+                let hash = <[u8; 32]>::try_from(message)
+                    .map_err(|_| Error::<T>::SignatureRecoveryFailed)?;
+                return verify_eth_signature_prehashed(eth_address, &hash, signature)
+                    .map_err(Into::into);
+            }
+            SigScheme::Ed25519(public) => {
+                substrate_key_address(public) == *eth_address
+                    && ed25519::Signature::try_from(signature).is_ok_and(|signature| {
+                        sp_io::crypto::ed25519_verify(
+                            &signature,
+                            message,
+                            &ed25519::Public::from_raw(*public),
+                        )
+                    })
+            }
+            SigScheme::Sr25519(public) => {
+                substrate_key_address(public) == *eth_address
+                    && sr25519::Signature::try_from(signature).is_ok_and(|signature| {
+                        sp_io::crypto::sr25519_verify(
+                            &signature,
+                            message,
+                            &sr25519::Public::from_raw(*public),
+                        )
+                    })
+            }
+        };
+        ensure!(
+            !eth_address.is_zero() && valid,
+            Error::<T>::InvalidSignature
+        );
+        Ok(())
+    }
+
+    /// Check an EIP-712 `SetUsername { owner: eth_address, username, nonce }` signature under
+    /// this chain's domain.
+    pub fn verify_ethereum_signature_712(
+        eth_address: &H160,
+        username: &[u8],
+        nonce: u64,
+        signature: &[u8],
+    ) -> Result<(), Error<T>> {
+        let hash = eip712_set_username_hash(T::ChainId::get(), eth_address, username, nonce);
+        Self::verify_signature(&SigScheme::EthEip712, eth_address, &hash, signature)
+    }
+
+    /// Check that the `personal_sign` signature over `message` was made by `eth_address`.
+    ///
+    /// Neither the expected nor the recovered address may be zero, so a degenerate recovery
+    /// can never match a zero `eth_address`.
+    pub fn verify_ethereum_signature(
+        eth_address: &H160,
+        message: &[u8],
+        signature: &[u8],
+    ) -> Result<(), Error<T>> {
+        verify_eth_signature(eth_address, message, signature).map_err(Into::into)
+    }
+
+    /// The persistent offchain local storage key holding the SCALE-encoded `H160` owner of
+    /// `username`, written by the offchain worker when [`Config::OffchainIndexing`] is set.
+    pub fn offchain_owner_key(username: &[u8]) -> Vec<u8> {
+        [OFFCHAIN_OWNER_PREFIX, &Self::normalize(username)].concat()
+    }
+
+    /// Mirror the username changes of the current block into offchain local storage.
+    ///
+    /// The addresses to refresh are taken from this block's events. On its first run the worker
+    /// also indexes every registered address, so names set at genesis or before indexing was
+    /// enabled are found too. Besides the owner entries at [`Self::offchain_owner_key`], each
+    /// address keeps its indexed name so the owner entry can be removed again when the name
+    /// changes or is cleared.
+    fn index_usernames_offchain() {
+        let mut backfilled = StorageValueRef::persistent(OFFCHAIN_BACKFILLED_KEY);
+        let needs_backfill = !matches!(backfilled.get::<bool>(), Ok(Some(true)));
+
+        let mut touched: scale_info::prelude::collections::BTreeSet<H160> =
+            frame_system::Pallet::<T>::read_events_no_consensus()
+                .filter_map(|record| {
+                    let event: <T as Config>::RuntimeEvent = record.event.into();
+                    TryInto::<Event<T>>::try_into(event).ok()
+                })
+                .flat_map(|event| match event {
+                    Event::UsernameSet { eth_address, .. }
+                    | Event::UsernameUpdated { eth_address, .. }
+                    | Event::UsernameCleared { eth_address }
+                    | Event::UsernameForceSet { eth_address, .. }
+                    | Event::UsernameForceCleared { eth_address }
+                    | Event::UsernameExpired { eth_address, .. } => vec![eth_address],
+                    Event::UsernameTransferred { from, to, .. } => vec![from, to],
+                    _ => vec![],
+                })
+                .collect();
+        if needs_backfill {
+            touched.extend(Usernames::<T>::iter_keys());
+        }
+
+        for eth_address in touched {
+            let name_key = [OFFCHAIN_NAME_PREFIX, eth_address.as_bytes()].concat();
+            let mut indexed_name = StorageValueRef::persistent(&name_key);
+            if let Ok(Some(old)) = indexed_name.get::<Vec<u8>>() {
+                StorageValueRef::persistent(&Self::offchain_owner_key(&old)).clear();
+            }
+
+            match Usernames::<T>::get(eth_address) {
+                Some(info) => {
+                    let owner_key = Self::offchain_owner_key(&info.username);
+                    StorageValueRef::persistent(&owner_key).set(&eth_address);
+                    indexed_name.set(&Self::normalize(&info.username));
+                }
+                None => indexed_name.clear(),
+            }
+        }
+
+        if needs_backfill {
+            backfilled.set(&true);
+        }
+    }
+
+    /// Check that the username maps agree with each other:
+    ///
+    /// * every [`Usernames`] entry has a [`UsernameOwners`] entry pointing back to its address,
+    ///   and there are no other [`UsernameOwners`] entries,
+    /// * no two addresses own the same normalized username,
+    /// * [`UsernameCount`] equals the number of [`Usernames`] entries.
+    #[cfg(any(feature = "try-runtime", test))]
+    pub fn do_try_state() -> Result<(), TryRuntimeError> {
+        let mut names = scale_info::prelude::collections::BTreeSet::new();
+        let mut count: u32 = 0;
+        for (eth_address, info) in Usernames::<T>::iter() {
+            let key = Self::owner_key(&info.username);
+            ensure!(
+                UsernameOwners::<T>::get(&key) == Some(eth_address),
+                "username has no matching UsernameOwners entry"
+            );
+            ensure!(
+                names.insert(key),
+                "two addresses own the same normalized username"
+            );
+            count += 1;
+        }
+
+        ensure!(
+            UsernameOwners::<T>::iter_keys().count() == names.len(),
+            "UsernameOwners has entries without a matching username"
+        );
+        ensure!(
+            UsernameCount::<T>::get() == count,
+            "UsernameCount does not match the number of usernames"
+        );
+        Ok(())
     }
 }
@@ -0,0 +1,59 @@
+//! Storage migrations for the template pallet.
+
+use super::*;
+use frame_support::traits::{GetStorageVersion, OnRuntimeUpgrade, StorageVersion};
+
+#[cfg(feature = "try-runtime")]
+use frame_support::sp_runtime::TryRuntimeError;
+
+/// Migration to storage version 2.
+pub mod v2 {
+    use super::*;
+
+    /// The unused `u32` demo value the pallet declared before storage version 2.
+    #[frame_support::storage_alias]
+    pub type Something<T: Config> = StorageValue<Pallet<T>, u32>;
+}
+
+/// Remove the dead `Something` storage item and move the pallet to storage version 2.
+///
+/// Releases before storage version 2 did not declare a storage version at all, so a live chain
+/// reports version 0 here. The migration therefore runs for any on-chain version below 2, rather
+/// than through a `VersionedMigration` pinned to a version the chain never recorded, and does
+/// nothing once the chain is at version 2.
+pub struct MigrateToV2<T>(core::marker::PhantomData<T>);
+
+impl<T: Config> OnRuntimeUpgrade for MigrateToV2<T> {
+    fn on_runtime_upgrade() -> Weight {
+        if Pallet::<T>::on_chain_storage_version() >= 2 {
+            return T::DbWeight::get().reads(1);
+        }
+
+        v2::Something::<T>::kill();
+        StorageVersion::new(2).put::<Pallet<T>>();
+
+        T::DbWeight::get().reads_writes(1, 2)
+    }
+
+    #[cfg(feature = "try-runtime")]
+    fn pre_upgrade() -> Result<Vec<u8>, TryRuntimeError> {
+        ensure!(
+            Pallet::<T>::on_chain_storage_version() < 2 || !v2::Something::<T>::exists(),
+            "`Something` should already be gone at storage version 2"
+        );
+        Ok(Vec::new())
+    }
+
+    #[cfg(feature = "try-runtime")]
+    fn post_upgrade(_state: Vec<u8>) -> Result<(), TryRuntimeError> {
+        ensure!(
+            !v2::Something::<T>::exists(),
+            "`Something` should have been removed"
+        );
+        ensure!(
+            Pallet::<T>::on_chain_storage_version() == 2,
+            "the pallet should be at storage version 2"
+        );
+        Ok(())
+    }
+}
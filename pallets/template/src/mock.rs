@@ -1,6 +1,12 @@
 use crate as pallet_template;
-use frame_support::derive_impl;
+use crate::{AlphanumericUnderscore, ValidateUsername};
+use frame_support::{derive_impl, parameter_types};
+use sp_core::{ecdsa, Pair, H160};
+use sp_keystore::{testing::MemoryKeystore, KeystoreExt};
 use sp_runtime::BuildStorage;
+use username_primitives::{
+	eth_message_hash, recover_eth_address, set_username_message, ETH_V_OFFSET, SIGNATURE_LEN,
+};
 
 type Block = frame_system::mocking::MockBlock<Test>;
 
@@ -28,19 +34,176 @@ mod runtime {
 
 	#[runtime::pallet_index(1)]
 	pub type Template = pallet_template::Pallet<Test>;
+
+	#[runtime::pallet_index(2)]
+	pub type Balances = pallet_balances::Pallet<Test>;
 }
 
 #[derive_impl(frame_system::config_preludes::TestDefaultConfig)]
 impl frame_system::Config for Test {
 	type Block = Block;
+	type AccountData = pallet_balances::AccountData<u64>;
+}
+
+#[derive_impl(pallet_balances::config_preludes::TestDefaultConfig)]
+impl pallet_balances::Config for Test {
+	type AccountStore = System;
+}
+
+/// Free balance given to each of the first few test accounts at genesis.
+pub const INITIAL_BALANCE: u64 = 1_000;
+
+parameter_types! {
+	pub static MaxUsernameLength: u32 = 32;
+	pub static MinUsernameLength: u32 = 3;
+	pub static ChainId: u64 = 1;
+	pub static ChangeCooldown: u64 = 0;
+	pub static UsernameDeposit: u64 = 10;
+	pub static UsernameTtl: Option<u64> = None;
+	pub static MaxExpiriesPerBlock: u32 = 2;
+	pub static MaxAliases: u32 = 2;
+	pub static MaxHistory: u32 = 2;
+	pub static RequireAllowlist: bool = false;
+	pub static OffchainIndexing: bool = true;
+	pub static AllowHyphenAndDot: bool = false;
+	pub static AllowNonceSkip: bool = false;
+	pub static MaxRegistrationsPerBlock: u32 = 100;
+	pub static EmitMessageHash: bool = false;
+}
+
+/// The default username rules, or ENS-like rules that also accept `-` and `.` while
+/// [`AllowHyphenAndDot`] is set.
+pub struct MockUsernameValidator;
+
+impl ValidateUsername for MockUsernameValidator {
+	fn is_valid(name: &[u8]) -> bool {
+		if !AllowHyphenAndDot::get() {
+			return AlphanumericUnderscore::is_valid(name);
+		}
+		name.iter().all(|&c| c.is_ascii_alphanumeric() || matches!(c, b'_' | b'-' | b'.'))
+			&& name.first().is_some_and(u8::is_ascii_alphabetic)
+	}
+
+	fn description() -> &'static str {
+		if AllowHyphenAndDot::get() {
+			"ASCII letters, digits, `_`, `-` and `.`, starting with a letter"
+		} else {
+			AlphanumericUnderscore::description()
+		}
+	}
 }
 
 impl pallet_template::Config for Test {
 	type RuntimeEvent = RuntimeEvent;
+	type MaxUsernameLength = MaxUsernameLength;
+	type MinUsernameLength = MinUsernameLength;
+	type UsernameValidator = MockUsernameValidator;
+	type ChainId = ChainId;
+	type ChangeCooldown = ChangeCooldown;
+	type Currency = Balances;
+	type UsernameDeposit = UsernameDeposit;
+	type UsernameTtl = UsernameTtl;
+	type MaxExpiriesPerBlock = MaxExpiriesPerBlock;
+	type MaxAliases = MaxAliases;
+	type MaxHistory = MaxHistory;
+	type RequireAllowlist = RequireAllowlist;
+	type AllowNonceSkip = AllowNonceSkip;
+	type OffchainIndexing = OffchainIndexing;
+	type MaxRegistrationsPerBlock = MaxRegistrationsPerBlock;
+	type EmitMessageHash = EmitMessageHash;
 	type WeightInfo = ();
 }
 
+/// Builds test externalities for a mock runtime with the constants a test needs.
+///
+/// The constants are thread-local statics, which [`Self::build`] sets before building genesis
+/// so genesis usernames are checked against them too. Every test runs on its own thread, so
+/// they don't leak into other tests.
+pub struct ExtBuilder {
+	max_username_length: u32,
+	min_username_length: u32,
+	change_cooldown: u64,
+	usernames: Vec<(H160, Vec<u8>)>,
+}
+
+impl Default for ExtBuilder {
+	fn default() -> Self {
+		Self {
+			max_username_length: 32,
+			min_username_length: 3,
+			change_cooldown: 0,
+			usernames: vec![],
+		}
+	}
+}
+
+impl ExtBuilder {
+	pub fn max_username_length(mut self, max_username_length: u32) -> Self {
+		self.max_username_length = max_username_length;
+		self
+	}
+
+	pub fn min_username_length(mut self, min_username_length: u32) -> Self {
+		self.min_username_length = min_username_length;
+		self
+	}
+
+	pub fn change_cooldown(mut self, change_cooldown: u64) -> Self {
+		self.change_cooldown = change_cooldown;
+		self
+	}
+
+	/// Preload `usernames` through the pallet's genesis config.
+	pub fn usernames(mut self, usernames: Vec<(H160, Vec<u8>)>) -> Self {
+		self.usernames = usernames;
+		self
+	}
+
+	pub fn build(self) -> sp_io::TestExternalities {
+		MaxUsernameLength::set(self.max_username_length);
+		MinUsernameLength::set(self.min_username_length);
+		ChangeCooldown::set(self.change_cooldown);
+
+		let mut ext: sp_io::TestExternalities = RuntimeGenesisConfig {
+			system: Default::default(),
+			balances: pallet_balances::GenesisConfig {
+				balances: (1..=3).map(|account| (account, INITIAL_BALANCE)).collect(),
+				..Default::default()
+			},
+			template: pallet_template::GenesisConfig {
+				usernames: self.usernames,
+				..Default::default()
+			},
+		}
+		.build_storage()
+		.unwrap()
+		.into();
+		// The benchmarks generate and sign with ECDSA keys through the keystore host functions.
+		ext.register_extension(KeystoreExt::new(MemoryKeystore::new()));
+		ext
+	}
+}
+
 // Build genesis storage according to the mock runtime.
 pub fn new_test_ext() -> sp_io::TestExternalities {
-	frame_system::GenesisConfig::<Test>::default().build_storage().unwrap().into()
+	ExtBuilder::default().build()
+}
+
+// Build genesis storage with `usernames` preloaded by the pallet's genesis config.
+pub fn new_test_ext_with_usernames(usernames: Vec<(H160, Vec<u8>)>) -> sp_io::TestExternalities {
+	ExtBuilder::default().usernames(usernames).build()
+}
+
+/// Sign the `set_username` message for `username` and `nonce` on the mock chain with the ECDSA
+/// key `seed`, like the `signature` CLI does.
+///
+/// Returns the key's Ethereum address and the 65-byte signature with `v` in the 27/28
+/// convention.
+pub fn sign_set_username(seed: [u8; 32], username: &[u8], nonce: u64) -> (H160, Vec<u8>) {
+	let pair = ecdsa::Pair::from_seed(&seed);
+	let message = set_username_message(ChainId::get(), username, nonce);
+	let mut signature = pair.sign_prehashed(&eth_message_hash(&message)).0.to_vec();
+	signature[SIGNATURE_LEN - 1] += ETH_V_OFFSET;
+	let address = recover_eth_address(&message, &signature).expect("signature was just made");
+	(address, signature)
 }
@@ -1,24 +1,2511 @@
-use crate::{mock::*, Error, Event, Something};
-use frame_support::{assert_noop, assert_ok};
+use crate::{
+	migrations::{self, MigrateToV2},
+	mock::*,
+	AddressProfile, AliasOf, Aliases, AlphanumericUnderscore, CheckUsernameNonce, Error, Event,
+	LastUsernameChange, Nonces, ProfileMetadata, Profiles, ScanError, ScanPage, SigScheme,
+	UsernameCount, UsernameDeposits, UsernameOwners, Usernames, ValidateUsername, WeightInfo,
+	MAX_RPC_SCAN,
+};
+use codec::Encode;
+use frame_support::{
+	assert_noop, assert_ok,
+	dispatch::{DispatchInfo, DispatchResult, WithPostDispatchInfo},
+	traits::{GetStorageVersion, Hooks, OnRuntimeUpgrade, StorageVersion},
+};
+use sp_core::{
+	ecdsa, ed25519,
+	offchain::{testing::TestOffchainExt, OffchainDbExt, OffchainWorkerExt},
+	sr25519, Pair, H160,
+};
+use sp_runtime::{
+	offchain::storage::StorageValueRef,
+	traits::{TransactionExtension, TxBaseImplication},
+	transaction_validity::{
+		InvalidTransaction, TransactionSource, TransactionValidity, ValidTransaction,
+	},
+	DispatchError,
+};
+use username_primitives::{
+	eip712_set_username_hash, eth_address, eth_message_hash, substrate_key_address,
+};
+
+/// Sign `message` the way an Ethereum wallet would with `personal_sign`.
+fn eth_sign(pair: &ecdsa::Pair, message: &[u8]) -> Vec<u8> {
+	let mut signature = pair.sign_prehashed(&eth_message_hash(message)).0.to_vec();
+	signature[64] += 27;
+	signature
+}
+
+/// Derive a deterministic test wallet and the address the pallet recovers for it.
+fn eth_account(seed: u8) -> (ecdsa::Pair, H160) {
+	let pair = ecdsa::Pair::from_seed(&[seed; 32]);
+	let hash = [0u8; 32];
+	let signature = pair.sign_prehashed(&hash);
+	let pubkey = sp_io::crypto::secp256k1_ecdsa_recover(&signature.0, &hash).unwrap();
+	(pair, eth_address(&pubkey))
+}
+
+fn set_username_message(username: &[u8], nonce: u64) -> Vec<u8> {
+	username_primitives::set_username_message(ChainId::get(), username, nonce)
+}
+
+fn set_username(pair: &ecdsa::Pair, eth_address: H160, username: &[u8]) -> DispatchResult {
+	let nonce = Nonces::<Test>::get(eth_address);
+	let signature = eth_sign(pair, &set_username_message(username, nonce));
+	Template::set_username(
+		RuntimeOrigin::signed(1),
+		eth_address,
+		username.to_vec(),
+		nonce,
+		signature,
+	)
+	.map(|_| ())
+	.map_err(|e| e.error)
+}
 
 #[test]
-fn it_works_for_default_value() {
+fn set_username_works() {
 	new_test_ext().execute_with(|| {
 		// Go past genesis block so events get deposited
 		System::set_block_number(1);
-		// Dispatch a signed extrinsic.
-		assert_ok!(Template::do_something(RuntimeOrigin::signed(1), 42));
-		// Read pallet storage and assert an expected result.
-		assert_eq!(Something::<Test>::get(), Some(42));
-		// Assert that the correct event was deposited
-		System::assert_last_event(Event::SomethingStored { something: 42, who: 1 }.into());
+		let (pair, address) = eth_account(1);
+
+		assert_ok!(set_username(&pair, address, b"alice"));
+
+		assert_eq!(Template::get_username(address), Some(b"alice".to_vec()));
+		assert_eq!(Nonces::<Test>::get(address), 1);
+		System::assert_has_event(
+			Event::UsernameSet {
+				eth_address: address,
+				username: b"alice".to_vec().try_into().unwrap(),
+				submitter: 1,
+				deposit: 10,
+				message_hash: None,
+			}
+			.into(),
+		);
+	});
+}
+
+#[test]
+fn username_set_carries_message_hash_when_enabled() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		EmitMessageHash::set(true);
+		let (pair, address) = eth_account(1);
+
+		assert_ok!(set_username(&pair, address, b"alice"));
+
+		// Keccak-256 of "\x19Ethereum Signed Message:\n22set_username:1:alice:0".
+		System::assert_has_event(
+			Event::UsernameSet {
+				eth_address: address,
+				username: b"alice".to_vec().try_into().unwrap(),
+				submitter: 1,
+				deposit: 10,
+				message_hash: Some(hex_literal::hex!(
+					"108d4e46859226120769d22db3c9994b17a20f09b9f09782139cc0274c1ccb96"
+				)),
+			}
+			.into(),
+		);
+	});
+}
+
+#[test]
+fn username_set_records_submitter() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let (pair, address) = eth_account(1);
+		assert_ok!(set_username(&pair, address, b"alice"));
+
+		// A relayer other than the deposit holder can submit the owner's signed rename.
+		let signature = eth_sign(&pair, &set_username_message(b"alice2", 1));
+		assert_ok!(Template::set_username(
+			RuntimeOrigin::signed(2),
+			address,
+			b"alice2".to_vec(),
+			1,
+			signature
+		));
+		System::assert_has_event(
+			Event::UsernameSet {
+				eth_address: address,
+				username: b"alice2".to_vec().try_into().unwrap(),
+				submitter: 2,
+				deposit: 0,
+				message_hash: None,
+			}
+			.into(),
+		);
+	});
+}
+
+#[test]
+fn signing_helper_produces_valid_set_username() {
+	new_test_ext().execute_with(|| {
+		let (address, signature) = sign_set_username([7; 32], b"carol", 0);
+		assert_eq!(signature.len(), 65);
+		assert!(matches!(signature[64], 27 | 28));
+
+		assert_ok!(Template::set_username(
+			RuntimeOrigin::signed(1),
+			address,
+			b"carol".to_vec(),
+			0,
+			signature
+		));
+		assert_eq!(Template::get_username(address), Some(b"carol".to_vec()));
+		assert_eq!(Template::get_nonce(address), 1);
+	});
+}
+
+#[test]
+fn set_username_rejects_bad_signature() {
+	new_test_ext().execute_with(|| {
+		let (_, address) = eth_account(1);
+		let (other, _) = eth_account(2);
+		let signature = eth_sign(&other, &set_username_message(b"alice", 0));
+
+		assert_noop!(
+			Template::set_username(
+				RuntimeOrigin::signed(1),
+				address,
+				b"alice".to_vec(),
+				0,
+				signature
+			),
+			Error::<Test>::SignatureAddressMismatch
+		);
+	});
+}
+
+#[test]
+fn set_username_rejects_unrecoverable_signature() {
+	new_test_ext().execute_with(|| {
+		let (pair, address) = eth_account(1);
+		let mut signature = eth_sign(&pair, &set_username_message(b"alice", 0));
+		signature.pop();
+
+		assert_noop!(
+			Template::set_username(
+				RuntimeOrigin::signed(1),
+				address,
+				b"alice".to_vec(),
+				0,
+				signature
+			),
+			Error::<Test>::SignatureRecoveryFailed
+		);
+	});
+}
+
+#[test]
+fn eth_signature_errors_tell_recovery_from_mismatch() {
+	let (pair, address) = eth_account(1);
+	let (other, _) = eth_account(2);
+	let message = set_username_message(b"alice", 0);
+	let mut bad_v = eth_sign(&pair, &message);
+	bad_v[64] = 4;
+
+	assert!(
+		Template::verify_ethereum_signature(&address, &message, &eth_sign(&pair, &message)).is_ok()
+	);
+	assert!(matches!(
+		Template::verify_ethereum_signature(&address, &message, &eth_sign(&other, &message)),
+		Err(Error::<Test>::SignatureAddressMismatch)
+	));
+	assert!(matches!(
+		Template::verify_ethereum_signature(&address, &message, &bad_v),
+		Err(Error::<Test>::SignatureRecoveryFailed)
+	));
+	assert!(matches!(
+		Template::verify_signature(&SigScheme::EthEip712, &address, b"not a digest", &bad_v),
+		Err(Error::<Test>::SignatureRecoveryFailed)
+	));
+}
+
+#[test]
+fn reverse_lookup_tracks_owner() {
+	new_test_ext().execute_with(|| {
+		let (pair, address) = eth_account(1);
+
+		assert_ok!(set_username(&pair, address, b"alice"));
+
+		assert_eq!(Template::get_owner(b"alice".to_vec()), Some(address));
+		assert_eq!(Template::get_owner(b"bob".to_vec()), None);
+	});
+}
+
+#[test]
+fn owner_can_reset_same_username() {
+	new_test_ext().execute_with(|| {
+		let (pair, address) = eth_account(1);
+
+		assert_ok!(set_username(&pair, address, b"alice"));
+		assert_ok!(set_username(&pair, address, b"alice"));
+
+		assert_eq!(Template::get_owner(b"alice".to_vec()), Some(address));
+		assert_eq!(Nonces::<Test>::get(address), 2);
+	});
+}
+
+#[test]
+fn username_taken_by_other_address_is_rejected() {
+	new_test_ext().execute_with(|| {
+		let (alice, alice_address) = eth_account(1);
+		let (bob, bob_address) = eth_account(2);
+
+		assert_ok!(set_username(&alice, alice_address, b"alice"));
+		assert_noop!(set_username(&bob, bob_address, b"alice"), Error::<Test>::UsernameTaken);
+	});
+}
+
+#[test]
+fn changing_username_releases_previous_name() {
+	new_test_ext().execute_with(|| {
+		let (alice, alice_address) = eth_account(1);
+		let (bob, bob_address) = eth_account(2);
+
+		assert_ok!(set_username(&alice, alice_address, b"alice"));
+		assert_ok!(set_username(&alice, alice_address, b"alice_2"));
+
+		assert_eq!(Template::get_owner(b"alice".to_vec()), None);
+		assert_eq!(Template::get_owner(b"alice_2".to_vec()), Some(alice_address));
+		assert_eq!(UsernameOwners::<Test>::iter().count(), Usernames::<Test>::iter().count());
+
+		// The released name can now be claimed by someone else.
+		assert_ok!(set_username(&bob, bob_address, b"alice"));
+		assert_eq!(Template::get_owner(b"alice".to_vec()), Some(bob_address));
+	});
+}
+
+fn clear_username(pair: &ecdsa::Pair, eth_address: H160) -> DispatchResult {
+	let nonce = Nonces::<Test>::get(eth_address);
+	let message =
+		format!("clear_username:{}:{:#x}:{}", ChainId::get(), eth_address, nonce).into_bytes();
+	let signature = eth_sign(pair, &message);
+	Template::clear_username(RuntimeOrigin::signed(1), eth_address, nonce, signature)
+}
+
+#[test]
+fn clear_username_works() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let (pair, address) = eth_account(1);
+		assert_ok!(set_username(&pair, address, b"alice"));
+
+		assert_ok!(clear_username(&pair, address));
+
+		assert_eq!(Template::get_username(address), None);
+		assert_eq!(Template::get_owner(b"alice".to_vec()), None);
+		assert_eq!(Nonces::<Test>::get(address), 2);
+		System::assert_last_event(Event::UsernameCleared { eth_address: address }.into());
+	});
+}
+
+#[test]
+fn clear_username_fails_without_username() {
+	new_test_ext().execute_with(|| {
+		let (pair, address) = eth_account(1);
+
+		assert_noop!(clear_username(&pair, address), Error::<Test>::UsernameNotFound);
+		assert_eq!(Nonces::<Test>::get(address), 0);
+	});
+}
+
+#[test]
+fn replayed_clear_is_rejected() {
+	new_test_ext().execute_with(|| {
+		let (pair, address) = eth_account(1);
+		assert_ok!(set_username(&pair, address, b"alice"));
+
+		let message =
+			format!("clear_username:{}:{:#x}:{}", ChainId::get(), address, 1).into_bytes();
+		let signature = eth_sign(&pair, &message);
+		assert_ok!(Template::clear_username(
+			RuntimeOrigin::signed(1),
+			address,
+			1,
+			signature.clone()
+		));
+
+		assert_ok!(set_username(&pair, address, b"alice"));
+		assert_noop!(
+			Template::clear_username(RuntimeOrigin::signed(1), address, 1, signature),
+			Error::<Test>::InvalidNonce
+		);
+	});
+}
+
+#[test]
+fn username_shorter_than_minimum_is_rejected() {
+	new_test_ext().execute_with(|| {
+		let (pair, address) = eth_account(1);
+
+		assert_noop!(set_username(&pair, address, b"ab"), Error::<Test>::UsernameTooShort);
+		assert_ok!(set_username(&pair, address, b"abc"));
+		assert_ok!(set_username(&pair, address, b"abcd"));
+	});
+}
+
+#[test]
+fn short_username_is_checked_before_charset() {
+	new_test_ext().execute_with(|| {
+		let (pair, address) = eth_account(1);
+
+		assert_noop!(set_username(&pair, address, b"a-"), Error::<Test>::UsernameTooShort);
+		assert_noop!(set_username(&pair, address, b"ab-"), Error::<Test>::InvalidUsername);
+	});
+}
+
+#[test]
+fn username_of_exactly_maximum_length_is_accepted() {
+	new_test_ext().execute_with(|| {
+		let (pair, address) = eth_account(1);
+		let max = vec![b'a'; MaxUsernameLength::get() as usize];
+
+		assert_noop!(
+			set_username(&pair, address, &[&max[..], b"a"].concat()),
+			Error::<Test>::UsernameTooLong
+		);
+		assert_ok!(set_username(&pair, address, &max));
+		assert_eq!(Template::get_username(address), Some(max));
+	});
+}
+
+#[test]
+fn length_bounds_follow_the_configured_maximum() {
+	for max in [1, 4, 64] {
+		ExtBuilder::default().min_username_length(1).max_username_length(max).build().execute_with(
+			|| {
+				let (pair, address) = eth_account(1);
+				let longest = vec![b'a'; max as usize];
+
+				assert_noop!(
+					set_username(&pair, address, &[&longest[..], b"a"].concat()),
+					Error::<Test>::UsernameTooLong
+				);
+				assert_ok!(set_username(&pair, address, &longest));
+				assert_eq!(Template::username_config().max_length, max);
+			},
+		);
+	}
+}
+
+#[test]
+fn genesis_accepts_usernames_up_to_the_configured_maximum() {
+	let (_, address) = eth_account(1);
+	let username = vec![b'a'; 64];
+	ExtBuilder::default()
+		.max_username_length(64)
+		.usernames(vec![(address, username.clone())])
+		.build()
+		.execute_with(|| {
+			assert_eq!(Template::get_username(address), Some(username));
+		});
+}
+
+#[test]
+fn long_username_is_checked_before_charset() {
+	new_test_ext().execute_with(|| {
+		let (pair, address) = eth_account(1);
+		let mut username = vec![b'a'; MaxUsernameLength::get() as usize];
+		*username.last_mut().unwrap() = b'-';
+
+		// At the maximum length the name gets past the bound, so the charset check reports it.
+		assert_noop!(set_username(&pair, address, &username), Error::<Test>::InvalidUsername);
+		username.push(b'-');
+		assert_noop!(set_username(&pair, address, &username), Error::<Test>::UsernameTooLong);
+	});
+}
+
+#[test]
+fn non_utf8_username_is_rejected_by_charset() {
+	new_test_ext().execute_with(|| {
+		let (pair, address) = eth_account(1);
+		let username = b"al\xffce";
+
+		// The signature covers the raw bytes, so the call reaches the charset check intact.
+		assert_noop!(set_username(&pair, address, username), Error::<Test>::InvalidUsername);
+		assert_eq!(Nonces::<Test>::get(address), 0);
+	});
+}
+
+#[test]
+fn username_needs_an_alphanumeric_byte() {
+	new_test_ext().execute_with(|| {
+		let (pair, address) = eth_account(1);
+
+		assert_noop!(set_username(&pair, address, b"___"), Error::<Test>::InvalidUsername);
+		assert_ok!(set_username(&pair, address, b"_a_"));
+		assert_eq!(Template::get_username(address), Some(b"_a_".to_vec()));
+	});
+}
+
+#[test]
+fn username_cannot_start_with_a_digit() {
+	new_test_ext().execute_with(|| {
+		let (pair, address) = eth_account(1);
+
+		assert_noop!(set_username(&pair, address, b"1abc"), Error::<Test>::InvalidUsername);
+		assert_ok!(set_username(&pair, address, b"abc1"));
+	});
+}
+
+#[test]
+fn runtime_validator_decides_the_charset() {
+	new_test_ext().execute_with(|| {
+		let (pair, address) = eth_account(1);
+		assert_noop!(set_username(&pair, address, b"alice.eth"), Error::<Test>::InvalidUsername);
+		assert!(!Template::is_valid_username(b"alice-b".to_vec()));
+
+		AllowHyphenAndDot::set(true);
+		assert!(Template::is_valid_username(b"alice-b".to_vec()));
+		assert_ok!(set_username(&pair, address, b"alice.eth"));
+		assert_eq!(Template::get_owner(b"ALICE.eth".to_vec()), Some(address));
+		assert_noop!(set_username(&pair, address, b"-alice"), Error::<Test>::InvalidUsername);
+	});
+}
+
+#[test]
+fn username_config_reflects_the_runtime() {
+	ExtBuilder::default().max_username_length(20).change_cooldown(5).build().execute_with(|| {
+		let config = Template::username_config();
+		assert_eq!(config.max_length, 20);
+		assert_eq!(config.min_length, MinUsernameLength::get());
+		assert_eq!(config.change_cooldown, 5);
+		assert_eq!(config.allowed_chars, AlphanumericUnderscore::description().as_bytes());
+
+		AllowHyphenAndDot::set(true);
+		assert_eq!(
+			Template::username_config().allowed_chars,
+			MockUsernameValidator::description().as_bytes()
+		);
+		assert_ne!(Template::username_config().allowed_chars, config.allowed_chars);
+	});
+}
+
+#[test]
+fn nonce_overflow_fails_cleanly() {
+	new_test_ext().execute_with(|| {
+		let (pair, address) = eth_account(1);
+		Nonces::<Test>::insert(address, u64::MAX);
+
+		assert_noop!(set_username(&pair, address, b"alice"), Error::<Test>::NonceOverflow);
+		assert_eq!(Template::get_username(address), None);
+	});
+}
+
+#[test]
+fn username_updated_carries_previous_name() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let (pair, address) = eth_account(1);
+
+		assert_ok!(set_username(&pair, address, b"alice"));
+		System::assert_last_event(
+			Event::UsernameUpdated {
+				eth_address: address,
+				old_username: None,
+				new_username: b"alice".to_vec().try_into().unwrap(),
+			}
+			.into(),
+		);
+
+		assert_ok!(set_username(&pair, address, b"alice_2"));
+		System::assert_last_event(
+			Event::UsernameUpdated {
+				eth_address: address,
+				old_username: Some(b"alice".to_vec().try_into().unwrap()),
+				new_username: b"alice_2".to_vec().try_into().unwrap(),
+			}
+			.into(),
+		);
+	});
+}
+
+#[test]
+fn username_uniqueness_is_case_insensitive() {
+	new_test_ext().execute_with(|| {
+		let (alice, alice_address) = eth_account(1);
+		let (bob, bob_address) = eth_account(2);
+
+		assert_ok!(set_username(&alice, alice_address, b"Alice"));
+		assert_noop!(set_username(&bob, bob_address, b"alice"), Error::<Test>::UsernameTaken);
+
+		// The display casing is preserved while lookups ignore case.
+		assert_eq!(Template::get_username(alice_address), Some(b"Alice".to_vec()));
+		assert_eq!(Template::get_owner(b"ALICE".to_vec()), Some(alice_address));
+	});
+}
+
+#[test]
+fn owner_can_change_casing_of_own_username() {
+	new_test_ext().execute_with(|| {
+		let (pair, address) = eth_account(1);
+
+		assert_ok!(set_username(&pair, address, b"alice"));
+		assert_ok!(set_username(&pair, address, b"Alice"));
+
+		assert_eq!(Template::get_username(address), Some(b"Alice".to_vec()));
+		assert_eq!(Template::get_owner(b"alice".to_vec()), Some(address));
+	});
+}
+
+#[test]
+fn genesis_usernames_are_queryable() {
+	let (_, address) = eth_account(1);
+	new_test_ext_with_usernames(vec![(address, b"Alice".to_vec())]).execute_with(|| {
+		assert_eq!(Template::get_username(address), Some(b"Alice".to_vec()));
+		assert_eq!(Template::get_owner(b"alice".to_vec()), Some(address));
+		assert_eq!(Nonces::<Test>::get(address), 0);
+	});
+}
+
+#[test]
+#[should_panic(expected = "genesis username exceeds MaxUsernameLength")]
+fn genesis_username_too_long_panics() {
+	new_test_ext_with_usernames(vec![(H160::repeat_byte(1), vec![b'a'; 33])]);
+}
+
+#[test]
+fn integrity_test_accepts_mock_bounds() {
+	Template::integrity_test();
+}
+
+#[test]
+#[should_panic(expected = "MinUsernameLength must not exceed MaxUsernameLength")]
+fn integrity_test_rejects_inverted_bounds() {
+	MinUsernameLength::set(33);
+	Template::integrity_test();
+}
+
+#[test]
+#[should_panic(expected = "MaxUsernameLength must be greater than zero")]
+fn integrity_test_rejects_zero_max_length() {
+	MaxUsernameLength::set(0);
+	MinUsernameLength::set(0);
+	Template::integrity_test();
+}
+
+#[test]
+fn force_set_username_works() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let address = H160::repeat_byte(1);
+
+		assert_ok!(Template::force_set_username(RuntimeOrigin::root(), address, b"alice".to_vec()));
+
+		assert_eq!(Template::get_username(address), Some(b"alice".to_vec()));
+		assert_eq!(Template::get_owner(b"alice".to_vec()), Some(address));
+		assert_eq!(Nonces::<Test>::get(address), 0);
+		System::assert_last_event(
+			Event::UsernameForceSet {
+				eth_address: address,
+				username: b"alice".to_vec().try_into().unwrap(),
+			}
+			.into(),
+		);
+	});
+}
+
+#[test]
+fn force_set_username_requires_root() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			Template::force_set_username(
+				RuntimeOrigin::signed(1),
+				H160::repeat_byte(1),
+				b"alice".to_vec()
+			),
+			DispatchError::BadOrigin
+		);
+	});
+}
+
+#[test]
+fn force_set_username_enforces_rules() {
+	new_test_ext().execute_with(|| {
+		let (pair, address) = eth_account(1);
+		assert_ok!(set_username(&pair, address, b"alice"));
+
+		let other = H160::repeat_byte(2);
+		assert_noop!(
+			Template::force_set_username(RuntimeOrigin::root(), other, b"ab".to_vec()),
+			Error::<Test>::UsernameTooShort
+		);
+		assert_noop!(
+			Template::force_set_username(RuntimeOrigin::root(), other, b"a-b".to_vec()),
+			Error::<Test>::InvalidUsername
+		);
+		assert_noop!(
+			Template::force_set_username(RuntimeOrigin::root(), other, b"ALICE".to_vec()),
+			Error::<Test>::UsernameTaken
+		);
+	});
+}
+
+#[test]
+fn force_set_username_enforces_address_rules() {
+	new_test_ext().execute_with(|| {
+		let (primary_pair, primary) = eth_account(1);
+		let (alias_pair, alias) = eth_account(2);
+		assert_ok!(set_username(&primary_pair, primary, b"alice"));
+		assert_ok!(link_alias(&primary_pair, primary, &alias_pair, alias));
+
+		assert_noop!(
+			Template::force_set_username(RuntimeOrigin::root(), H160::zero(), b"bob".to_vec()),
+			Error::<Test>::InvalidAddress
+		);
+		assert_noop!(
+			Template::force_set_username(RuntimeOrigin::root(), alias, b"bob".to_vec()),
+			Error::<Test>::AliasAlreadyLinked
+		);
+
+		let blocked = H160::repeat_byte(3);
+		assert_ok!(Template::block_address(RuntimeOrigin::root(), blocked));
+		assert_noop!(
+			Template::force_set_username(RuntimeOrigin::root(), blocked, b"bob".to_vec()),
+			Error::<Test>::AddressBlocked
+		);
+	});
+}
+
+#[test]
+fn force_clear_username_works() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let (pair, address) = eth_account(1);
+		assert_ok!(set_username(&pair, address, b"alice"));
+
+		assert_ok!(Template::force_clear_username(RuntimeOrigin::root(), address));
+
+		assert_eq!(Template::get_username(address), None);
+		assert_eq!(Template::get_owner(b"alice".to_vec()), None);
+		System::assert_last_event(Event::UsernameForceCleared { eth_address: address }.into());
+		assert_noop!(
+			Template::force_clear_username(RuntimeOrigin::root(), address),
+			Error::<Test>::UsernameNotFound
+		);
+	});
+}
+
+#[test]
+fn force_expire_all_drains_usernames_over_several_calls() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		for (seed, name) in [b"alice", b"bobby", b"carol", b"david", b"erica"].iter().enumerate() {
+			let (pair, address) = eth_account(seed as u8 + 1);
+			assert_ok!(set_username(&pair, address, *name));
+		}
+
+		for removed in [2, 2, 1, 0] {
+			assert_ok!(Template::force_expire_all(RuntimeOrigin::root(), 2));
+			System::assert_last_event(Event::UsernamesForceExpired { count: removed }.into());
+		}
+
+		assert_eq!(Usernames::<Test>::iter().count(), 0);
+		assert_eq!(UsernameOwners::<Test>::iter().count(), 0);
+		assert_eq!(UsernameCount::<Test>::get(), 0);
+		assert_eq!(Template::get_username(eth_account(1).1), None);
+		assert_ok!(Template::do_try_state());
+		assert_noop!(
+			Template::force_expire_all(RuntimeOrigin::signed(1), 2),
+			DispatchError::BadOrigin
+		);
 	});
 }
 
+/// Turn `signature` into its high-`s` twin `(r, n - s, v ^ 1)`, which recovers the same key.
+fn malleate(signature: &[u8]) -> Vec<u8> {
+	const ORDER: [u8; 32] =
+		hex_literal::hex!("fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364141");
+	let mut malleated = signature.to_vec();
+	let mut borrow = 0i16;
+	for i in (0..32).rev() {
+		let mut digit = ORDER[i] as i16 - signature[32 + i] as i16 - borrow;
+		borrow = (digit < 0) as i16;
+		if digit < 0 {
+			digit += 256;
+		}
+		malleated[32 + i] = digit as u8;
+	}
+	malleated[64] = 27 + ((signature[64] - 27) ^ 1);
+	malleated
+}
+
 #[test]
-fn correct_error_for_none_value() {
+fn high_s_signature_is_rejected() {
 	new_test_ext().execute_with(|| {
-		// Ensure the expected error is thrown when no value is present.
-		assert_noop!(Template::cause_error(RuntimeOrigin::signed(1)), Error::<Test>::NoneValue);
+		let (pair, address) = eth_account(1);
+		let message = set_username_message(b"alice", 0);
+		let signature = eth_sign(&pair, &message);
+		let malleated = malleate(&signature);
+
+		assert_ne!(malleated, signature);
+		assert!(Template::verify_ethereum_signature(&address, &message, &signature).is_ok());
+		assert!(matches!(
+			Template::verify_ethereum_signature(&address, &message, &malleated),
+			Err(Error::<Test>::SignatureRecoveryFailed)
+		));
+		assert_noop!(
+			Template::set_username(
+				RuntimeOrigin::signed(1),
+				address,
+				b"alice".to_vec(),
+				0,
+				malleated
+			),
+			Error::<Test>::SignatureRecoveryFailed
+		);
+	});
+}
+
+#[test]
+fn verify_ethereum_signature_known_answer() {
+	// The well-known private key from the web3.js documentation, signing with `personal_sign`.
+	let seed =
+		hex_literal::hex!("4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318");
+	let message = b"set_username:alice:0";
+	let signature = hex_literal::hex!(
+		"562c1b2c72cdf941786af1515283c319ffa45a571e4f9d59c169f471685ec755"
+		"6726ddb33a5d7c161669b8fe2f8301b88e4d501497383a12e3c50e50a10c21761b"
+	);
+	let address = H160(hex_literal::hex!("2c7536e3605d9c16a7a3d7b1898e529396a65c23"));
+
+	assert_eq!(eth_sign(&ecdsa::Pair::from_seed(&seed), message), signature.to_vec());
+	assert!(Template::verify_ethereum_signature(&address, message, &signature).is_ok());
+
+	let mut mutated = signature;
+	mutated[0] ^= 0x01;
+	assert!(Template::verify_ethereum_signature(&address, message, &mutated).is_err());
+}
+
+#[test]
+fn raw_recovery_id_is_accepted() {
+	new_test_ext().execute_with(|| {
+		let (pair, address) = eth_account(1);
+		let message = set_username_message(b"alice", 0);
+		let mut raw = eth_sign(&pair, &message);
+		raw[64] -= 27;
+
+		assert!(Template::verify_ethereum_signature(&address, &message, &raw).is_ok());
+		assert_ok!(Template::set_username(
+			RuntimeOrigin::signed(1),
+			address,
+			b"alice".to_vec(),
+			0,
+			raw
+		));
+	});
+}
+
+#[test]
+fn zero_address_is_rejected() {
+	new_test_ext().execute_with(|| {
+		let (pair, _) = eth_account(1);
+		let signature = eth_sign(&pair, &set_username_message(b"alice", 0));
+
+		assert_noop!(
+			Template::set_username(
+				RuntimeOrigin::signed(1),
+				H160::zero(),
+				b"alice".to_vec(),
+				0,
+				signature
+			),
+			Error::<Test>::InvalidAddress.with_weight(Template::set_username_rejected_weight())
+		);
+		assert_noop!(
+			Template::set_username_with_scheme(
+				RuntimeOrigin::signed(1),
+				H160::zero(),
+				b"alice".to_vec(),
+				0,
+				SigScheme::EthEip712,
+				vec![0; 65]
+			),
+			Error::<Test>::InvalidAddress.with_weight(Template::set_username_rejected_weight())
+		);
+	});
+}
+
+#[test]
+fn zero_address_never_verifies() {
+	new_test_ext().execute_with(|| {
+		let (pair, _) = eth_account(1);
+		let message = set_username_message(b"alice", 0);
+		let signature = eth_sign(&pair, &message);
+		let digest = eip712_set_username_hash(ChainId::get(), &H160::zero(), b"alice", 0);
+		let mut typed_signature = pair.sign_prehashed(&digest).0.to_vec();
+		typed_signature[64] += 27;
+
+		// No key is known to recover to zero, so the guard is exercised from the expected side:
+		// neither a valid signature by another key nor an all-zero blob may match it.
+		assert!(matches!(
+			Template::verify_ethereum_signature(&H160::zero(), &message, &signature),
+			Err(Error::<Test>::SignatureAddressMismatch)
+		));
+		assert!(matches!(
+			Template::verify_ethereum_signature(&H160::zero(), &message, &[0; 65]),
+			Err(Error::<Test>::SignatureRecoveryFailed)
+		));
+		assert!(matches!(
+			Template::verify_signature(
+				&SigScheme::EthEip712,
+				&H160::zero(),
+				&digest,
+				&typed_signature
+			),
+			Err(Error::<Test>::SignatureAddressMismatch)
+		));
+		assert!(matches!(
+			Template::verify_signature(&SigScheme::EthEip712, &H160::zero(), &digest, &[0; 65]),
+			Err(Error::<Test>::SignatureRecoveryFailed)
+		));
+	});
+}
+
+#[test]
+fn set_username_with_eth_scheme_matches_set_username() {
+	new_test_ext().execute_with(|| {
+		let (pair, address) = eth_account(1);
+		let signature = eth_sign(&pair, &set_username_message(b"alice", 0));
+
+		assert_ok!(Template::set_username_with_scheme(
+			RuntimeOrigin::signed(1),
+			address,
+			b"alice".to_vec(),
+			0,
+			SigScheme::EthEcdsa,
+			signature
+		));
+		assert_eq!(Template::get_username(address), Some(b"alice".to_vec()));
+	});
+}
+
+#[test]
+fn set_username_with_eip712_works() {
+	new_test_ext().execute_with(|| {
+		let (pair, address) = eth_account(1);
+		let hash = eip712_set_username_hash(ChainId::get(), &address, b"alice", 0);
+		let mut signature = pair.sign_prehashed(&hash).0.to_vec();
+		signature[64] += 27;
+
+		// A personal_sign signature is not accepted under the EIP-712 scheme.
+		assert_noop!(
+			Template::set_username_with_scheme(
+				RuntimeOrigin::signed(1),
+				address,
+				b"alice".to_vec(),
+				0,
+				SigScheme::EthEip712,
+				eth_sign(&pair, &set_username_message(b"alice", 0))
+			),
+			Error::<Test>::SignatureAddressMismatch
+		);
+
+		assert_ok!(Template::set_username_with_scheme(
+			RuntimeOrigin::signed(1),
+			address,
+			b"alice".to_vec(),
+			0,
+			SigScheme::EthEip712,
+			signature
+		));
+		assert_eq!(Template::get_username(address), Some(b"alice".to_vec()));
+	});
+}
+
+#[test]
+fn set_username_with_ed25519_works() {
+	new_test_ext().execute_with(|| {
+		let pair = ed25519::Pair::from_seed(&[1; 32]);
+		let public = pair.public().0;
+		let address = substrate_key_address(&public);
+		let signature = pair.sign(&set_username_message(b"alice", 0)).0.to_vec();
+
+		assert_ok!(Template::set_username_with_scheme(
+			RuntimeOrigin::signed(1),
+			address,
+			b"alice".to_vec(),
+			0,
+			SigScheme::Ed25519(public),
+			signature
+		));
+		assert_eq!(Template::get_username(address), Some(b"alice".to_vec()));
+		assert_eq!(Nonces::<Test>::get(address), 1);
+	});
+}
+
+#[test]
+fn set_username_with_sr25519_works() {
+	new_test_ext().execute_with(|| {
+		let pair = sr25519::Pair::from_seed(&[1; 32]);
+		let public = pair.public().0;
+		let address = substrate_key_address(&public);
+		let signature = pair.sign(&set_username_message(b"alice", 0)).0.to_vec();
+
+		assert_ok!(Template::set_username_with_scheme(
+			RuntimeOrigin::signed(1),
+			address,
+			b"alice".to_vec(),
+			0,
+			SigScheme::Sr25519(public),
+			signature
+		));
+		assert_eq!(Template::get_username(address), Some(b"alice".to_vec()));
+	});
+}
+
+#[test]
+fn substrate_scheme_rejects_key_for_another_address() {
+	new_test_ext().execute_with(|| {
+		let pair = ed25519::Pair::from_seed(&[1; 32]);
+		let public = pair.public().0;
+		let (_, other_address) = eth_account(2);
+		let signature = pair.sign(&set_username_message(b"alice", 0)).0.to_vec();
+
+		assert_noop!(
+			Template::set_username_with_scheme(
+				RuntimeOrigin::signed(1),
+				other_address,
+				b"alice".to_vec(),
+				0,
+				SigScheme::Ed25519(public),
+				signature
+			),
+			Error::<Test>::InvalidSignature
+		);
+	});
+}
+
+#[test]
+fn substrate_scheme_rejects_wrong_message() {
+	new_test_ext().execute_with(|| {
+		let pair = sr25519::Pair::from_seed(&[1; 32]);
+		let public = pair.public().0;
+		let address = substrate_key_address(&public);
+		let signature = pair.sign(&set_username_message(b"mallory", 0)).0.to_vec();
+
+		assert_noop!(
+			Template::set_username_with_scheme(
+				RuntimeOrigin::signed(1),
+				address,
+				b"alice".to_vec(),
+				0,
+				SigScheme::Sr25519(public),
+				signature
+			),
+			Error::<Test>::InvalidSignature
+		);
+	});
+}
+
+#[test]
+fn set_username_reserves_deposit_from_submitter() {
+	new_test_ext().execute_with(|| {
+		let (pair, address) = eth_account(1);
+
+		assert_ok!(set_username(&pair, address, b"alice"));
+		assert_eq!(Balances::reserved_balance(1), 10);
+		assert_eq!(Balances::free_balance(1), INITIAL_BALANCE - 10);
+		assert_eq!(UsernameDeposits::<Test>::get(address), Some((1, 10)));
+
+		// Changing the name keeps the existing deposit instead of taking another one.
+		assert_ok!(set_username(&pair, address, b"alice2"));
+		assert_eq!(Balances::reserved_balance(1), 10);
+	});
+}
+
+#[test]
+fn clear_username_refunds_original_payer() {
+	new_test_ext().execute_with(|| {
+		let (pair, address) = eth_account(1);
+		let signature = eth_sign(&pair, &set_username_message(b"alice", 0));
+		assert_ok!(Template::set_username(
+			RuntimeOrigin::signed(2),
+			address,
+			b"alice".to_vec(),
+			0,
+			signature
+		));
+		assert_eq!(Balances::reserved_balance(2), 10);
+
+		// The clear is submitted by account 1, but the deposit goes back to account 2.
+		assert_ok!(clear_username(&pair, address));
+		assert_eq!(Balances::reserved_balance(2), 0);
+		assert_eq!(Balances::free_balance(2), INITIAL_BALANCE);
+		assert_eq!(Balances::free_balance(1), INITIAL_BALANCE);
+		assert_eq!(UsernameDeposits::<Test>::get(address), None);
+	});
+}
+
+#[test]
+fn set_username_fails_without_funds_for_deposit() {
+	new_test_ext().execute_with(|| {
+		let (pair, address) = eth_account(1);
+		UsernameDeposit::set(INITIAL_BALANCE + 1);
+
+		assert_noop!(
+			set_username(&pair, address, b"alice"),
+			pallet_balances::Error::<Test>::InsufficientBalance
+		);
+	});
+}
+
+#[test]
+fn force_clear_username_refunds_deposit() {
+	new_test_ext().execute_with(|| {
+		let (pair, address) = eth_account(1);
+		assert_ok!(set_username(&pair, address, b"alice"));
+
+		assert_ok!(Template::force_clear_username(RuntimeOrigin::root(), address));
+		assert_eq!(Balances::reserved_balance(1), 0);
+	});
+}
+
+#[test]
+fn reserved_username_cannot_be_registered_until_unreserved() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let (pair, address) = eth_account(1);
+
+		assert_ok!(Template::reserve_username(RuntimeOrigin::root(), b"Admin".to_vec()));
+		System::assert_last_event(
+			Event::UsernameReserved { username: b"admin".to_vec().try_into().unwrap() }.into(),
+		);
+		assert_noop!(set_username(&pair, address, b"admin"), Error::<Test>::UsernameReserved);
+		assert_noop!(set_username(&pair, address, b"ADMIN"), Error::<Test>::UsernameReserved);
+
+		assert_ok!(Template::unreserve_username(RuntimeOrigin::root(), b"admin".to_vec()));
+		assert_ok!(set_username(&pair, address, b"admin"));
+		assert_eq!(Template::get_username(address), Some(b"admin".to_vec()));
+	});
+}
+
+#[test]
+fn reservations_require_root() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			Template::reserve_username(RuntimeOrigin::signed(1), b"admin".to_vec()),
+			DispatchError::BadOrigin
+		);
+		assert_noop!(
+			Template::unreserve_username(RuntimeOrigin::root(), b"admin".to_vec()),
+			Error::<Test>::UsernameNotReserved
+		);
+	});
+}
+
+#[test]
+fn force_set_username_overrides_reservation() {
+	new_test_ext().execute_with(|| {
+		let (_, address) = eth_account(1);
+		assert_ok!(Template::reserve_username(RuntimeOrigin::root(), b"root".to_vec()));
+
+		assert_ok!(Template::force_set_username(RuntimeOrigin::root(), address, b"root".to_vec()));
+		assert_eq!(Template::get_username(address), Some(b"root".to_vec()));
+	});
+}
+
+#[test]
+fn set_username_rejected_before_signature_check_is_charged_less() {
+	new_test_ext().execute_with(|| {
+		let (pair, address) = eth_account(1);
+		let (other, _) = eth_account(2);
+		let call = |nonce, signature| {
+			Template::set_username(
+				RuntimeOrigin::signed(1),
+				address,
+				b"alice".to_vec(),
+				nonce,
+				signature,
+			)
+			.unwrap_err()
+		};
+
+		let nonce_failure = call(1, eth_sign(&pair, &set_username_message(b"alice", 1)));
+		let signature_failure = call(0, eth_sign(&other, &set_username_message(b"alice", 0)));
+
+		assert_eq!(nonce_failure.error, Error::<Test>::InvalidNonce.into());
+		assert_eq!(signature_failure.error, Error::<Test>::SignatureAddressMismatch.into());
+		assert_eq!(
+			nonce_failure.post_info.actual_weight,
+			Some(Template::set_username_rejected_weight())
+		);
+		// The signature was recovered, so the full declared weight is charged.
+		assert_eq!(signature_failure.post_info.actual_weight, None);
+		assert!(Template::set_username_rejected_weight()
+			.all_lt(<() as WeightInfo>::set_username(b"alice".len() as u32)));
+	});
+}
+
+#[test]
+fn signature_for_another_chain_is_rejected() {
+	new_test_ext().execute_with(|| {
+		let (pair, address) = eth_account(1);
+		ChainId::set(1);
+		let signature = eth_sign(&pair, &set_username_message(b"alice", 0));
+
+		ChainId::set(2);
+		assert_noop!(
+			Template::set_username(
+				RuntimeOrigin::signed(1),
+				address,
+				b"alice".to_vec(),
+				0,
+				signature
+			),
+			Error::<Test>::SignatureAddressMismatch
+		);
+
+		// Re-signing for the configured chain succeeds.
+		assert_ok!(set_username(&pair, address, b"alice"));
+	});
+}
+
+#[test]
+fn username_change_respects_cooldown() {
+	ExtBuilder::default().change_cooldown(5).build().execute_with(|| {
+		let (pair, address) = eth_account(1);
+		System::set_block_number(1);
+		assert_ok!(set_username(&pair, address, b"alice"));
+		assert_eq!(LastUsernameChange::<Test>::get(address), Some(1));
+
+		System::set_block_number(5);
+		assert_noop!(set_username(&pair, address, b"alice2"), Error::<Test>::ChangeTooSoon);
+
+		System::set_block_number(6);
+		assert_ok!(set_username(&pair, address, b"alice2"));
+		assert_eq!(Template::get_username(address), Some(b"alice2".to_vec()));
+		assert_eq!(LastUsernameChange::<Test>::get(address), Some(6));
+	});
+}
+
+/// Run `on_initialize` for every block after the current one up to and including `n`.
+fn run_to_block(n: u64) {
+	while System::block_number() < n {
+		System::set_block_number(System::block_number() + 1);
+		Template::on_initialize(System::block_number());
+	}
+}
+
+#[test]
+fn registrations_are_capped_per_block() {
+	new_test_ext().execute_with(|| {
+		MaxRegistrationsPerBlock::set(2);
+		System::set_block_number(1);
+		let (alice_pair, alice) = eth_account(1);
+		let (bob_pair, bob) = eth_account(2);
+		let (carol_pair, carol) = eth_account(3);
+		assert_ok!(set_username(&alice_pair, alice, b"alice"));
+		assert_ok!(set_username(&bob_pair, bob, b"bob"));
+
+		assert_noop!(
+			set_username(&carol_pair, carol, b"carol"),
+			Error::<Test>::TooManyRegistrationsThisBlock
+		);
+
+		run_to_block(2);
+		assert_ok!(set_username(&carol_pair, carol, b"carol"));
+		assert_eq!(Template::get_username(carol), Some(b"carol".to_vec()));
+	});
+}
+
+#[test]
+fn username_expires_after_ttl() {
+	new_test_ext().execute_with(|| {
+		UsernameTtl::set(Some(10));
+		System::set_block_number(1);
+		let (pair, address) = eth_account(1);
+		assert_ok!(set_username(&pair, address, b"alice"));
+		assert_eq!(Usernames::<Test>::get(address).unwrap().expires_at, Some(11));
+
+		run_to_block(10);
+		assert_eq!(Template::get_username(address), Some(b"alice".to_vec()));
+
+		run_to_block(11);
+		assert_eq!(Template::get_username(address), None);
+		assert_eq!(Template::get_owner(b"alice".to_vec()), None);
+		assert_eq!(Template::username_count(), 0);
+		assert_eq!(Balances::reserved_balance(1), 0);
+		System::assert_has_event(
+			Event::UsernameExpired {
+				eth_address: address,
+				username: b"alice".to_vec().try_into().unwrap(),
+			}
+			.into(),
+		);
+	});
+}
+
+#[test]
+fn expiry_sweep_is_capped_per_block() {
+	new_test_ext().execute_with(|| {
+		UsernameTtl::set(Some(5));
+		System::set_block_number(1);
+		let accounts: Vec<_> = (1..=3u8).map(eth_account).collect();
+		for (i, (pair, address)) in accounts.iter().enumerate() {
+			assert_ok!(set_username(pair, *address, format!("user{}", i).as_bytes()));
+		}
+
+		// MaxExpiriesPerBlock is 2, so the third name is only removed one block later.
+		run_to_block(6);
+		assert_eq!(Template::username_count(), 1);
+		run_to_block(7);
+		assert_eq!(Template::username_count(), 0);
+	});
+}
+
+#[test]
+fn expiry_sweep_charges_each_removal_for_its_aliases() {
+	new_test_ext().execute_with(|| {
+		UsernameTtl::set(Some(5));
+		System::set_block_number(1);
+		let (primary_pair, primary) = eth_account(1);
+		let (alias_pair, alias) = eth_account(2);
+		assert_ok!(set_username(&primary_pair, primary, b"alice"));
+		assert_ok!(link_alias(&primary_pair, primary, &alias_pair, alias));
+
+		System::set_block_number(6);
+		let weight = Template::on_initialize(6);
+
+		assert_eq!(Template::get_username(primary), None);
+		assert_eq!(AliasOf::<Test>::get(alias), None);
+		assert!(weight.all_gte(<() as WeightInfo>::expire_username(1)));
+	});
+}
+
+#[test]
+fn changing_username_renews_expiry() {
+	new_test_ext().execute_with(|| {
+		UsernameTtl::set(Some(10));
+		System::set_block_number(1);
+		let (pair, address) = eth_account(1);
+		assert_ok!(set_username(&pair, address, b"alice"));
+
+		run_to_block(5);
+		assert_ok!(set_username(&pair, address, b"alice2"));
+
+		// The stale queue entry at block 11 must not remove the renewed name.
+		run_to_block(11);
+		assert_eq!(Template::get_username(address), Some(b"alice2".to_vec()));
+		run_to_block(15);
+		assert_eq!(Template::get_username(address), None);
+	});
+}
+
+#[test]
+fn list_usernames_pages_through_all_entries() {
+	let usernames: Vec<_> =
+		(1..=5u8).map(|i| (H160::repeat_byte(i), format!("user{}", i).into_bytes())).collect();
+	new_test_ext_with_usernames(usernames.clone()).execute_with(|| {
+		let first = Template::list_usernames(None, 3).unwrap();
+		assert_eq!(first.entries.len(), 3);
+		assert_eq!(first.next_cursor, first.entries.last().map(|(address, _)| *address));
+
+		let second = Template::list_usernames(first.next_cursor, 3).unwrap();
+		assert_eq!(second.entries.len(), 2);
+		assert_eq!(second.next_cursor, None);
+
+		let mut all: Vec<_> = first.entries.into_iter().chain(second.entries).collect();
+		all.sort();
+		assert_eq!(all, usernames);
+	});
+}
+
+#[test]
+fn search_usernames_matches_prefix_case_insensitively() {
+	let usernames = vec![
+		(H160::repeat_byte(1), b"Alice".to_vec()),
+		(H160::repeat_byte(2), b"alan".to_vec()),
+		(H160::repeat_byte(3), b"bob".to_vec()),
+	];
+	new_test_ext_with_usernames(usernames).execute_with(|| {
+		let mut found = Template::search_usernames(b"AL".to_vec(), None, 10).unwrap().entries;
+		found.sort();
+		assert_eq!(
+			found,
+			vec![
+				(H160::repeat_byte(1), b"Alice".to_vec()),
+				(H160::repeat_byte(2), b"alan".to_vec())
+			]
+		);
+
+		assert_eq!(Template::search_usernames(b"al".to_vec(), None, 1).unwrap().entries.len(), 1);
+		assert!(Template::search_usernames(b"carol".to_vec(), None, 10)
+			.unwrap()
+			.entries
+			.is_empty());
+		assert!(Template::search_usernames(Vec::new(), None, 10).unwrap().entries.is_empty());
+	});
+}
+
+/// One more registration than a single scan may visit.
+fn more_usernames_than_the_scan_cap() -> Vec<(H160, Vec<u8>)> {
+	(0..=MAX_RPC_SCAN)
+		.map(|i| (H160::from_low_u64_be(i as u64 + 1), format!("user{}", i).into_bytes()))
+		.collect()
+}
+
+#[test]
+fn scans_refuse_limits_above_the_cap() {
+	new_test_ext().execute_with(|| {
+		let too_high = ScanError::LimitTooHigh { requested: MAX_RPC_SCAN + 1, max: MAX_RPC_SCAN };
+		assert_eq!(Template::list_usernames(None, MAX_RPC_SCAN + 1), Err(too_high.clone()));
+		assert_eq!(
+			Template::search_usernames(b"user".to_vec(), None, MAX_RPC_SCAN + 1),
+			Err(too_high)
+		);
+		assert_eq!(
+			Template::list_usernames(None, u32::MAX),
+			Err(ScanError::LimitTooHigh { requested: u32::MAX, max: MAX_RPC_SCAN })
+		);
+		assert_eq!(
+			Template::search_usernames(b"user".to_vec(), Some(vec![b'a'; 33]), 10),
+			Err(ScanError::InvalidCursor)
+		);
+	});
+}
+
+#[test]
+fn scans_refuse_a_zero_limit() {
+	new_test_ext_with_usernames(vec![(H160::repeat_byte(1), b"user1".to_vec())]).execute_with(
+		|| {
+			assert_eq!(Template::list_usernames(None, 0), Err(ScanError::ZeroLimit));
+			assert_eq!(
+				Template::search_usernames(b"user".to_vec(), None, 0),
+				Err(ScanError::ZeroLimit)
+			);
+		},
+	);
+}
+
+#[test]
+fn list_usernames_pages_past_the_scan_cap() {
+	let usernames = more_usernames_than_the_scan_cap();
+	new_test_ext_with_usernames(usernames.clone()).execute_with(|| {
+		let first = Template::list_usernames(None, MAX_RPC_SCAN).unwrap();
+		assert_eq!(first.entries.len(), MAX_RPC_SCAN as usize);
+		assert!(first.next_cursor.is_some());
+
+		let second = Template::list_usernames(first.next_cursor, MAX_RPC_SCAN).unwrap();
+		assert_eq!(second.entries.len(), 1);
+		assert_eq!(second.next_cursor, None);
+
+		let mut all: Vec<_> = first.entries.into_iter().chain(second.entries).collect();
+		all.sort();
+		let mut expected = usernames;
+		expected.sort();
+		assert_eq!(all, expected);
+	});
+}
+
+#[test]
+fn search_usernames_stops_at_the_scan_cap_and_resumes() {
+	new_test_ext_with_usernames(more_usernames_than_the_scan_cap()).execute_with(|| {
+		// Nothing matches, so only the cap ends the first call, which still returns a cursor.
+		let first = Template::search_usernames(b"zz".to_vec(), None, 10).unwrap();
+		assert!(first.entries.is_empty());
+		assert!(first.next_cursor.is_some());
+		let second = Template::search_usernames(b"zz".to_vec(), first.next_cursor, 10).unwrap();
+		assert_eq!(second, ScanPage { entries: vec![], next_cursor: None });
+
+		// `user1`, `user10`..`user19`, `user100`..`user199` and `user1000`, in pages of 50.
+		let mut found = Vec::new();
+		let mut cursor = None;
+		let mut calls = 0;
+		loop {
+			let page = Template::search_usernames(b"USER1".to_vec(), cursor, 50).unwrap();
+			assert!(page.entries.len() <= 50);
+			found.extend(page.entries);
+			calls += 1;
+			cursor = page.next_cursor;
+			if cursor.is_none() {
+				break;
+			}
+		}
+		assert!(calls >= 3);
+		found.sort();
+		found.dedup();
+		assert_eq!(found.len(), 112);
+		assert!(found.iter().all(|(_, name)| name.starts_with(b"user1")));
+	});
+}
+
+#[test]
+fn get_usernames_keeps_input_order() {
+	new_test_ext().execute_with(|| {
+		let (alice, alice_address) = eth_account(1);
+		let (bob, bob_address) = eth_account(2);
+		let unknown = H160::repeat_byte(9);
+		assert_ok!(set_username(&alice, alice_address, b"alice"));
+		assert_ok!(set_username(&bob, bob_address, b"bob"));
+
+		assert_eq!(
+			Template::get_usernames(vec![bob_address, unknown, alice_address]),
+			vec![
+				(bob_address, Some(b"bob".to_vec())),
+				(unknown, None),
+				(alice_address, Some(b"alice".to_vec())),
+			]
+		);
+	});
+}
+
+#[test]
+fn is_username_available_reflects_owners_rules_and_reservations() {
+	new_test_ext().execute_with(|| {
+		let (pair, address) = eth_account(1);
+		assert!(Template::is_username_available(b"alice".to_vec()));
+
+		assert_ok!(set_username(&pair, address, b"Alice"));
+		assert!(!Template::is_username_available(b"alice".to_vec()));
+		assert!(!Template::is_username_available(b"ALICE".to_vec()));
+
+		assert_ok!(Template::reserve_username(RuntimeOrigin::root(), b"admin".to_vec()));
+		assert!(!Template::is_username_available(b"admin".to_vec()));
+
+		assert!(!Template::is_username_available(b"ab".to_vec()));
+		assert!(!Template::is_username_available(b"a-b-c".to_vec()));
+		assert!(!Template::is_username_available(vec![b'a'; 33]));
+	});
+}
+
+#[test]
+fn username_count_tracks_registrations() {
+	new_test_ext_with_usernames(vec![(H160::repeat_byte(9), b"genesis".to_vec())]).execute_with(
+		|| {
+			let (alice, alice_address) = eth_account(1);
+			let (bob, bob_address) = eth_account(2);
+			assert_eq!(UsernameCount::<Test>::get(), 1);
+
+			assert_ok!(set_username(&alice, alice_address, b"alice"));
+			assert_ok!(set_username(&bob, bob_address, b"bob"));
+			assert_eq!(Template::username_count(), 3);
+
+			// Re-setting an existing address's name doesn't add a registration.
+			assert_ok!(set_username(&alice, alice_address, b"alice_2"));
+			assert_eq!(Template::username_count(), 3);
+
+			assert_ok!(clear_username(&bob, bob_address));
+			assert_eq!(Template::username_count(), 2);
+
+			assert_ok!(Template::force_set_username(
+				RuntimeOrigin::root(),
+				bob_address,
+				b"bob".to_vec()
+			));
+			assert_eq!(Template::username_count(), 3);
+			assert_ok!(Template::force_clear_username(RuntimeOrigin::root(), alice_address));
+			assert_eq!(Template::username_count(), 2);
+			assert_eq!(Usernames::<Test>::iter().count(), 2);
+		},
+	);
+}
+
+#[test]
+fn get_nonce_defaults_to_zero_and_follows_signed_calls() {
+	new_test_ext().execute_with(|| {
+		let (pair, address) = eth_account(1);
+		assert_eq!(Template::get_nonce(address), 0);
+
+		assert_ok!(set_username(&pair, address, b"alice"));
+		assert_eq!(Template::get_nonce(address), 1);
+	});
+}
+
+/// Put storage back the way a chain running the release without a storage version left it: no
+/// version key, and `Something` still present.
+fn baseline_storage() {
+	frame_support::storage::unhashed::kill(&StorageVersion::storage_key::<Template>());
+	migrations::v2::Something::<Test>::put(7);
+	assert_eq!(Template::on_chain_storage_version(), 0);
+}
+
+#[test]
+fn migration_to_v2_upgrades_baseline_storage() {
+	new_test_ext().execute_with(|| {
+		baseline_storage();
+
+		MigrateToV2::<Test>::on_runtime_upgrade();
+
+		assert!(!migrations::v2::Something::<Test>::exists());
+		assert_eq!(Template::on_chain_storage_version(), 2);
+	});
+}
+
+#[test]
+fn migration_to_v2_is_a_no_op_at_v2() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(Template::on_chain_storage_version(), 2);
+		migrations::v2::Something::<Test>::put(7);
+
+		MigrateToV2::<Test>::on_runtime_upgrade();
+
+		assert_eq!(migrations::v2::Something::<Test>::get(), Some(7));
+		assert_eq!(Template::on_chain_storage_version(), 2);
+	});
+}
+
+#[cfg(feature = "try-runtime")]
+#[test]
+fn migration_to_v2_passes_try_runtime_checks() {
+	new_test_ext().execute_with(|| {
+		baseline_storage();
+
+		assert_ok!(MigrateToV2::<Test>::try_on_runtime_upgrade(true));
+		assert_ok!(MigrateToV2::<Test>::try_on_runtime_upgrade(true));
+	});
+}
+
+fn profile(display_name: &[u8], avatar_uri: &[u8], bio: &[u8]) -> ProfileMetadata {
+	ProfileMetadata {
+		display_name: display_name.to_vec().try_into().unwrap(),
+		avatar_uri: avatar_uri.to_vec().try_into().unwrap(),
+		bio: bio.to_vec().try_into().unwrap(),
+	}
+}
+
+fn set_profile(pair: &ecdsa::Pair, eth_address: H160, metadata: ProfileMetadata) -> DispatchResult {
+	let nonce = Nonces::<Test>::get(eth_address);
+	let signature = eth_sign(pair, &Template::set_profile_message(&eth_address, &metadata, nonce));
+	Template::set_profile(RuntimeOrigin::signed(1), eth_address, metadata, nonce, signature)
+}
+
+#[test]
+fn set_profile_message_format() {
+	new_test_ext().execute_with(|| {
+		// keccak-256 of the SCALE encoding of an empty profile, `[0, 0, 0]`.
+		assert_eq!(
+			Template::set_profile_message(&H160::repeat_byte(0x11), &ProfileMetadata::default(), 0),
+			b"set_profile:1:0x1111111111111111111111111111111111111111:0:\
+			0x99ff0d9125e1fc9531a11262e15aeb2c60509a078c4cc4c64cefdfb06ff68647"
+				.to_vec()
+		);
+	});
+}
+
+#[test]
+fn set_profile_works() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let (pair, address) = eth_account(1);
+		assert_ok!(set_username(&pair, address, b"alice"));
+		let metadata = profile(b"Alice", b"ipfs://avatar", b"gm");
+
+		assert_ok!(set_profile(&pair, address, metadata.clone()));
+
+		assert_eq!(Template::get_profile(address), Some(metadata.clone()));
+		assert_eq!(Nonces::<Test>::get(address), 2);
+		System::assert_last_event(Event::ProfileSet { eth_address: address, metadata }.into());
+	});
+}
+
+#[test]
+fn set_profile_requires_username() {
+	new_test_ext().execute_with(|| {
+		let (pair, address) = eth_account(1);
+
+		assert_noop!(
+			set_profile(&pair, address, profile(b"Alice", b"", b"")),
+			Error::<Test>::UsernameNotFound
+		);
+	});
+}
+
+#[test]
+fn set_profile_rejects_signature_over_other_metadata() {
+	new_test_ext().execute_with(|| {
+		let (pair, address) = eth_account(1);
+		assert_ok!(set_username(&pair, address, b"alice"));
+		let signed = profile(b"Alice", b"", b"");
+		let signature = eth_sign(&pair, &Template::set_profile_message(&address, &signed, 1));
+
+		assert_noop!(
+			Template::set_profile(
+				RuntimeOrigin::signed(1),
+				address,
+				profile(b"Mallory", b"", b""),
+				1,
+				signature
+			),
+			Error::<Test>::SignatureAddressMismatch
+		);
+	});
+}
+
+#[test]
+fn profile_survives_rename_and_is_removed_with_username() {
+	new_test_ext().execute_with(|| {
+		let (pair, address) = eth_account(1);
+		assert_ok!(set_username(&pair, address, b"alice"));
+		assert_ok!(set_profile(&pair, address, profile(b"Alice", b"", b"")));
+
+		assert_ok!(set_username(&pair, address, b"alice2"));
+		assert!(Profiles::<Test>::contains_key(address));
+
+		assert_ok!(clear_username(&pair, address));
+		assert_eq!(Template::get_profile(address), None);
+	});
+}
+
+#[test]
+fn address_profile_collects_username_nonce_block_and_metadata() {
+	new_test_ext().execute_with(|| {
+		let (pair, address) = eth_account(1);
+		assert_eq!(Template::get_address_profile(address), AddressProfile::default());
+
+		System::set_block_number(5);
+		assert_ok!(set_username(&pair, address, b"alice"));
+		let metadata = profile(b"Alice", b"ipfs://avatar", b"gm");
+		assert_ok!(set_profile(&pair, address, metadata.clone()));
+
+		assert_eq!(
+			Template::get_address_profile(address),
+			AddressProfile {
+				username: Some(b"alice".to_vec()),
+				nonce: 2,
+				registered_at: Some(5),
+				metadata: Some(metadata),
+			}
+		);
+	});
+}
+
+fn link_alias(
+	primary_pair: &ecdsa::Pair,
+	primary: H160,
+	alias_pair: &ecdsa::Pair,
+	alias: H160,
+) -> DispatchResult {
+	let nonce = Nonces::<Test>::get(primary);
+	let message = Template::alias_message("link_alias", &primary, &alias, nonce);
+	Template::link_alias(
+		RuntimeOrigin::signed(1),
+		primary,
+		alias,
+		nonce,
+		eth_sign(primary_pair, &message),
+		eth_sign(alias_pair, &message),
+	)
+}
+
+fn unlink_alias(signer: &ecdsa::Pair, primary: H160, alias: H160) -> DispatchResult {
+	let nonce = Nonces::<Test>::get(primary);
+	let message = Template::alias_message("unlink_alias", &primary, &alias, nonce);
+	Template::unlink_alias(
+		RuntimeOrigin::signed(1),
+		primary,
+		alias,
+		nonce,
+		eth_sign(signer, &message),
+	)
+}
+
+#[test]
+fn linked_alias_resolves_to_primary_username() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let (primary_pair, primary) = eth_account(1);
+		let (alias_pair, alias) = eth_account(2);
+		assert_ok!(set_username(&primary_pair, primary, b"alice"));
+
+		assert_ok!(link_alias(&primary_pair, primary, &alias_pair, alias));
+
+		assert_eq!(Template::get_username(alias), Some(b"alice".to_vec()));
+		assert_eq!(Template::get_primary(alias), Some(primary));
+		assert_eq!(Aliases::<Test>::get(primary).into_inner(), vec![alias]);
+		assert_eq!(Nonces::<Test>::get(primary), 2);
+		System::assert_last_event(Event::AliasLinked { primary, alias }.into());
+
+		// Renaming the primary is visible through the alias.
+		assert_ok!(set_username(&primary_pair, primary, b"alice2"));
+		assert_eq!(Template::get_username(alias), Some(b"alice2".to_vec()));
+	});
+}
+
+#[test]
+fn link_alias_needs_both_signatures() {
+	new_test_ext().execute_with(|| {
+		let (primary_pair, primary) = eth_account(1);
+		let (alias_pair, alias) = eth_account(2);
+		let (other_pair, _) = eth_account(3);
+		assert_ok!(set_username(&primary_pair, primary, b"alice"));
+
+		assert_noop!(
+			link_alias(&primary_pair, primary, &other_pair, alias),
+			Error::<Test>::SignatureAddressMismatch
+		);
+		assert_noop!(
+			link_alias(&other_pair, primary, &alias_pair, alias),
+			Error::<Test>::SignatureAddressMismatch
+		);
+	});
+}
+
+#[test]
+fn link_alias_rejects_invalid_aliases() {
+	new_test_ext().execute_with(|| {
+		let (primary_pair, primary) = eth_account(1);
+		let (alias_pair, alias) = eth_account(2);
+		let (other_pair, other) = eth_account(3);
+
+		assert_noop!(
+			link_alias(&primary_pair, primary, &alias_pair, alias),
+			Error::<Test>::UsernameNotFound
+		);
+
+		assert_ok!(set_username(&primary_pair, primary, b"alice"));
+		assert_ok!(set_username(&other_pair, other, b"bob"));
+		assert_noop!(
+			link_alias(&primary_pair, primary, &primary_pair, primary),
+			Error::<Test>::InvalidAlias
+		);
+		assert_noop!(
+			link_alias(&primary_pair, primary, &other_pair, other),
+			Error::<Test>::InvalidAlias
+		);
+
+		assert_ok!(link_alias(&primary_pair, primary, &alias_pair, alias));
+		assert_noop!(
+			link_alias(&other_pair, other, &alias_pair, alias),
+			Error::<Test>::AliasAlreadyLinked
+		);
+		assert_noop!(set_username(&alias_pair, alias, b"carol"), Error::<Test>::AliasAlreadyLinked);
+	});
+}
+
+#[test]
+fn link_alias_is_capped_at_max_aliases() {
+	new_test_ext().execute_with(|| {
+		let (primary_pair, primary) = eth_account(1);
+		assert_ok!(set_username(&primary_pair, primary, b"alice"));
+		for seed in 2..2 + MaxAliases::get() as u8 {
+			let (alias_pair, alias) = eth_account(seed);
+			assert_ok!(link_alias(&primary_pair, primary, &alias_pair, alias));
+		}
+
+		let (alias_pair, alias) = eth_account(10);
+		assert_noop!(
+			link_alias(&primary_pair, primary, &alias_pair, alias),
+			Error::<Test>::TooManyAliases
+		);
+	});
+}
+
+#[test]
+fn link_alias_is_rejected_while_paused() {
+	new_test_ext().execute_with(|| {
+		let (primary_pair, primary) = eth_account(1);
+		let (alias_pair, alias) = eth_account(2);
+		assert_ok!(set_username(&primary_pair, primary, b"alice"));
+
+		assert_ok!(Template::set_paused(RuntimeOrigin::root(), true));
+		assert_noop!(
+			link_alias(&primary_pair, primary, &alias_pair, alias),
+			Error::<Test>::PalletPaused
+		);
+
+		assert_ok!(Template::set_paused(RuntimeOrigin::root(), false));
+		assert_ok!(link_alias(&primary_pair, primary, &alias_pair, alias));
+	});
+}
+
+#[test]
+fn link_alias_rejects_blocked_addresses() {
+	new_test_ext().execute_with(|| {
+		let (primary_pair, primary) = eth_account(1);
+		let (alias_pair, alias) = eth_account(2);
+		assert_ok!(set_username(&primary_pair, primary, b"alice"));
+
+		assert_ok!(Template::block_address(RuntimeOrigin::root(), alias));
+		assert_noop!(
+			link_alias(&primary_pair, primary, &alias_pair, alias),
+			Error::<Test>::AddressBlocked
+		);
+		assert_ok!(Template::unblock_address(RuntimeOrigin::root(), alias));
+
+		assert_ok!(Template::block_address(RuntimeOrigin::root(), primary));
+		assert_noop!(
+			link_alias(&primary_pair, primary, &alias_pair, alias),
+			Error::<Test>::AddressBlocked
+		);
+		assert_ok!(Template::unblock_address(RuntimeOrigin::root(), primary));
+
+		assert_ok!(link_alias(&primary_pair, primary, &alias_pair, alias));
+	});
+}
+
+#[test]
+fn unlink_alias_works_for_either_address() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let (primary_pair, primary) = eth_account(1);
+		let (alias_pair, alias) = eth_account(2);
+		let (second_pair, second) = eth_account(3);
+		assert_ok!(set_username(&primary_pair, primary, b"alice"));
+		assert_ok!(link_alias(&primary_pair, primary, &alias_pair, alias));
+		assert_ok!(link_alias(&primary_pair, primary, &second_pair, second));
+
+		assert_ok!(unlink_alias(&primary_pair, primary, alias));
+		System::assert_last_event(Event::AliasUnlinked { primary, alias }.into());
+		assert_ok!(unlink_alias(&second_pair, primary, second));
+
+		assert_eq!(Template::get_username(alias), None);
+		assert_eq!(Template::get_username(second), None);
+		assert!(Aliases::<Test>::get(primary).is_empty());
+		assert_noop!(unlink_alias(&primary_pair, primary, alias), Error::<Test>::AliasNotFound);
+	});
+}
+
+#[test]
+fn removing_primary_username_unlinks_aliases() {
+	new_test_ext().execute_with(|| {
+		let (primary_pair, primary) = eth_account(1);
+		let (alias_pair, alias) = eth_account(2);
+		assert_ok!(set_username(&primary_pair, primary, b"alice"));
+		assert_ok!(link_alias(&primary_pair, primary, &alias_pair, alias));
+
+		assert_ok!(clear_username(&primary_pair, primary));
+
+		assert_eq!(Template::get_username(alias), None);
+		assert!(!AliasOf::<Test>::contains_key(alias));
+		assert!(!Aliases::<Test>::contains_key(primary));
+		assert_ok!(set_username(&alias_pair, alias, b"carol"));
+	});
+}
+
+#[test]
+fn clearing_primary_username_clears_every_alias() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let (primary_pair, primary) = eth_account(1);
+		let (alias_pair, alias) = eth_account(2);
+		let (second_pair, second) = eth_account(3);
+		assert_ok!(set_username(&primary_pair, primary, b"alice"));
+		assert_ok!(link_alias(&primary_pair, primary, &alias_pair, alias));
+		assert_ok!(link_alias(&primary_pair, primary, &second_pair, second));
+
+		assert_ok!(clear_username(&primary_pair, primary));
+
+		System::assert_has_event(Event::AliasesCleared { primary, count: 2 }.into());
+		for address in [alias, second] {
+			assert_eq!(Template::get_username(address), None);
+			assert_eq!(Template::get_primary(address), None);
+			assert!(!AliasOf::<Test>::contains_key(address));
+		}
+		assert!(!Aliases::<Test>::contains_key(primary));
+		assert_eq!(Template::get_owner(b"alice".to_vec()), None);
+	});
+}
+
+#[test]
+fn transferring_primary_username_clears_aliases() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let (primary_pair, primary) = eth_account(1);
+		let (alias_pair, alias) = eth_account(2);
+		let (_, to) = eth_account(3);
+		assert_ok!(set_username(&primary_pair, primary, b"alice"));
+		assert_ok!(link_alias(&primary_pair, primary, &alias_pair, alias));
+
+		assert_ok!(transfer_username(&primary_pair, primary, to));
+
+		System::assert_has_event(Event::AliasesCleared { primary, count: 1 }.into());
+		assert_eq!(Template::get_username(alias), None);
+		assert!(!AliasOf::<Test>::contains_key(alias));
+		assert!(Aliases::<Test>::get(to).is_empty());
+	});
+}
+
+#[test]
+fn try_state_holds_after_username_changes() {
+	new_test_ext().execute_with(|| {
+		let (pair, address) = eth_account(1);
+		assert_ok!(set_username(&pair, address, b"alice"));
+		assert_ok!(set_username(&pair, address, b"Alice2"));
+		assert_ok!(Template::force_set_username(
+			RuntimeOrigin::root(),
+			H160::repeat_byte(2),
+			b"bob".to_vec()
+		));
+		assert_ok!(clear_username(&pair, address));
+
+		assert_ok!(Template::do_try_state());
+	});
+}
+
+#[test]
+fn try_state_detects_missing_owner_entry() {
+	new_test_ext().execute_with(|| {
+		let (pair, address) = eth_account(1);
+		assert_ok!(set_username(&pair, address, b"alice"));
+
+		UsernameOwners::<Test>::remove(b"alice".to_vec().try_into().unwrap());
+
+		assert!(Template::do_try_state().is_err());
+	});
+}
+
+#[test]
+fn try_state_detects_wrong_count() {
+	new_test_ext().execute_with(|| {
+		let (pair, address) = eth_account(1);
+		assert_ok!(set_username(&pair, address, b"alice"));
+
+		UsernameCount::<Test>::put(2);
+
+		assert!(Template::do_try_state().is_err());
+	});
+}
+
+#[test]
+fn try_state_detects_shared_normalized_name() {
+	new_test_ext().execute_with(|| {
+		let (pair, address) = eth_account(1);
+		assert_ok!(set_username(&pair, address, b"alice"));
+
+		let info = Usernames::<Test>::get(address).unwrap();
+		Usernames::<Test>::insert(
+			H160::repeat_byte(2),
+			crate::UsernameInfo { username: b"ALICE".to_vec().try_into().unwrap(), ..info },
+		);
+		UsernameCount::<Test>::put(2);
+
+		assert!(Template::do_try_state().is_err());
+	});
+}
+
+#[test]
+fn history_records_changes_oldest_first() {
+	new_test_ext().execute_with(|| {
+		MaxHistory::set(3);
+		let (pair, address) = eth_account(1);
+
+		System::set_block_number(1);
+		assert_ok!(set_username(&pair, address, b"alice"));
+		System::set_block_number(2);
+		assert_ok!(set_username(&pair, address, b"bob"));
+		System::set_block_number(3);
+		assert_ok!(clear_username(&pair, address));
+
+		assert_eq!(
+			Template::get_username_history(address),
+			vec![(b"alice".to_vec(), 1), (b"bob".to_vec(), 2), (Vec::new(), 3)]
+		);
+	});
+}
+
+#[test]
+fn history_drops_oldest_entry_when_full() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(MaxHistory::get(), 2);
+		let (pair, address) = eth_account(1);
+
+		System::set_block_number(1);
+		assert_ok!(set_username(&pair, address, b"alice"));
+		System::set_block_number(2);
+		assert_ok!(set_username(&pair, address, b"bob"));
+		System::set_block_number(3);
+		assert_ok!(set_username(&pair, address, b"carol"));
+
+		assert_eq!(
+			Template::get_username_history(address),
+			vec![(b"bob".to_vec(), 2), (b"carol".to_vec(), 3)]
+		);
+	});
+}
+
+#[test]
+fn paused_pallet_rejects_username_changes() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let (pair, address) = eth_account(1);
+		assert_ok!(set_username(&pair, address, b"alice"));
+
+		assert_noop!(
+			Template::set_paused(RuntimeOrigin::signed(1), true),
+			DispatchError::BadOrigin
+		);
+		assert_ok!(Template::set_paused(RuntimeOrigin::root(), true));
+		System::assert_last_event(Event::PauseToggled { paused: true }.into());
+
+		assert_noop!(set_username(&pair, address, b"bob"), Error::<Test>::PalletPaused);
+		assert_noop!(clear_username(&pair, address), Error::<Test>::PalletPaused);
+
+		assert_ok!(Template::set_paused(RuntimeOrigin::root(), false));
+		System::assert_last_event(Event::PauseToggled { paused: false }.into());
+		assert_ok!(set_username(&pair, address, b"bob"));
+		assert_ok!(clear_username(&pair, address));
+	});
+}
+
+#[test]
+fn allowlist_is_ignored_unless_required() {
+	new_test_ext().execute_with(|| {
+		let (pair, address) = eth_account(1);
+
+		assert_ok!(set_username(&pair, address, b"alice"));
+	});
+}
+
+#[test]
+fn required_allowlist_rejects_unlisted_addresses() {
+	new_test_ext().execute_with(|| {
+		RequireAllowlist::set(true);
+		System::set_block_number(1);
+		let (pair, address) = eth_account(1);
+
+		assert_noop!(set_username(&pair, address, b"alice"), Error::<Test>::NotAllowed);
+
+		assert_noop!(
+			Template::add_to_allowlist(RuntimeOrigin::signed(1), address),
+			DispatchError::BadOrigin
+		);
+		assert_ok!(Template::add_to_allowlist(RuntimeOrigin::root(), address));
+		System::assert_last_event(Event::AllowlistAdded { eth_address: address }.into());
+		assert_ok!(set_username(&pair, address, b"alice"));
+
+		assert_ok!(Template::remove_from_allowlist(RuntimeOrigin::root(), address));
+		System::assert_last_event(Event::AllowlistRemoved { eth_address: address }.into());
+		assert_noop!(set_username(&pair, address, b"alice2"), Error::<Test>::NotAllowed);
+		assert_noop!(
+			Template::remove_from_allowlist(RuntimeOrigin::root(), address),
+			Error::<Test>::NotAllowed
+		);
+	});
+}
+
+fn validate_nonce(call: RuntimeCall) -> TransactionValidity {
+	CheckUsernameNonce::<Test>::new()
+		.validate(
+			RuntimeOrigin::signed(1),
+			&call,
+			&DispatchInfo::default(),
+			0,
+			(),
+			&TxBaseImplication(()),
+			TransactionSource::External,
+		)
+		.map(|(validity, ..)| validity)
+}
+
+fn set_username_call(eth_address: H160, nonce: u64) -> RuntimeCall {
+	RuntimeCall::Template(crate::Call::set_username {
+		eth_address,
+		username: b"alice".to_vec(),
+		nonce,
+		eth_signature: vec![0; 65],
+	})
+}
+
+#[test]
+fn nonce_extension_rejects_stale_nonce() {
+	new_test_ext().execute_with(|| {
+		let (_, address) = eth_account(1);
+		Nonces::<Test>::insert(address, 2);
+
+		assert_eq!(
+			validate_nonce(set_username_call(address, 1)),
+			Err(InvalidTransaction::Stale.into())
+		);
+		let clear = RuntimeCall::Template(crate::Call::clear_username {
+			eth_address: address,
+			nonce: 0,
+			eth_signature: vec![0; 65],
+		});
+		assert_eq!(validate_nonce(clear), Err(InvalidTransaction::Stale.into()));
+	});
+}
+
+#[test]
+fn nonce_extension_orders_calls_by_nonce() {
+	new_test_ext().execute_with(|| {
+		let (_, address) = eth_account(1);
+		Nonces::<Test>::insert(address, 2);
+
+		let current = validate_nonce(set_username_call(address, 2)).unwrap();
+		assert!(current.requires.is_empty());
+		assert_eq!(current.provides, vec![(b"username_nonce", address, 2u64).encode()]);
+
+		let future = validate_nonce(set_username_call(address, 3)).unwrap();
+		assert_eq!(future.requires, current.provides);
+		assert_eq!(future.provides, vec![(b"username_nonce", address, 3u64).encode()]);
+	});
+}
+
+#[test]
+fn nonce_extension_ignores_other_calls() {
+	new_test_ext().execute_with(|| {
+		let call = RuntimeCall::Template(crate::Call::set_paused { paused: true });
+		assert_eq!(validate_nonce(call), Ok(ValidTransaction::default()));
+		let call = RuntimeCall::System(frame_system::Call::remark { remark: vec![] });
+		assert_eq!(validate_nonce(call), Ok(ValidTransaction::default()));
+	});
+}
+
+fn new_offchain_test_ext() -> sp_io::TestExternalities {
+	new_offchain_test_ext_with_usernames(vec![])
+}
+
+fn new_offchain_test_ext_with_usernames(
+	usernames: Vec<(H160, Vec<u8>)>,
+) -> sp_io::TestExternalities {
+	let mut ext = new_test_ext_with_usernames(usernames);
+	let (offchain, _state) = TestOffchainExt::new();
+	ext.register_extension(OffchainDbExt::new(offchain.clone()));
+	ext.register_extension(OffchainWorkerExt::new(offchain));
+	ext
+}
+
+fn offchain_owner(username: &[u8]) -> Option<H160> {
+	StorageValueRef::persistent(&Template::offchain_owner_key(username)).get().unwrap()
+}
+
+#[test]
+fn offchain_worker_indexes_usernames() {
+	new_offchain_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let (pair, address) = eth_account(1);
+		assert_ok!(set_username(&pair, address, b"Alice"));
+		Template::offchain_worker(1);
+		assert_eq!(offchain_owner(b"alice"), Some(address));
+
+		System::reset_events();
+		System::set_block_number(2);
+		assert_ok!(set_username(&pair, address, b"bob"));
+		Template::offchain_worker(2);
+		assert_eq!(offchain_owner(b"alice"), None);
+		assert_eq!(offchain_owner(b"BOB"), Some(address));
+
+		System::reset_events();
+		System::set_block_number(3);
+		assert_ok!(clear_username(&pair, address));
+		Template::offchain_worker(3);
+		assert_eq!(offchain_owner(b"bob"), None);
+	});
+}
+
+#[test]
+fn offchain_worker_backfills_genesis_usernames() {
+	let (pair, address) = eth_account(1);
+	new_offchain_test_ext_with_usernames(vec![(address, b"Genesis".to_vec())]).execute_with(|| {
+		System::set_block_number(1);
+		Template::offchain_worker(1);
+		assert_eq!(offchain_owner(b"genesis"), Some(address));
+
+		System::set_block_number(2);
+		assert_ok!(clear_username(&pair, address));
+		Template::offchain_worker(2);
+		assert_eq!(offchain_owner(b"genesis"), None);
+	});
+}
+
+#[test]
+fn offchain_worker_respects_indexing_flag() {
+	new_offchain_test_ext().execute_with(|| {
+		OffchainIndexing::set(false);
+		System::set_block_number(1);
+		let (pair, address) = eth_account(1);
+		assert_ok!(set_username(&pair, address, b"alice"));
+		Template::offchain_worker(1);
+		assert_eq!(offchain_owner(b"alice"), None);
+	});
+}
+
+#[test]
+fn registration_block_survives_renames_only() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(3);
+		let (pair, address) = eth_account(1);
+		assert_eq!(Template::get_registration_block(address), None);
+		assert_ok!(set_username(&pair, address, b"alice"));
+		assert_eq!(Template::get_registration_block(address), Some(3));
+
+		System::set_block_number(7);
+		assert_ok!(set_username(&pair, address, b"alice2"));
+		assert_eq!(Template::get_registration_block(address), Some(3));
+
+		assert_ok!(clear_username(&pair, address));
+		assert_eq!(Template::get_registration_block(address), None);
+
+		System::set_block_number(10);
+		assert_ok!(set_username(&pair, address, b"alice"));
+		assert_eq!(Template::get_registration_block(address), Some(10));
+	});
+}
+
+fn transfer_username(pair: &ecdsa::Pair, from: H160, to: H160) -> DispatchResult {
+	let nonce = Nonces::<Test>::get(from);
+	let signature = eth_sign(pair, &Template::transfer_message(&from, &to, nonce));
+	Template::transfer_username(RuntimeOrigin::signed(1), from, to, nonce, signature)
+}
+
+#[test]
+fn transfer_username_moves_name() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let (pair, from) = eth_account(1);
+		let (_, to) = eth_account(2);
+		assert_ok!(set_username(&pair, from, b"alice"));
+
+		assert_ok!(transfer_username(&pair, from, to));
+
+		assert_eq!(Template::get_username(from), None);
+		assert_eq!(Template::get_username(to), Some(b"alice".to_vec()));
+		assert_eq!(Template::get_owner(b"alice".to_vec()), Some(to));
+		assert_eq!(Template::get_registration_block(to), Some(1));
+		assert_eq!(UsernameDeposits::<Test>::get(from), None);
+		assert_eq!(UsernameDeposits::<Test>::get(to), Some((1, 10)));
+		assert_eq!(UsernameCount::<Test>::get(), 1);
+		assert_eq!(Nonces::<Test>::get(from), 2);
+		assert_ok!(Template::do_try_state());
+		System::assert_last_event(
+			Event::UsernameTransferred {
+				from,
+				to,
+				username: b"alice".to_vec().try_into().unwrap(),
+			}
+			.into(),
+		);
+	});
+}
+
+#[test]
+fn transfer_username_rejects_registered_recipient() {
+	new_test_ext().execute_with(|| {
+		let (alice, from) = eth_account(1);
+		let (bob, to) = eth_account(2);
+		assert_ok!(set_username(&alice, from, b"alice"));
+		assert_ok!(set_username(&bob, to, b"bob"));
+
+		assert_noop!(transfer_username(&alice, from, to), Error::<Test>::RecipientHasUsername);
+	});
+}
+
+#[test]
+fn transfer_username_requires_owner_signature() {
+	new_test_ext().execute_with(|| {
+		let (alice, from) = eth_account(1);
+		let (bob, to) = eth_account(2);
+		assert_ok!(set_username(&alice, from, b"alice"));
+
+		assert_noop!(transfer_username(&bob, from, to), Error::<Test>::SignatureAddressMismatch);
+		assert_noop!(transfer_username(&alice, from, from), Error::<Test>::InvalidAddress);
+		assert_noop!(transfer_username(&bob, to, from), Error::<Test>::UsernameNotFound);
+	});
+}
+
+#[test]
+fn transfer_username_requires_allowlisted_recipient() {
+	new_test_ext().execute_with(|| {
+		RequireAllowlist::set(true);
+		let (pair, from) = eth_account(1);
+		let (_, to) = eth_account(2);
+		assert_ok!(Template::add_to_allowlist(RuntimeOrigin::root(), from));
+		assert_ok!(set_username(&pair, from, b"alice"));
+
+		assert_noop!(transfer_username(&pair, from, to), Error::<Test>::NotAllowed);
+
+		assert_ok!(Template::add_to_allowlist(RuntimeOrigin::root(), to));
+		assert_ok!(transfer_username(&pair, from, to));
+		assert_eq!(Template::get_username(to), Some(b"alice".to_vec()));
+	});
+}
+
+#[test]
+fn transfer_username_respects_recipient_cooldown() {
+	ExtBuilder::default().change_cooldown(5).build().execute_with(|| {
+		let (alice, from) = eth_account(1);
+		let (bob, to) = eth_account(2);
+		System::set_block_number(1);
+		assert_ok!(set_username(&alice, from, b"alice"));
+		assert_ok!(set_username(&bob, to, b"bob"));
+		assert_ok!(clear_username(&bob, to));
+
+		System::set_block_number(5);
+		assert_noop!(transfer_username(&alice, from, to), Error::<Test>::ChangeTooSoon);
+
+		System::set_block_number(6);
+		assert_ok!(transfer_username(&alice, from, to));
+		assert_eq!(Template::get_username(to), Some(b"alice".to_vec()));
+	});
+}
+
+#[test]
+fn reset_nonce_sets_the_next_nonce() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let (pair, address) = eth_account(1);
+
+		assert_noop!(
+			Template::reset_nonce(RuntimeOrigin::signed(1), address, 5),
+			DispatchError::BadOrigin
+		);
+		assert_ok!(Template::reset_nonce(RuntimeOrigin::root(), address, 5));
+		System::assert_last_event(Event::NonceReset { eth_address: address, new_nonce: 5 }.into());
+		assert_eq!(Template::get_nonce(address), 5);
+
+		let signature = eth_sign(&pair, &set_username_message(b"alice", 0));
+		assert_noop!(
+			Template::set_username(
+				RuntimeOrigin::signed(1),
+				address,
+				b"alice".to_vec(),
+				0,
+				signature
+			),
+			Error::<Test>::InvalidNonce.with_weight(Template::set_username_rejected_weight())
+		);
+		assert_ok!(set_username(&pair, address, b"alice"));
+		assert_eq!(Template::get_nonce(address), 6);
+	});
+}
+
+fn set_username_with_nonce(
+	pair: &ecdsa::Pair,
+	eth_address: H160,
+	username: &[u8],
+	nonce: u64,
+) -> DispatchResult {
+	let signature = eth_sign(pair, &set_username_message(username, nonce));
+	Template::set_username(
+		RuntimeOrigin::signed(1),
+		eth_address,
+		username.to_vec(),
+		nonce,
+		signature,
+	)
+	.map(|_| ())
+	.map_err(|e| e.error)
+}
+
+#[test]
+fn strict_nonces_reject_skips() {
+	new_test_ext().execute_with(|| {
+		let (pair, address) = eth_account(1);
+		assert_noop!(
+			set_username_with_nonce(&pair, address, b"alice", 2),
+			Error::<Test>::InvalidNonce
+		);
+		assert_ok!(set_username_with_nonce(&pair, address, b"alice", 0));
+	});
+}
+
+#[test]
+fn nonce_skip_mode_accepts_later_nonces_once() {
+	new_test_ext().execute_with(|| {
+		AllowNonceSkip::set(true);
+		let (pair, address) = eth_account(1);
+
+		assert_ok!(set_username_with_nonce(&pair, address, b"alice", 2));
+		assert_eq!(Template::get_nonce(address), 3);
+
+		// The used nonce and the skipped ones below it are all retired.
+		assert_noop!(
+			set_username_with_nonce(&pair, address, b"alice2", 2),
+			Error::<Test>::InvalidNonce
+		);
+		assert_noop!(
+			set_username_with_nonce(&pair, address, b"alice2", 1),
+			Error::<Test>::InvalidNonce
+		);
+		assert_ok!(set_username_with_nonce(&pair, address, b"alice2", 3));
+	});
+}
+
+#[test]
+fn nonce_extension_skip_mode_needs_no_predecessor() {
+	new_test_ext().execute_with(|| {
+		AllowNonceSkip::set(true);
+		let (_, address) = eth_account(1);
+
+		let future = validate_nonce(set_username_call(address, 3)).unwrap();
+		assert!(future.requires.is_empty());
+	});
+}
+
+#[test]
+fn blocked_addresses_cannot_register_until_unblocked() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let (pair, address) = eth_account(1);
+
+		assert_noop!(
+			Template::block_address(RuntimeOrigin::signed(1), address),
+			DispatchError::BadOrigin
+		);
+		assert_ok!(Template::block_address(RuntimeOrigin::root(), address));
+		System::assert_last_event(Event::AddressBlocked { eth_address: address }.into());
+		assert_noop!(set_username(&pair, address, b"alice"), Error::<Test>::AddressBlocked);
+
+		assert_ok!(Template::unblock_address(RuntimeOrigin::root(), address));
+		System::assert_last_event(Event::AddressUnblocked { eth_address: address }.into());
+		assert_ok!(set_username(&pair, address, b"alice"));
+		assert_noop!(
+			Template::unblock_address(RuntimeOrigin::root(), address),
+			Error::<Test>::AddressNotBlocked
+		);
+	});
+}
+
+#[test]
+fn transfer_to_blocked_address_is_rejected() {
+	new_test_ext().execute_with(|| {
+		let (pair, from) = eth_account(1);
+		let (_, to) = eth_account(2);
+		assert_ok!(set_username(&pair, from, b"alice"));
+
+		assert_ok!(Template::block_address(RuntimeOrigin::root(), to));
+		assert_noop!(transfer_username(&pair, from, to), Error::<Test>::AddressBlocked);
+
+		assert_ok!(Template::unblock_address(RuntimeOrigin::root(), to));
+		assert_ok!(transfer_username(&pair, from, to));
+	});
+}
+
+#[test]
+fn substrate_account_claims_username() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(Template::set_substrate_username(RuntimeOrigin::signed(2), b"dave".to_vec()));
+		System::assert_last_event(
+			Event::SubstrateUsernameSet { who: 2, username: b"dave".to_vec().try_into().unwrap() }
+				.into(),
+		);
+		assert_eq!(Template::get_substrate_username(2), Some(b"dave".to_vec()));
+		assert!(!Template::is_username_available(b"DAVE".to_vec()));
+
+		// Renaming releases the previous name.
+		assert_ok!(Template::set_substrate_username(RuntimeOrigin::signed(2), b"dave2".to_vec()));
+		assert_ok!(Template::set_substrate_username(RuntimeOrigin::signed(3), b"dave".to_vec()));
+		assert_noop!(
+			Template::set_substrate_username(RuntimeOrigin::signed(3), b"Dave2".to_vec()),
+			Error::<Test>::UsernameTaken
+		);
+		assert_noop!(
+			Template::set_substrate_username(RuntimeOrigin::signed(3), b"1dave".to_vec()),
+			Error::<Test>::InvalidUsername
+		);
+	});
+}
+
+#[test]
+fn substrate_and_ethereum_usernames_share_one_namespace() {
+	new_test_ext().execute_with(|| {
+		let (pair, address) = eth_account(1);
+		assert_ok!(set_username(&pair, address, b"alice"));
+		assert_noop!(
+			Template::set_substrate_username(RuntimeOrigin::signed(2), b"Alice".to_vec()),
+			Error::<Test>::UsernameTaken
+		);
+
+		assert_ok!(Template::set_substrate_username(RuntimeOrigin::signed(2), b"bob".to_vec()));
+		let (bob, bob_address) = eth_account(2);
+		assert_noop!(set_username(&bob, bob_address, b"BOB"), Error::<Test>::UsernameTaken);
+		assert_eq!(Template::get_owner(b"bob".to_vec()), None);
 	});
 }
@@ -1,29 +1,16 @@
-
-//! Autogenerated weights for pallet_template
+//! Placeholder weights for pallet_template.
 //!
-//! THIS FILE WAS AUTO-GENERATED USING THE SUBSTRATE BENCHMARK CLI VERSION 4.0.0-dev
-//! DATE: 2023-04-06, STEPS: `50`, REPEAT: `20`, LOW RANGE: `[]`, HIGH RANGE: `[]`
-//! WORST CASE MAP SIZE: `1000000`
-//! HOSTNAME: `Alexs-MacBook-Pro-2.local`, CPU: `<UNKNOWN>`
-//! EXECUTION: Some(Wasm), WASM-EXECUTION: Compiled, CHAIN: Some("dev"), DB CACHE: 1024
-
-// Executed Command:
-// ../../target/release/node-template
-// benchmark
-// pallet
-// --chain
-// dev
-// --pallet
-// pallet_template
-// --extrinsic
-// *
-// --steps=50
-// --repeat=20
-// --wasm-execution=compiled
-// --output
-// pallets/template/src/weights.rs
-// --template
-// ../../.maintain/frame-weight-template.hbs
+//! NOT BENCHMARKED. These numbers are written by hand and were never measured. The storage
+//! items listed for each function, and its read and write counts, are taken from the code along
+//! its most expensive path, counting each key once. The execution times and proof sizes are
+//! rough guesses. Regenerate this file with the benchmark CLI on reference hardware before
+//! relying on it:
+//!
+//! ```text
+//! ./target/release/solochain-template-node benchmark pallet --chain dev \
+//!     --pallet pallet_template --extrinsic '*' --steps 50 --repeat 20 \
+//!     --wasm-execution compiled --output pallets/template/src/weights.rs
+//! ```
 
 #![cfg_attr(rustfmt, rustfmt_skip)]
 #![allow(unused_parens)]
@@ -34,57 +21,695 @@ use core::marker::PhantomData;
 
 /// Weight functions needed for pallet_template.
 pub trait WeightInfo {
-	fn do_something() -> Weight;
-	fn cause_error() -> Weight;
+	fn set_username(l: u32, ) -> Weight;
+	fn clear_username(a: u32, ) -> Weight;
+	fn transfer_username(a: u32, ) -> Weight;
+	fn force_clear_username(a: u32, ) -> Weight;
+	fn reserve_username() -> Weight;
+	fn unreserve_username() -> Weight;
+	fn set_paused() -> Weight;
+	fn add_to_allowlist() -> Weight;
+	fn remove_from_allowlist() -> Weight;
+	fn block_address() -> Weight;
+	fn unblock_address() -> Weight;
+	fn force_expire_all(n: u32, ) -> Weight;
+	fn expire_username(a: u32, ) -> Weight;
+	fn set_profile() -> Weight;
+	fn link_alias(a: u32, ) -> Weight;
+	fn unlink_alias(a: u32, ) -> Weight;
+	fn reset_nonce() -> Weight;
+	fn set_substrate_username(l: u32, ) -> Weight;
 }
 
 /// Weights for pallet_template using the Substrate node and recommended hardware.
 pub struct SubstrateWeight<T>(PhantomData<T>);
 impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
-	/// Storage: Template Something (r:0 w:1)
-	/// Proof: Template Something (max_values: Some(1), max_size: Some(4), added: 499, mode: MaxEncodedLen)
-	fn do_something() -> Weight {
-		// Proof Size summary in bytes:
-		//  Measured:  `0`
-		//  Estimated: `0`
-		// Minimum execution time: 8_000_000 picoseconds.
-		Weight::from_parts(9_000_000, 0)
+	/// Storage: `Template::Paused` (r:1 w:0)
+	/// Proof: `Template::Paused` (`max_values`: Some(1), `max_size`: Some(1), added: 496, mode: `MaxEncodedLen`)
+	/// Storage: `Template::RegistrationsThisBlock` (r:1 w:1)
+	/// Proof: `Template::RegistrationsThisBlock` (`max_values`: Some(1), `max_size`: Some(4), added: 499, mode: `MaxEncodedLen`)
+	/// Storage: `Template::Blocklist` (r:1 w:0)
+	/// Proof: `Template::Blocklist` (`max_values`: None, `max_size`: Some(36), added: 2511, mode: `MaxEncodedLen`)
+	/// Storage: `Template::Allowlist` (r:1 w:0)
+	/// Proof: `Template::Allowlist` (`max_values`: None, `max_size`: Some(36), added: 2511, mode: `MaxEncodedLen`)
+	/// Storage: `Template::AliasOf` (r:1 w:0)
+	/// Proof: `Template::AliasOf` (`max_values`: None, `max_size`: Some(56), added: 2531, mode: `MaxEncodedLen`)
+	/// Storage: `Template::Nonces` (r:1 w:1)
+	/// Proof: `Template::Nonces` (`max_values`: None, `max_size`: Some(44), added: 2519, mode: `MaxEncodedLen`)
+	/// Storage: `Template::LastUsernameChange` (r:1 w:1)
+	/// Proof: `Template::LastUsernameChange` (`max_values`: None, `max_size`: Some(40), added: 2515, mode: `MaxEncodedLen`)
+	/// Storage: `Template::UsernameOwners` (r:1 w:1)
+	/// Proof: `Template::UsernameOwners` (`max_values`: None, `max_size`: Some(69), added: 2544, mode: `MaxEncodedLen`)
+	/// Storage: `Template::SubstrateUsernameOwners` (r:1 w:0)
+	/// Proof: `Template::SubstrateUsernameOwners` (`max_values`: None, `max_size`: Some(81), added: 2556, mode: `MaxEncodedLen`)
+	/// Storage: `Template::ReservedUsernames` (r:1 w:0)
+	/// Proof: `Template::ReservedUsernames` (`max_values`: None, `max_size`: Some(49), added: 2524, mode: `MaxEncodedLen`)
+	/// Storage: `Template::UsernameDeposits` (r:1 w:1)
+	/// Proof: `Template::UsernameDeposits` (`max_values`: None, `max_size`: Some(84), added: 2559, mode: `MaxEncodedLen`)
+	/// Storage: `System::Account` (r:1 w:1)
+	/// Proof: `System::Account` (`max_values`: None, `max_size`: Some(128), added: 2603, mode: `MaxEncodedLen`)
+	/// Storage: `Template::Usernames` (r:1 w:1)
+	/// Proof: `Template::Usernames` (`max_values`: None, `max_size`: Some(74), added: 2549, mode: `MaxEncodedLen`)
+	/// Storage: `Template::UsernameCount` (r:1 w:1)
+	/// Proof: `Template::UsernameCount` (`max_values`: Some(1), `max_size`: Some(4), added: 499, mode: `MaxEncodedLen`)
+	/// Storage: `Template::UsernameHistory` (r:1 w:1)
+	/// Proof: `Template::UsernameHistory` (`max_values`: None, `max_size`: Some(222), added: 2697, mode: `MaxEncodedLen`)
+	/// Storage: `Template::RegisteredAt` (r:0 w:1)
+	/// Proof: `Template::RegisteredAt` (`max_values`: None, `max_size`: Some(40), added: 2515, mode: `MaxEncodedLen`)
+	/// Storage: `Template::ExpiryQueue` (r:0 w:1)
+	/// Proof: `Template::ExpiryQueue` (`max_values`: None, `max_size`: Some(48), added: 2523, mode: `MaxEncodedLen`)
+	/// The range of component `l` is `[3, 32]`.
+	fn set_username(l: u32, ) -> Weight {
+		// Estimated proof size: `3687` bytes.
+		Weight::from_parts(59_127_000, 3687)
+			.saturating_add(Weight::from_parts(4_372, 0).saturating_mul(l.into()))
+			.saturating_add(T::DbWeight::get().reads(15_u64))
+			.saturating_add(T::DbWeight::get().writes(11_u64))
+	}
+	/// Storage: `Template::Paused` (r:1 w:0)
+	/// Proof: `Template::Paused` (`max_values`: Some(1), `max_size`: Some(1), added: 496, mode: `MaxEncodedLen`)
+	/// Storage: `Template::Nonces` (r:1 w:1)
+	/// Proof: `Template::Nonces` (`max_values`: None, `max_size`: Some(44), added: 2519, mode: `MaxEncodedLen`)
+	/// Storage: `Template::Usernames` (r:1 w:1)
+	/// Proof: `Template::Usernames` (`max_values`: None, `max_size`: Some(74), added: 2549, mode: `MaxEncodedLen`)
+	/// Storage: `Template::UsernameCount` (r:1 w:1)
+	/// Proof: `Template::UsernameCount` (`max_values`: Some(1), `max_size`: Some(4), added: 499, mode: `MaxEncodedLen`)
+	/// Storage: `Template::UsernameDeposits` (r:1 w:1)
+	/// Proof: `Template::UsernameDeposits` (`max_values`: None, `max_size`: Some(84), added: 2559, mode: `MaxEncodedLen`)
+	/// Storage: `System::Account` (r:1 w:1)
+	/// Proof: `System::Account` (`max_values`: None, `max_size`: Some(128), added: 2603, mode: `MaxEncodedLen`)
+	/// Storage: `Template::Aliases` (r:1 w:1)
+	/// Proof: `Template::Aliases` (`max_values`: None, `max_size`: Some(197), added: 2672, mode: `MaxEncodedLen`)
+	/// Storage: `Template::UsernameHistory` (r:1 w:1)
+	/// Proof: `Template::UsernameHistory` (`max_values`: None, `max_size`: Some(222), added: 2697, mode: `MaxEncodedLen`)
+	/// Storage: `Template::UsernameOwners` (r:0 w:1)
+	/// Proof: `Template::UsernameOwners` (`max_values`: None, `max_size`: Some(69), added: 2544, mode: `MaxEncodedLen`)
+	/// Storage: `Template::ExpiryQueue` (r:0 w:1)
+	/// Proof: `Template::ExpiryQueue` (`max_values`: None, `max_size`: Some(48), added: 2523, mode: `MaxEncodedLen`)
+	/// Storage: `Template::Profiles` (r:0 w:1)
+	/// Proof: `Template::Profiles` (`max_values`: None, `max_size`: Some(642), added: 3117, mode: `MaxEncodedLen`)
+	/// Storage: `Template::RegisteredAt` (r:0 w:1)
+	/// Proof: `Template::RegisteredAt` (`max_values`: None, `max_size`: Some(40), added: 2515, mode: `MaxEncodedLen`)
+	/// Storage: `Template::AliasOf` (r:0 w:8)
+	/// Proof: `Template::AliasOf` (`max_values`: None, `max_size`: Some(56), added: 2531, mode: `MaxEncodedLen`)
+	/// The range of component `a` is `[0, 8]`.
+	fn clear_username(a: u32, ) -> Weight {
+		// Estimated proof size: `3687` bytes.
+		Weight::from_parts(63_842_000, 3687)
+			.saturating_add(Weight::from_parts(1_447_000, 0).saturating_mul(a.into()))
+			.saturating_add(T::DbWeight::get().reads(8_u64))
+			.saturating_add(T::DbWeight::get().writes(11_u64))
+			.saturating_add(T::DbWeight::get().writes((1_u64).saturating_mul(a.into())))
+	}
+	/// Storage: `Template::Paused` (r:1 w:0)
+	/// Proof: `Template::Paused` (`max_values`: Some(1), `max_size`: Some(1), added: 496, mode: `MaxEncodedLen`)
+	/// Storage: `Template::Blocklist` (r:2 w:0)
+	/// Proof: `Template::Blocklist` (`max_values`: None, `max_size`: Some(36), added: 2511, mode: `MaxEncodedLen`)
+	/// Storage: `Template::Allowlist` (r:1 w:0)
+	/// Proof: `Template::Allowlist` (`max_values`: None, `max_size`: Some(36), added: 2511, mode: `MaxEncodedLen`)
+	/// Storage: `Template::Nonces` (r:1 w:1)
+	/// Proof: `Template::Nonces` (`max_values`: None, `max_size`: Some(44), added: 2519, mode: `MaxEncodedLen`)
+	/// Storage: `Template::Usernames` (r:2 w:2)
+	/// Proof: `Template::Usernames` (`max_values`: None, `max_size`: Some(74), added: 2549, mode: `MaxEncodedLen`)
+	/// Storage: `Template::AliasOf` (r:1 w:8)
+	/// Proof: `Template::AliasOf` (`max_values`: None, `max_size`: Some(56), added: 2531, mode: `MaxEncodedLen`)
+	/// Storage: `Template::UsernameDeposits` (r:1 w:2)
+	/// Proof: `Template::UsernameDeposits` (`max_values`: None, `max_size`: Some(84), added: 2559, mode: `MaxEncodedLen`)
+	/// Storage: `Template::RegisteredAt` (r:1 w:2)
+	/// Proof: `Template::RegisteredAt` (`max_values`: None, `max_size`: Some(40), added: 2515, mode: `MaxEncodedLen`)
+	/// Storage: `Template::Profiles` (r:1 w:2)
+	/// Proof: `Template::Profiles` (`max_values`: None, `max_size`: Some(642), added: 3117, mode: `MaxEncodedLen`)
+	/// Storage: `Template::Aliases` (r:1 w:1)
+	/// Proof: `Template::Aliases` (`max_values`: None, `max_size`: Some(197), added: 2672, mode: `MaxEncodedLen`)
+	/// Storage: `Template::UsernameHistory` (r:2 w:2)
+	/// Proof: `Template::UsernameHistory` (`max_values`: None, `max_size`: Some(222), added: 2697, mode: `MaxEncodedLen`)
+	/// Storage: `Template::UsernameOwners` (r:0 w:1)
+	/// Proof: `Template::UsernameOwners` (`max_values`: None, `max_size`: Some(69), added: 2544, mode: `MaxEncodedLen`)
+	/// Storage: `Template::ExpiryQueue` (r:0 w:2)
+	/// Proof: `Template::ExpiryQueue` (`max_values`: None, `max_size`: Some(48), added: 2523, mode: `MaxEncodedLen`)
+	/// Storage: `Template::LastUsernameChange` (r:1 w:1)
+	/// Proof: `Template::LastUsernameChange` (`max_values`: None, `max_size`: Some(40), added: 2515, mode: `MaxEncodedLen`)
+	/// The range of component `a` is `[0, 8]`.
+	fn transfer_username(a: u32, ) -> Weight {
+		// Estimated proof size: `4107` bytes.
+		Weight::from_parts(76_219_000, 4107)
+			.saturating_add(Weight::from_parts(1_512_000, 0).saturating_mul(a.into()))
+			.saturating_add(T::DbWeight::get().reads(15_u64))
+			.saturating_add(T::DbWeight::get().writes(16_u64))
+			.saturating_add(T::DbWeight::get().writes((1_u64).saturating_mul(a.into())))
+	}
+	/// Storage: `Template::Usernames` (r:1 w:1)
+	/// Proof: `Template::Usernames` (`max_values`: None, `max_size`: Some(74), added: 2549, mode: `MaxEncodedLen`)
+	/// Storage: `Template::UsernameCount` (r:1 w:1)
+	/// Proof: `Template::UsernameCount` (`max_values`: Some(1), `max_size`: Some(4), added: 499, mode: `MaxEncodedLen`)
+	/// Storage: `Template::UsernameDeposits` (r:1 w:1)
+	/// Proof: `Template::UsernameDeposits` (`max_values`: None, `max_size`: Some(84), added: 2559, mode: `MaxEncodedLen`)
+	/// Storage: `System::Account` (r:1 w:1)
+	/// Proof: `System::Account` (`max_values`: None, `max_size`: Some(128), added: 2603, mode: `MaxEncodedLen`)
+	/// Storage: `Template::Aliases` (r:1 w:1)
+	/// Proof: `Template::Aliases` (`max_values`: None, `max_size`: Some(197), added: 2672, mode: `MaxEncodedLen`)
+	/// Storage: `Template::UsernameHistory` (r:1 w:1)
+	/// Proof: `Template::UsernameHistory` (`max_values`: None, `max_size`: Some(222), added: 2697, mode: `MaxEncodedLen`)
+	/// Storage: `Template::UsernameOwners` (r:0 w:1)
+	/// Proof: `Template::UsernameOwners` (`max_values`: None, `max_size`: Some(69), added: 2544, mode: `MaxEncodedLen`)
+	/// Storage: `Template::ExpiryQueue` (r:0 w:1)
+	/// Proof: `Template::ExpiryQueue` (`max_values`: None, `max_size`: Some(48), added: 2523, mode: `MaxEncodedLen`)
+	/// Storage: `Template::Profiles` (r:0 w:1)
+	/// Proof: `Template::Profiles` (`max_values`: None, `max_size`: Some(642), added: 3117, mode: `MaxEncodedLen`)
+	/// Storage: `Template::RegisteredAt` (r:0 w:1)
+	/// Proof: `Template::RegisteredAt` (`max_values`: None, `max_size`: Some(40), added: 2515, mode: `MaxEncodedLen`)
+	/// Storage: `Template::AliasOf` (r:0 w:8)
+	/// Proof: `Template::AliasOf` (`max_values`: None, `max_size`: Some(56), added: 2531, mode: `MaxEncodedLen`)
+	/// The range of component `a` is `[0, 8]`.
+	fn force_clear_username(a: u32, ) -> Weight {
+		// Estimated proof size: `3687` bytes.
+		Weight::from_parts(27_318_000, 3687)
+			.saturating_add(Weight::from_parts(1_447_000, 0).saturating_mul(a.into()))
+			.saturating_add(T::DbWeight::get().reads(6_u64))
+			.saturating_add(T::DbWeight::get().writes(10_u64))
+			.saturating_add(T::DbWeight::get().writes((1_u64).saturating_mul(a.into())))
+	}
+	/// Storage: `Template::ReservedUsernames` (r:0 w:1)
+	/// Proof: `Template::ReservedUsernames` (`max_values`: None, `max_size`: Some(49), added: 2524, mode: `MaxEncodedLen`)
+	fn reserve_username() -> Weight {
+		// Estimated proof size: `0` bytes.
+		Weight::from_parts(9_412_000, 0)
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: `Template::ReservedUsernames` (r:1 w:1)
+	/// Proof: `Template::ReservedUsernames` (`max_values`: None, `max_size`: Some(49), added: 2524, mode: `MaxEncodedLen`)
+	fn unreserve_username() -> Weight {
+		// Estimated proof size: `3514` bytes.
+		Weight::from_parts(12_087_000, 3514)
+			.saturating_add(T::DbWeight::get().reads(1_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: `Template::Paused` (r:0 w:1)
+	/// Proof: `Template::Paused` (`max_values`: Some(1), `max_size`: Some(1), added: 496, mode: `MaxEncodedLen`)
+	fn set_paused() -> Weight {
+		// Estimated proof size: `0` bytes.
+		Weight::from_parts(6_203_000, 0)
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: `Template::Allowlist` (r:0 w:1)
+	/// Proof: `Template::Allowlist` (`max_values`: None, `max_size`: Some(36), added: 2511, mode: `MaxEncodedLen`)
+	fn add_to_allowlist() -> Weight {
+		// Estimated proof size: `0` bytes.
+		Weight::from_parts(8_951_000, 0)
 			.saturating_add(T::DbWeight::get().writes(1_u64))
 	}
-	/// Storage: Template Something (r:1 w:1)
-	/// Proof: Template Something (max_values: Some(1), max_size: Some(4), added: 499, mode: MaxEncodedLen)
-	fn cause_error() -> Weight {
-		// Proof Size summary in bytes:
-		//  Measured:  `32`
-		//  Estimated: `1489`
-		// Minimum execution time: 6_000_000 picoseconds.
-		Weight::from_parts(6_000_000, 1489)
+	/// Storage: `Template::Allowlist` (r:1 w:1)
+	/// Proof: `Template::Allowlist` (`max_values`: None, `max_size`: Some(36), added: 2511, mode: `MaxEncodedLen`)
+	fn remove_from_allowlist() -> Weight {
+		// Estimated proof size: `3501` bytes.
+		Weight::from_parts(11_634_000, 3501)
 			.saturating_add(T::DbWeight::get().reads(1_u64))
 			.saturating_add(T::DbWeight::get().writes(1_u64))
 	}
+	/// Storage: `Template::Blocklist` (r:0 w:1)
+	/// Proof: `Template::Blocklist` (`max_values`: None, `max_size`: Some(36), added: 2511, mode: `MaxEncodedLen`)
+	fn block_address() -> Weight {
+		// Estimated proof size: `0` bytes.
+		Weight::from_parts(8_874_000, 0)
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: `Template::Blocklist` (r:1 w:1)
+	/// Proof: `Template::Blocklist` (`max_values`: None, `max_size`: Some(36), added: 2511, mode: `MaxEncodedLen`)
+	fn unblock_address() -> Weight {
+		// Estimated proof size: `3501` bytes.
+		Weight::from_parts(11_502_000, 3501)
+			.saturating_add(T::DbWeight::get().reads(1_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: `Template::Usernames` (r:101 w:100)
+	/// Proof: `Template::Usernames` (`max_values`: None, `max_size`: Some(74), added: 2549, mode: `MaxEncodedLen`)
+	/// Storage: `Template::UsernameCount` (r:1 w:1)
+	/// Proof: `Template::UsernameCount` (`max_values`: Some(1), `max_size`: Some(4), added: 499, mode: `MaxEncodedLen`)
+	/// Storage: `Template::UsernameDeposits` (r:100 w:100)
+	/// Proof: `Template::UsernameDeposits` (`max_values`: None, `max_size`: Some(84), added: 2559, mode: `MaxEncodedLen`)
+	/// Storage: `System::Account` (r:100 w:100)
+	/// Proof: `System::Account` (`max_values`: None, `max_size`: Some(128), added: 2603, mode: `MaxEncodedLen`)
+	/// Storage: `Template::Aliases` (r:100 w:100)
+	/// Proof: `Template::Aliases` (`max_values`: None, `max_size`: Some(197), added: 2672, mode: `MaxEncodedLen`)
+	/// Storage: `Template::UsernameHistory` (r:100 w:100)
+	/// Proof: `Template::UsernameHistory` (`max_values`: None, `max_size`: Some(222), added: 2697, mode: `MaxEncodedLen`)
+	/// Storage: `Template::UsernameOwners` (r:0 w:100)
+	/// Proof: `Template::UsernameOwners` (`max_values`: None, `max_size`: Some(69), added: 2544, mode: `MaxEncodedLen`)
+	/// Storage: `Template::ExpiryQueue` (r:0 w:100)
+	/// Proof: `Template::ExpiryQueue` (`max_values`: None, `max_size`: Some(48), added: 2523, mode: `MaxEncodedLen`)
+	/// Storage: `Template::Profiles` (r:0 w:100)
+	/// Proof: `Template::Profiles` (`max_values`: None, `max_size`: Some(642), added: 3117, mode: `MaxEncodedLen`)
+	/// Storage: `Template::RegisteredAt` (r:0 w:100)
+	/// Proof: `Template::RegisteredAt` (`max_values`: None, `max_size`: Some(40), added: 2515, mode: `MaxEncodedLen`)
+	/// Storage: `Template::AliasOf` (r:0 w:800)
+	/// Proof: `Template::AliasOf` (`max_values`: None, `max_size`: Some(56), added: 2531, mode: `MaxEncodedLen`)
+	/// The range of component `n` is `[0, 100]`. Every username has the maximum of 8 aliases.
+	fn force_expire_all(n: u32, ) -> Weight {
+		// Estimated proof size: `3539 + n * 13080` bytes.
+		Weight::from_parts(5_214_000, 3539)
+			.saturating_add(Weight::from_parts(39_106_000, 0).saturating_mul(n.into()))
+			.saturating_add(T::DbWeight::get().reads(2_u64))
+			.saturating_add(T::DbWeight::get().reads((5_u64).saturating_mul(n.into())))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+			.saturating_add(T::DbWeight::get().writes((17_u64).saturating_mul(n.into())))
+			.saturating_add(Weight::from_parts(0, 13080).saturating_mul(n.into()))
+	}
+	/// Storage: `Template::Usernames` (r:1 w:1)
+	/// Proof: `Template::Usernames` (`max_values`: None, `max_size`: Some(74), added: 2549, mode: `MaxEncodedLen`)
+	/// Storage: `Template::UsernameCount` (r:1 w:1)
+	/// Proof: `Template::UsernameCount` (`max_values`: Some(1), `max_size`: Some(4), added: 499, mode: `MaxEncodedLen`)
+	/// Storage: `Template::UsernameDeposits` (r:1 w:1)
+	/// Proof: `Template::UsernameDeposits` (`max_values`: None, `max_size`: Some(84), added: 2559, mode: `MaxEncodedLen`)
+	/// Storage: `System::Account` (r:1 w:1)
+	/// Proof: `System::Account` (`max_values`: None, `max_size`: Some(128), added: 2603, mode: `MaxEncodedLen`)
+	/// Storage: `Template::Aliases` (r:1 w:1)
+	/// Proof: `Template::Aliases` (`max_values`: None, `max_size`: Some(197), added: 2672, mode: `MaxEncodedLen`)
+	/// Storage: `Template::UsernameHistory` (r:1 w:1)
+	/// Proof: `Template::UsernameHistory` (`max_values`: None, `max_size`: Some(222), added: 2697, mode: `MaxEncodedLen`)
+	/// Storage: `Template::UsernameOwners` (r:0 w:1)
+	/// Proof: `Template::UsernameOwners` (`max_values`: None, `max_size`: Some(69), added: 2544, mode: `MaxEncodedLen`)
+	/// Storage: `Template::ExpiryQueue` (r:0 w:1)
+	/// Proof: `Template::ExpiryQueue` (`max_values`: None, `max_size`: Some(48), added: 2523, mode: `MaxEncodedLen`)
+	/// Storage: `Template::Profiles` (r:0 w:1)
+	/// Proof: `Template::Profiles` (`max_values`: None, `max_size`: Some(642), added: 3117, mode: `MaxEncodedLen`)
+	/// Storage: `Template::RegisteredAt` (r:0 w:1)
+	/// Proof: `Template::RegisteredAt` (`max_values`: None, `max_size`: Some(40), added: 2515, mode: `MaxEncodedLen`)
+	/// Storage: `Template::AliasOf` (r:0 w:8)
+	/// Proof: `Template::AliasOf` (`max_values`: None, `max_size`: Some(56), added: 2531, mode: `MaxEncodedLen`)
+	/// The range of component `a` is `[0, 8]`.
+	fn expire_username(a: u32, ) -> Weight {
+		// Estimated proof size: `3687` bytes.
+		Weight::from_parts(25_906_000, 3687)
+			.saturating_add(Weight::from_parts(1_447_000, 0).saturating_mul(a.into()))
+			.saturating_add(T::DbWeight::get().reads(6_u64))
+			.saturating_add(T::DbWeight::get().writes(10_u64))
+			.saturating_add(T::DbWeight::get().writes((1_u64).saturating_mul(a.into())))
+	}
+	/// Storage: `Template::Nonces` (r:1 w:1)
+	/// Proof: `Template::Nonces` (`max_values`: None, `max_size`: Some(44), added: 2519, mode: `MaxEncodedLen`)
+	/// Storage: `Template::Usernames` (r:1 w:0)
+	/// Proof: `Template::Usernames` (`max_values`: None, `max_size`: Some(74), added: 2549, mode: `MaxEncodedLen`)
+	/// Storage: `Template::Profiles` (r:0 w:1)
+	/// Proof: `Template::Profiles` (`max_values`: None, `max_size`: Some(642), added: 3117, mode: `MaxEncodedLen`)
+	fn set_profile() -> Weight {
+		// Estimated proof size: `3539` bytes.
+		Weight::from_parts(48_315_000, 3539)
+			.saturating_add(T::DbWeight::get().reads(2_u64))
+			.saturating_add(T::DbWeight::get().writes(2_u64))
+	}
+	/// Storage: `Template::Paused` (r:1 w:0)
+	/// Proof: `Template::Paused` (`max_values`: Some(1), `max_size`: Some(1), added: 496, mode: `MaxEncodedLen`)
+	/// Storage: `Template::Blocklist` (r:2 w:0)
+	/// Proof: `Template::Blocklist` (`max_values`: None, `max_size`: Some(36), added: 2511, mode: `MaxEncodedLen`)
+	/// Storage: `Template::Nonces` (r:1 w:1)
+	/// Proof: `Template::Nonces` (`max_values`: None, `max_size`: Some(44), added: 2519, mode: `MaxEncodedLen`)
+	/// Storage: `Template::Usernames` (r:2 w:0)
+	/// Proof: `Template::Usernames` (`max_values`: None, `max_size`: Some(74), added: 2549, mode: `MaxEncodedLen`)
+	/// Storage: `Template::AliasOf` (r:2 w:1)
+	/// Proof: `Template::AliasOf` (`max_values`: None, `max_size`: Some(56), added: 2531, mode: `MaxEncodedLen`)
+	/// Storage: `Template::Aliases` (r:1 w:1)
+	/// Proof: `Template::Aliases` (`max_values`: None, `max_size`: Some(197), added: 2672, mode: `MaxEncodedLen`)
+	/// The range of component `a` is `[0, 7]`.
+	fn link_alias(a: u32, ) -> Weight {
+		// Estimated proof size: `3662` bytes.
+		Weight::from_parts(96_412_000, 3662)
+			.saturating_add(Weight::from_parts(41_000, 0).saturating_mul(a.into()))
+			.saturating_add(T::DbWeight::get().reads(9_u64))
+			.saturating_add(T::DbWeight::get().writes(3_u64))
+	}
+	/// Storage: `Template::Nonces` (r:1 w:1)
+	/// Proof: `Template::Nonces` (`max_values`: None, `max_size`: Some(44), added: 2519, mode: `MaxEncodedLen`)
+	/// Storage: `Template::AliasOf` (r:1 w:1)
+	/// Proof: `Template::AliasOf` (`max_values`: None, `max_size`: Some(56), added: 2531, mode: `MaxEncodedLen`)
+	/// Storage: `Template::Aliases` (r:1 w:1)
+	/// Proof: `Template::Aliases` (`max_values`: None, `max_size`: Some(197), added: 2672, mode: `MaxEncodedLen`)
+	/// The range of component `a` is `[1, 8]`.
+	fn unlink_alias(a: u32, ) -> Weight {
+		// Estimated proof size: `3662` bytes.
+		Weight::from_parts(95_184_000, 3662)
+			.saturating_add(Weight::from_parts(38_000, 0).saturating_mul(a.into()))
+			.saturating_add(T::DbWeight::get().reads(3_u64))
+			.saturating_add(T::DbWeight::get().writes(3_u64))
+	}
+	/// Storage: `Template::Nonces` (r:0 w:1)
+	/// Proof: `Template::Nonces` (`max_values`: None, `max_size`: Some(44), added: 2519, mode: `MaxEncodedLen`)
+	fn reset_nonce() -> Weight {
+		// Estimated proof size: `0` bytes.
+		Weight::from_parts(8_127_000, 0)
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: `Template::Paused` (r:1 w:0)
+	/// Proof: `Template::Paused` (`max_values`: Some(1), `max_size`: Some(1), added: 496, mode: `MaxEncodedLen`)
+	/// Storage: `Template::ReservedUsernames` (r:1 w:0)
+	/// Proof: `Template::ReservedUsernames` (`max_values`: None, `max_size`: Some(49), added: 2524, mode: `MaxEncodedLen`)
+	/// Storage: `Template::UsernameOwners` (r:1 w:0)
+	/// Proof: `Template::UsernameOwners` (`max_values`: None, `max_size`: Some(69), added: 2544, mode: `MaxEncodedLen`)
+	/// Storage: `Template::SubstrateUsernameOwners` (r:1 w:2)
+	/// Proof: `Template::SubstrateUsernameOwners` (`max_values`: None, `max_size`: Some(81), added: 2556, mode: `MaxEncodedLen`)
+	/// Storage: `Template::SubstrateUsernames` (r:1 w:1)
+	/// Proof: `Template::SubstrateUsernames` (`max_values`: None, `max_size`: Some(81), added: 2556, mode: `MaxEncodedLen`)
+	/// The range of component `l` is `[3, 32]`.
+	fn set_substrate_username(l: u32, ) -> Weight {
+		// Estimated proof size: `3546` bytes.
+		Weight::from_parts(21_604_000, 3546)
+			.saturating_add(Weight::from_parts(3_918, 0).saturating_mul(l.into()))
+			.saturating_add(T::DbWeight::get().reads(5_u64))
+			.saturating_add(T::DbWeight::get().writes(3_u64))
+	}
 }
 
 // For backwards compatibility and tests
 impl WeightInfo for () {
-	/// Storage: Template Something (r:0 w:1)
-	/// Proof: Template Something (max_values: Some(1), max_size: Some(4), added: 499, mode: MaxEncodedLen)
-	fn do_something() -> Weight {
-		// Proof Size summary in bytes:
-		//  Measured:  `0`
-		//  Estimated: `0`
-		// Minimum execution time: 8_000_000 picoseconds.
-		Weight::from_parts(9_000_000, 0)
+	/// Storage: `Template::Paused` (r:1 w:0)
+	/// Proof: `Template::Paused` (`max_values`: Some(1), `max_size`: Some(1), added: 496, mode: `MaxEncodedLen`)
+	/// Storage: `Template::RegistrationsThisBlock` (r:1 w:1)
+	/// Proof: `Template::RegistrationsThisBlock` (`max_values`: Some(1), `max_size`: Some(4), added: 499, mode: `MaxEncodedLen`)
+	/// Storage: `Template::Blocklist` (r:1 w:0)
+	/// Proof: `Template::Blocklist` (`max_values`: None, `max_size`: Some(36), added: 2511, mode: `MaxEncodedLen`)
+	/// Storage: `Template::Allowlist` (r:1 w:0)
+	/// Proof: `Template::Allowlist` (`max_values`: None, `max_size`: Some(36), added: 2511, mode: `MaxEncodedLen`)
+	/// Storage: `Template::AliasOf` (r:1 w:0)
+	/// Proof: `Template::AliasOf` (`max_values`: None, `max_size`: Some(56), added: 2531, mode: `MaxEncodedLen`)
+	/// Storage: `Template::Nonces` (r:1 w:1)
+	/// Proof: `Template::Nonces` (`max_values`: None, `max_size`: Some(44), added: 2519, mode: `MaxEncodedLen`)
+	/// Storage: `Template::LastUsernameChange` (r:1 w:1)
+	/// Proof: `Template::LastUsernameChange` (`max_values`: None, `max_size`: Some(40), added: 2515, mode: `MaxEncodedLen`)
+	/// Storage: `Template::UsernameOwners` (r:1 w:1)
+	/// Proof: `Template::UsernameOwners` (`max_values`: None, `max_size`: Some(69), added: 2544, mode: `MaxEncodedLen`)
+	/// Storage: `Template::SubstrateUsernameOwners` (r:1 w:0)
+	/// Proof: `Template::SubstrateUsernameOwners` (`max_values`: None, `max_size`: Some(81), added: 2556, mode: `MaxEncodedLen`)
+	/// Storage: `Template::ReservedUsernames` (r:1 w:0)
+	/// Proof: `Template::ReservedUsernames` (`max_values`: None, `max_size`: Some(49), added: 2524, mode: `MaxEncodedLen`)
+	/// Storage: `Template::UsernameDeposits` (r:1 w:1)
+	/// Proof: `Template::UsernameDeposits` (`max_values`: None, `max_size`: Some(84), added: 2559, mode: `MaxEncodedLen`)
+	/// Storage: `System::Account` (r:1 w:1)
+	/// Proof: `System::Account` (`max_values`: None, `max_size`: Some(128), added: 2603, mode: `MaxEncodedLen`)
+	/// Storage: `Template::Usernames` (r:1 w:1)
+	/// Proof: `Template::Usernames` (`max_values`: None, `max_size`: Some(74), added: 2549, mode: `MaxEncodedLen`)
+	/// Storage: `Template::UsernameCount` (r:1 w:1)
+	/// Proof: `Template::UsernameCount` (`max_values`: Some(1), `max_size`: Some(4), added: 499, mode: `MaxEncodedLen`)
+	/// Storage: `Template::UsernameHistory` (r:1 w:1)
+	/// Proof: `Template::UsernameHistory` (`max_values`: None, `max_size`: Some(222), added: 2697, mode: `MaxEncodedLen`)
+	/// Storage: `Template::RegisteredAt` (r:0 w:1)
+	/// Proof: `Template::RegisteredAt` (`max_values`: None, `max_size`: Some(40), added: 2515, mode: `MaxEncodedLen`)
+	/// Storage: `Template::ExpiryQueue` (r:0 w:1)
+	/// Proof: `Template::ExpiryQueue` (`max_values`: None, `max_size`: Some(48), added: 2523, mode: `MaxEncodedLen`)
+	/// The range of component `l` is `[3, 32]`.
+	fn set_username(l: u32, ) -> Weight {
+		// Estimated proof size: `3687` bytes.
+		Weight::from_parts(59_127_000, 3687)
+			.saturating_add(Weight::from_parts(4_372, 0).saturating_mul(l.into()))
+			.saturating_add(RocksDbWeight::get().reads(15_u64))
+			.saturating_add(RocksDbWeight::get().writes(11_u64))
+	}
+	/// Storage: `Template::Paused` (r:1 w:0)
+	/// Proof: `Template::Paused` (`max_values`: Some(1), `max_size`: Some(1), added: 496, mode: `MaxEncodedLen`)
+	/// Storage: `Template::Nonces` (r:1 w:1)
+	/// Proof: `Template::Nonces` (`max_values`: None, `max_size`: Some(44), added: 2519, mode: `MaxEncodedLen`)
+	/// Storage: `Template::Usernames` (r:1 w:1)
+	/// Proof: `Template::Usernames` (`max_values`: None, `max_size`: Some(74), added: 2549, mode: `MaxEncodedLen`)
+	/// Storage: `Template::UsernameCount` (r:1 w:1)
+	/// Proof: `Template::UsernameCount` (`max_values`: Some(1), `max_size`: Some(4), added: 499, mode: `MaxEncodedLen`)
+	/// Storage: `Template::UsernameDeposits` (r:1 w:1)
+	/// Proof: `Template::UsernameDeposits` (`max_values`: None, `max_size`: Some(84), added: 2559, mode: `MaxEncodedLen`)
+	/// Storage: `System::Account` (r:1 w:1)
+	/// Proof: `System::Account` (`max_values`: None, `max_size`: Some(128), added: 2603, mode: `MaxEncodedLen`)
+	/// Storage: `Template::Aliases` (r:1 w:1)
+	/// Proof: `Template::Aliases` (`max_values`: None, `max_size`: Some(197), added: 2672, mode: `MaxEncodedLen`)
+	/// Storage: `Template::UsernameHistory` (r:1 w:1)
+	/// Proof: `Template::UsernameHistory` (`max_values`: None, `max_size`: Some(222), added: 2697, mode: `MaxEncodedLen`)
+	/// Storage: `Template::UsernameOwners` (r:0 w:1)
+	/// Proof: `Template::UsernameOwners` (`max_values`: None, `max_size`: Some(69), added: 2544, mode: `MaxEncodedLen`)
+	/// Storage: `Template::ExpiryQueue` (r:0 w:1)
+	/// Proof: `Template::ExpiryQueue` (`max_values`: None, `max_size`: Some(48), added: 2523, mode: `MaxEncodedLen`)
+	/// Storage: `Template::Profiles` (r:0 w:1)
+	/// Proof: `Template::Profiles` (`max_values`: None, `max_size`: Some(642), added: 3117, mode: `MaxEncodedLen`)
+	/// Storage: `Template::RegisteredAt` (r:0 w:1)
+	/// Proof: `Template::RegisteredAt` (`max_values`: None, `max_size`: Some(40), added: 2515, mode: `MaxEncodedLen`)
+	/// Storage: `Template::AliasOf` (r:0 w:8)
+	/// Proof: `Template::AliasOf` (`max_values`: None, `max_size`: Some(56), added: 2531, mode: `MaxEncodedLen`)
+	/// The range of component `a` is `[0, 8]`.
+	fn clear_username(a: u32, ) -> Weight {
+		// Estimated proof size: `3687` bytes.
+		Weight::from_parts(63_842_000, 3687)
+			.saturating_add(Weight::from_parts(1_447_000, 0).saturating_mul(a.into()))
+			.saturating_add(RocksDbWeight::get().reads(8_u64))
+			.saturating_add(RocksDbWeight::get().writes(11_u64))
+			.saturating_add(RocksDbWeight::get().writes((1_u64).saturating_mul(a.into())))
+	}
+	/// Storage: `Template::Paused` (r:1 w:0)
+	/// Proof: `Template::Paused` (`max_values`: Some(1), `max_size`: Some(1), added: 496, mode: `MaxEncodedLen`)
+	/// Storage: `Template::Blocklist` (r:2 w:0)
+	/// Proof: `Template::Blocklist` (`max_values`: None, `max_size`: Some(36), added: 2511, mode: `MaxEncodedLen`)
+	/// Storage: `Template::Allowlist` (r:1 w:0)
+	/// Proof: `Template::Allowlist` (`max_values`: None, `max_size`: Some(36), added: 2511, mode: `MaxEncodedLen`)
+	/// Storage: `Template::Nonces` (r:1 w:1)
+	/// Proof: `Template::Nonces` (`max_values`: None, `max_size`: Some(44), added: 2519, mode: `MaxEncodedLen`)
+	/// Storage: `Template::Usernames` (r:2 w:2)
+	/// Proof: `Template::Usernames` (`max_values`: None, `max_size`: Some(74), added: 2549, mode: `MaxEncodedLen`)
+	/// Storage: `Template::AliasOf` (r:1 w:8)
+	/// Proof: `Template::AliasOf` (`max_values`: None, `max_size`: Some(56), added: 2531, mode: `MaxEncodedLen`)
+	/// Storage: `Template::UsernameDeposits` (r:1 w:2)
+	/// Proof: `Template::UsernameDeposits` (`max_values`: None, `max_size`: Some(84), added: 2559, mode: `MaxEncodedLen`)
+	/// Storage: `Template::RegisteredAt` (r:1 w:2)
+	/// Proof: `Template::RegisteredAt` (`max_values`: None, `max_size`: Some(40), added: 2515, mode: `MaxEncodedLen`)
+	/// Storage: `Template::Profiles` (r:1 w:2)
+	/// Proof: `Template::Profiles` (`max_values`: None, `max_size`: Some(642), added: 3117, mode: `MaxEncodedLen`)
+	/// Storage: `Template::Aliases` (r:1 w:1)
+	/// Proof: `Template::Aliases` (`max_values`: None, `max_size`: Some(197), added: 2672, mode: `MaxEncodedLen`)
+	/// Storage: `Template::UsernameHistory` (r:2 w:2)
+	/// Proof: `Template::UsernameHistory` (`max_values`: None, `max_size`: Some(222), added: 2697, mode: `MaxEncodedLen`)
+	/// Storage: `Template::UsernameOwners` (r:0 w:1)
+	/// Proof: `Template::UsernameOwners` (`max_values`: None, `max_size`: Some(69), added: 2544, mode: `MaxEncodedLen`)
+	/// Storage: `Template::ExpiryQueue` (r:0 w:2)
+	/// Proof: `Template::ExpiryQueue` (`max_values`: None, `max_size`: Some(48), added: 2523, mode: `MaxEncodedLen`)
+	/// Storage: `Template::LastUsernameChange` (r:1 w:1)
+	/// Proof: `Template::LastUsernameChange` (`max_values`: None, `max_size`: Some(40), added: 2515, mode: `MaxEncodedLen`)
+	/// The range of component `a` is `[0, 8]`.
+	fn transfer_username(a: u32, ) -> Weight {
+		// Estimated proof size: `4107` bytes.
+		Weight::from_parts(76_219_000, 4107)
+			.saturating_add(Weight::from_parts(1_512_000, 0).saturating_mul(a.into()))
+			.saturating_add(RocksDbWeight::get().reads(15_u64))
+			.saturating_add(RocksDbWeight::get().writes(16_u64))
+			.saturating_add(RocksDbWeight::get().writes((1_u64).saturating_mul(a.into())))
+	}
+	/// Storage: `Template::Usernames` (r:1 w:1)
+	/// Proof: `Template::Usernames` (`max_values`: None, `max_size`: Some(74), added: 2549, mode: `MaxEncodedLen`)
+	/// Storage: `Template::UsernameCount` (r:1 w:1)
+	/// Proof: `Template::UsernameCount` (`max_values`: Some(1), `max_size`: Some(4), added: 499, mode: `MaxEncodedLen`)
+	/// Storage: `Template::UsernameDeposits` (r:1 w:1)
+	/// Proof: `Template::UsernameDeposits` (`max_values`: None, `max_size`: Some(84), added: 2559, mode: `MaxEncodedLen`)
+	/// Storage: `System::Account` (r:1 w:1)
+	/// Proof: `System::Account` (`max_values`: None, `max_size`: Some(128), added: 2603, mode: `MaxEncodedLen`)
+	/// Storage: `Template::Aliases` (r:1 w:1)
+	/// Proof: `Template::Aliases` (`max_values`: None, `max_size`: Some(197), added: 2672, mode: `MaxEncodedLen`)
+	/// Storage: `Template::UsernameHistory` (r:1 w:1)
+	/// Proof: `Template::UsernameHistory` (`max_values`: None, `max_size`: Some(222), added: 2697, mode: `MaxEncodedLen`)
+	/// Storage: `Template::UsernameOwners` (r:0 w:1)
+	/// Proof: `Template::UsernameOwners` (`max_values`: None, `max_size`: Some(69), added: 2544, mode: `MaxEncodedLen`)
+	/// Storage: `Template::ExpiryQueue` (r:0 w:1)
+	/// Proof: `Template::ExpiryQueue` (`max_values`: None, `max_size`: Some(48), added: 2523, mode: `MaxEncodedLen`)
+	/// Storage: `Template::Profiles` (r:0 w:1)
+	/// Proof: `Template::Profiles` (`max_values`: None, `max_size`: Some(642), added: 3117, mode: `MaxEncodedLen`)
+	/// Storage: `Template::RegisteredAt` (r:0 w:1)
+	/// Proof: `Template::RegisteredAt` (`max_values`: None, `max_size`: Some(40), added: 2515, mode: `MaxEncodedLen`)
+	/// Storage: `Template::AliasOf` (r:0 w:8)
+	/// Proof: `Template::AliasOf` (`max_values`: None, `max_size`: Some(56), added: 2531, mode: `MaxEncodedLen`)
+	/// The range of component `a` is `[0, 8]`.
+	fn force_clear_username(a: u32, ) -> Weight {
+		// Estimated proof size: `3687` bytes.
+		Weight::from_parts(27_318_000, 3687)
+			.saturating_add(Weight::from_parts(1_447_000, 0).saturating_mul(a.into()))
+			.saturating_add(RocksDbWeight::get().reads(6_u64))
+			.saturating_add(RocksDbWeight::get().writes(10_u64))
+			.saturating_add(RocksDbWeight::get().writes((1_u64).saturating_mul(a.into())))
+	}
+	/// Storage: `Template::ReservedUsernames` (r:0 w:1)
+	/// Proof: `Template::ReservedUsernames` (`max_values`: None, `max_size`: Some(49), added: 2524, mode: `MaxEncodedLen`)
+	fn reserve_username() -> Weight {
+		// Estimated proof size: `0` bytes.
+		Weight::from_parts(9_412_000, 0)
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	/// Storage: `Template::ReservedUsernames` (r:1 w:1)
+	/// Proof: `Template::ReservedUsernames` (`max_values`: None, `max_size`: Some(49), added: 2524, mode: `MaxEncodedLen`)
+	fn unreserve_username() -> Weight {
+		// Estimated proof size: `3514` bytes.
+		Weight::from_parts(12_087_000, 3514)
+			.saturating_add(RocksDbWeight::get().reads(1_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	/// Storage: `Template::Paused` (r:0 w:1)
+	/// Proof: `Template::Paused` (`max_values`: Some(1), `max_size`: Some(1), added: 496, mode: `MaxEncodedLen`)
+	fn set_paused() -> Weight {
+		// Estimated proof size: `0` bytes.
+		Weight::from_parts(6_203_000, 0)
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	/// Storage: `Template::Allowlist` (r:0 w:1)
+	/// Proof: `Template::Allowlist` (`max_values`: None, `max_size`: Some(36), added: 2511, mode: `MaxEncodedLen`)
+	fn add_to_allowlist() -> Weight {
+		// Estimated proof size: `0` bytes.
+		Weight::from_parts(8_951_000, 0)
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	/// Storage: `Template::Allowlist` (r:1 w:1)
+	/// Proof: `Template::Allowlist` (`max_values`: None, `max_size`: Some(36), added: 2511, mode: `MaxEncodedLen`)
+	fn remove_from_allowlist() -> Weight {
+		// Estimated proof size: `3501` bytes.
+		Weight::from_parts(11_634_000, 3501)
+			.saturating_add(RocksDbWeight::get().reads(1_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	/// Storage: `Template::Blocklist` (r:0 w:1)
+	/// Proof: `Template::Blocklist` (`max_values`: None, `max_size`: Some(36), added: 2511, mode: `MaxEncodedLen`)
+	fn block_address() -> Weight {
+		// Estimated proof size: `0` bytes.
+		Weight::from_parts(8_874_000, 0)
 			.saturating_add(RocksDbWeight::get().writes(1_u64))
 	}
-	/// Storage: Template Something (r:1 w:1)
-	/// Proof: Template Something (max_values: Some(1), max_size: Some(4), added: 499, mode: MaxEncodedLen)
-	fn cause_error() -> Weight {
-		// Proof Size summary in bytes:
-		//  Measured:  `32`
-		//  Estimated: `1489`
-		// Minimum execution time: 6_000_000 picoseconds.
-		Weight::from_parts(6_000_000, 1489)
+	/// Storage: `Template::Blocklist` (r:1 w:1)
+	/// Proof: `Template::Blocklist` (`max_values`: None, `max_size`: Some(36), added: 2511, mode: `MaxEncodedLen`)
+	fn unblock_address() -> Weight {
+		// Estimated proof size: `3501` bytes.
+		Weight::from_parts(11_502_000, 3501)
 			.saturating_add(RocksDbWeight::get().reads(1_u64))
 			.saturating_add(RocksDbWeight::get().writes(1_u64))
 	}
+	/// Storage: `Template::Usernames` (r:101 w:100)
+	/// Proof: `Template::Usernames` (`max_values`: None, `max_size`: Some(74), added: 2549, mode: `MaxEncodedLen`)
+	/// Storage: `Template::UsernameCount` (r:1 w:1)
+	/// Proof: `Template::UsernameCount` (`max_values`: Some(1), `max_size`: Some(4), added: 499, mode: `MaxEncodedLen`)
+	/// Storage: `Template::UsernameDeposits` (r:100 w:100)
+	/// Proof: `Template::UsernameDeposits` (`max_values`: None, `max_size`: Some(84), added: 2559, mode: `MaxEncodedLen`)
+	/// Storage: `System::Account` (r:100 w:100)
+	/// Proof: `System::Account` (`max_values`: None, `max_size`: Some(128), added: 2603, mode: `MaxEncodedLen`)
+	/// Storage: `Template::Aliases` (r:100 w:100)
+	/// Proof: `Template::Aliases` (`max_values`: None, `max_size`: Some(197), added: 2672, mode: `MaxEncodedLen`)
+	/// Storage: `Template::UsernameHistory` (r:100 w:100)
+	/// Proof: `Template::UsernameHistory` (`max_values`: None, `max_size`: Some(222), added: 2697, mode: `MaxEncodedLen`)
+	/// Storage: `Template::UsernameOwners` (r:0 w:100)
+	/// Proof: `Template::UsernameOwners` (`max_values`: None, `max_size`: Some(69), added: 2544, mode: `MaxEncodedLen`)
+	/// Storage: `Template::ExpiryQueue` (r:0 w:100)
+	/// Proof: `Template::ExpiryQueue` (`max_values`: None, `max_size`: Some(48), added: 2523, mode: `MaxEncodedLen`)
+	/// Storage: `Template::Profiles` (r:0 w:100)
+	/// Proof: `Template::Profiles` (`max_values`: None, `max_size`: Some(642), added: 3117, mode: `MaxEncodedLen`)
+	/// Storage: `Template::RegisteredAt` (r:0 w:100)
+	/// Proof: `Template::RegisteredAt` (`max_values`: None, `max_size`: Some(40), added: 2515, mode: `MaxEncodedLen`)
+	/// Storage: `Template::AliasOf` (r:0 w:800)
+	/// Proof: `Template::AliasOf` (`max_values`: None, `max_size`: Some(56), added: 2531, mode: `MaxEncodedLen`)
+	/// The range of component `n` is `[0, 100]`. Every username has the maximum of 8 aliases.
+	fn force_expire_all(n: u32, ) -> Weight {
+		// Estimated proof size: `3539 + n * 13080` bytes.
+		Weight::from_parts(5_214_000, 3539)
+			.saturating_add(Weight::from_parts(39_106_000, 0).saturating_mul(n.into()))
+			.saturating_add(RocksDbWeight::get().reads(2_u64))
+			.saturating_add(RocksDbWeight::get().reads((5_u64).saturating_mul(n.into())))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+			.saturating_add(RocksDbWeight::get().writes((17_u64).saturating_mul(n.into())))
+			.saturating_add(Weight::from_parts(0, 13080).saturating_mul(n.into()))
+	}
+	/// Storage: `Template::Usernames` (r:1 w:1)
+	/// Proof: `Template::Usernames` (`max_values`: None, `max_size`: Some(74), added: 2549, mode: `MaxEncodedLen`)
+	/// Storage: `Template::UsernameCount` (r:1 w:1)
+	/// Proof: `Template::UsernameCount` (`max_values`: Some(1), `max_size`: Some(4), added: 499, mode: `MaxEncodedLen`)
+	/// Storage: `Template::UsernameDeposits` (r:1 w:1)
+	/// Proof: `Template::UsernameDeposits` (`max_values`: None, `max_size`: Some(84), added: 2559, mode: `MaxEncodedLen`)
+	/// Storage: `System::Account` (r:1 w:1)
+	/// Proof: `System::Account` (`max_values`: None, `max_size`: Some(128), added: 2603, mode: `MaxEncodedLen`)
+	/// Storage: `Template::Aliases` (r:1 w:1)
+	/// Proof: `Template::Aliases` (`max_values`: None, `max_size`: Some(197), added: 2672, mode: `MaxEncodedLen`)
+	/// Storage: `Template::UsernameHistory` (r:1 w:1)
+	/// Proof: `Template::UsernameHistory` (`max_values`: None, `max_size`: Some(222), added: 2697, mode: `MaxEncodedLen`)
+	/// Storage: `Template::UsernameOwners` (r:0 w:1)
+	/// Proof: `Template::UsernameOwners` (`max_values`: None, `max_size`: Some(69), added: 2544, mode: `MaxEncodedLen`)
+	/// Storage: `Template::ExpiryQueue` (r:0 w:1)
+	/// Proof: `Template::ExpiryQueue` (`max_values`: None, `max_size`: Some(48), added: 2523, mode: `MaxEncodedLen`)
+	/// Storage: `Template::Profiles` (r:0 w:1)
+	/// Proof: `Template::Profiles` (`max_values`: None, `max_size`: Some(642), added: 3117, mode: `MaxEncodedLen`)
+	/// Storage: `Template::RegisteredAt` (r:0 w:1)
+	/// Proof: `Template::RegisteredAt` (`max_values`: None, `max_size`: Some(40), added: 2515, mode: `MaxEncodedLen`)
+	/// Storage: `Template::AliasOf` (r:0 w:8)
+	/// Proof: `Template::AliasOf` (`max_values`: None, `max_size`: Some(56), added: 2531, mode: `MaxEncodedLen`)
+	/// The range of component `a` is `[0, 8]`.
+	fn expire_username(a: u32, ) -> Weight {
+		// Estimated proof size: `3687` bytes.
+		Weight::from_parts(25_906_000, 3687)
+			.saturating_add(Weight::from_parts(1_447_000, 0).saturating_mul(a.into()))
+			.saturating_add(RocksDbWeight::get().reads(6_u64))
+			.saturating_add(RocksDbWeight::get().writes(10_u64))
+			.saturating_add(RocksDbWeight::get().writes((1_u64).saturating_mul(a.into())))
+	}
+	/// Storage: `Template::Nonces` (r:1 w:1)
+	/// Proof: `Template::Nonces` (`max_values`: None, `max_size`: Some(44), added: 2519, mode: `MaxEncodedLen`)
+	/// Storage: `Template::Usernames` (r:1 w:0)
+	/// Proof: `Template::Usernames` (`max_values`: None, `max_size`: Some(74), added: 2549, mode: `MaxEncodedLen`)
+	/// Storage: `Template::Profiles` (r:0 w:1)
+	/// Proof: `Template::Profiles` (`max_values`: None, `max_size`: Some(642), added: 3117, mode: `MaxEncodedLen`)
+	fn set_profile() -> Weight {
+		// Estimated proof size: `3539` bytes.
+		Weight::from_parts(48_315_000, 3539)
+			.saturating_add(RocksDbWeight::get().reads(2_u64))
+			.saturating_add(RocksDbWeight::get().writes(2_u64))
+	}
+	/// Storage: `Template::Paused` (r:1 w:0)
+	/// Proof: `Template::Paused` (`max_values`: Some(1), `max_size`: Some(1), added: 496, mode: `MaxEncodedLen`)
+	/// Storage: `Template::Blocklist` (r:2 w:0)
+	/// Proof: `Template::Blocklist` (`max_values`: None, `max_size`: Some(36), added: 2511, mode: `MaxEncodedLen`)
+	/// Storage: `Template::Nonces` (r:1 w:1)
+	/// Proof: `Template::Nonces` (`max_values`: None, `max_size`: Some(44), added: 2519, mode: `MaxEncodedLen`)
+	/// Storage: `Template::Usernames` (r:2 w:0)
+	/// Proof: `Template::Usernames` (`max_values`: None, `max_size`: Some(74), added: 2549, mode: `MaxEncodedLen`)
+	/// Storage: `Template::AliasOf` (r:2 w:1)
+	/// Proof: `Template::AliasOf` (`max_values`: None, `max_size`: Some(56), added: 2531, mode: `MaxEncodedLen`)
+	/// Storage: `Template::Aliases` (r:1 w:1)
+	/// Proof: `Template::Aliases` (`max_values`: None, `max_size`: Some(197), added: 2672, mode: `MaxEncodedLen`)
+	/// The range of component `a` is `[0, 7]`.
+	fn link_alias(a: u32, ) -> Weight {
+		// Estimated proof size: `3662` bytes.
+		Weight::from_parts(96_412_000, 3662)
+			.saturating_add(Weight::from_parts(41_000, 0).saturating_mul(a.into()))
+			.saturating_add(RocksDbWeight::get().reads(9_u64))
+			.saturating_add(RocksDbWeight::get().writes(3_u64))
+	}
+	/// Storage: `Template::Nonces` (r:1 w:1)
+	/// Proof: `Template::Nonces` (`max_values`: None, `max_size`: Some(44), added: 2519, mode: `MaxEncodedLen`)
+	/// Storage: `Template::AliasOf` (r:1 w:1)
+	/// Proof: `Template::AliasOf` (`max_values`: None, `max_size`: Some(56), added: 2531, mode: `MaxEncodedLen`)
+	/// Storage: `Template::Aliases` (r:1 w:1)
+	/// Proof: `Template::Aliases` (`max_values`: None, `max_size`: Some(197), added: 2672, mode: `MaxEncodedLen`)
+	/// The range of component `a` is `[1, 8]`.
+	fn unlink_alias(a: u32, ) -> Weight {
+		// Estimated proof size: `3662` bytes.
+		Weight::from_parts(95_184_000, 3662)
+			.saturating_add(Weight::from_parts(38_000, 0).saturating_mul(a.into()))
+			.saturating_add(RocksDbWeight::get().reads(3_u64))
+			.saturating_add(RocksDbWeight::get().writes(3_u64))
+	}
+	/// Storage: `Template::Nonces` (r:0 w:1)
+	/// Proof: `Template::Nonces` (`max_values`: None, `max_size`: Some(44), added: 2519, mode: `MaxEncodedLen`)
+	fn reset_nonce() -> Weight {
+		// Estimated proof size: `0` bytes.
+		Weight::from_parts(8_127_000, 0)
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	/// Storage: `Template::Paused` (r:1 w:0)
+	/// Proof: `Template::Paused` (`max_values`: Some(1), `max_size`: Some(1), added: 496, mode: `MaxEncodedLen`)
+	/// Storage: `Template::ReservedUsernames` (r:1 w:0)
+	/// Proof: `Template::ReservedUsernames` (`max_values`: None, `max_size`: Some(49), added: 2524, mode: `MaxEncodedLen`)
+	/// Storage: `Template::UsernameOwners` (r:1 w:0)
+	/// Proof: `Template::UsernameOwners` (`max_values`: None, `max_size`: Some(69), added: 2544, mode: `MaxEncodedLen`)
+	/// Storage: `Template::SubstrateUsernameOwners` (r:1 w:2)
+	/// Proof: `Template::SubstrateUsernameOwners` (`max_values`: None, `max_size`: Some(81), added: 2556, mode: `MaxEncodedLen`)
+	/// Storage: `Template::SubstrateUsernames` (r:1 w:1)
+	/// Proof: `Template::SubstrateUsernames` (`max_values`: None, `max_size`: Some(81), added: 2556, mode: `MaxEncodedLen`)
+	/// The range of component `l` is `[3, 32]`.
+	fn set_substrate_username(l: u32, ) -> Weight {
+		// Estimated proof size: `3546` bytes.
+		Weight::from_parts(21_604_000, 3546)
+			.saturating_add(Weight::from_parts(3_918, 0).saturating_mul(l.into()))
+			.saturating_add(RocksDbWeight::get().reads(5_u64))
+			.saturating_add(RocksDbWeight::get().writes(3_u64))
+	}
 }
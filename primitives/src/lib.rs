@@ -0,0 +1,470 @@
+//! Signature helpers shared by the username pallet, the node RPC and the CLI tools.
+//!
+//! Everything here is `no_std` so the pallet and the native tools verify signatures with one
+//! implementation and can't drift apart.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+use alloc::{format, string::String, vec::Vec};
+use sp_core::H160;
+use sp_io::hashing::keccak_256;
+
+/// Hash `message` the way Ethereum wallets do for `personal_sign` (EIP-191).
+pub fn eth_message_hash(message: &[u8]) -> [u8; 32] {
+    let prefix = format!("\x19Ethereum Signed Message:\n{}", message.len());
+    let mut eth_message: Vec<u8> = prefix.into_bytes();
+    eth_message.extend_from_slice(message);
+    keccak_256(&eth_message)
+}
+
+/// Derive the Ethereum address of an uncompressed secp256k1 public key.
+///
+/// `pubkey` is the 64-byte `x || y` form returned by `secp256k1_ecdsa_recover`, without the
+/// `0x04` SEC1 tag.
+pub fn eth_address(pubkey: &[u8; 64]) -> H160 {
+    H160::from_slice(&keccak_256(pubkey)[12..32])
+}
+
+/// Derive the `H160` owner address of a 32-byte ed25519 or sr25519 public key.
+///
+/// This mirrors [`eth_address`]: the last 20 bytes of the keccak-256 hash of the raw key.
+pub fn substrate_key_address(public: &[u8; 32]) -> H160 {
+    H160::from_slice(&keccak_256(public)[12..32])
+}
+
+/// Format `address` with the EIP-55 mixed-case checksum, e.g.
+/// `0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed`.
+///
+/// A hex letter is upper-cased when the matching nibble of the keccak-256 hash of the lowercase
+/// hex is 8 or more, so a mistyped character is likely to break the pattern.
+pub fn to_checksum(address: &H160) -> String {
+    let lowercase: String = address
+        .as_bytes()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    let hash = keccak_256(lowercase.as_bytes());
+
+    let mut checksummed = String::with_capacity(2 + lowercase.len());
+    checksummed.push_str("0x");
+    for (i, c) in lowercase.chars().enumerate() {
+        let nibble = if i % 2 == 0 {
+            hash[i / 2] >> 4
+        } else {
+            hash[i / 2] & 0x0f
+        };
+        if nibble >= 8 {
+            checksummed.push(c.to_ascii_uppercase());
+        } else {
+            checksummed.push(c);
+        }
+    }
+    checksummed
+}
+
+/// Format `address` as `0x`-prefixed hex: EIP-55 checksummed as [`to_checksum`] does, or all
+/// lowercase with `lowercase`. The CLI tools print every address, JSON included, through this.
+pub fn format_address(address: &H160, lowercase: bool) -> String {
+    if lowercase {
+        format!("{:#x}", address)
+    } else {
+        to_checksum(address)
+    }
+}
+
+/// The `set_username:{chain_id}:{username}:{nonce}` message an owner signs to set `username`.
+///
+/// `username` is copied as raw bytes, so the pallet and the CLI agree even on names that are
+/// not valid UTF-8.
+pub fn set_username_message(chain_id: u64, username: &[u8], nonce: u64) -> Vec<u8> {
+    let mut message = format!("set_username:{}:", chain_id).into_bytes();
+    message.extend_from_slice(username);
+    message.extend_from_slice(format!(":{}", nonce).as_bytes());
+    message
+}
+
+/// The `clear_username:{chain_id}:{eth_address}:{nonce}` message an owner signs to remove their
+/// username. The address is `0x`-prefixed lowercase hex.
+pub fn clear_username_message(chain_id: u64, eth_address: &H160, nonce: u64) -> Vec<u8> {
+    format!("clear_username:{}:{:#x}:{}", chain_id, eth_address, nonce).into_bytes()
+}
+
+/// Length of a recoverable secp256k1 signature in the `r || s || v` form.
+pub const SIGNATURE_LEN: usize = 65;
+
+/// Offset Ethereum adds to the raw secp256k1 recovery id in the `v` byte.
+pub const ETH_V_OFFSET: u8 = 27;
+
+/// Largest raw secp256k1 recovery id. `v` must be at most this, or at most
+/// `ETH_V_OFFSET + MAX_RECOVERY_ID` in the Ethereum convention.
+pub const MAX_RECOVERY_ID: u8 = 3;
+
+/// Recover the Ethereum address that signed `message` with `personal_sign`.
+///
+/// `signature` is the 65-byte `r || s || v` form. `v` may use either convention: the raw
+/// secp256k1 recovery id `0..=3`, or the Ethereum form `27..=30`. Returns `None` for any other
+/// length or `v`, or if no public key can be recovered.
+pub fn recover_eth_address(message: &[u8], signature: &[u8]) -> Option<H160> {
+    recover_eth_address_prehashed(&eth_message_hash(message), signature)
+}
+
+/// Map a `v` byte in either accepted convention to the raw recovery id `0..=MAX_RECOVERY_ID`.
+fn normalize_recovery_id(v: u8) -> Option<u8> {
+    match v {
+        0..=MAX_RECOVERY_ID => Some(v),
+        ETH_V_OFFSET..=ETH_MAX_V => Some(v - ETH_V_OFFSET),
+        _ => None,
+    }
+}
+
+/// Largest `v` in the Ethereum convention.
+const ETH_MAX_V: u8 = ETH_V_OFFSET + MAX_RECOVERY_ID;
+
+/// Half of the secp256k1 group order, big-endian. Signatures with a larger `s` are rejected.
+const SECP256K1_HALF_ORDER: [u8; 32] = [
+    0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0x5d, 0x57, 0x6e, 0x73, 0x57, 0xa4, 0x50, 0x1d, 0xdf, 0xe9, 0x2f, 0x46, 0x68, 0x1b, 0x20, 0xa0,
+];
+
+/// Recover the Ethereum address that signed the 32-byte `hash` directly, as with EIP-712.
+///
+/// Accepts the same signature form as [`recover_eth_address`]. Like Ethereum since EIP-2, only
+/// the low-`s` form of a signature is accepted, so a signature can't be malleated into a second
+/// valid one.
+pub fn recover_eth_address_prehashed(hash: &[u8; 32], signature: &[u8]) -> Option<H160> {
+    let mut sig_array: [u8; SIGNATURE_LEN] = signature.try_into().ok()?;
+
+    sig_array[SIGNATURE_LEN - 1] = normalize_recovery_id(sig_array[SIGNATURE_LEN - 1])?;
+    // Equal-length big-endian byte strings compare like the numbers they encode.
+    if sig_array[32..64] > SECP256K1_HALF_ORDER[..] {
+        return None;
+    }
+
+    let pubkey = sp_io::crypto::secp256k1_ecdsa_recover(&sig_array, hash).ok()?;
+    Some(eth_address(&pubkey))
+}
+
+/// Why an Ethereum signature was rejected by [`verify_eth_signature`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EthSignatureError {
+    /// No address could be recovered: the signature is malformed, has an unsupported `v` or a
+    /// high `s`, or doesn't correspond to any key.
+    RecoveryFailed,
+    /// The signature is well formed but was made by a different key than the expected one.
+    AddressMismatch { recovered: H160 },
+}
+
+/// Check that the `personal_sign` signature over `message` was made by `expected`.
+pub fn verify_eth_signature(
+    expected: &H160,
+    message: &[u8],
+    signature: &[u8],
+) -> Result<(), EthSignatureError> {
+    verify_eth_signature_prehashed(expected, &eth_message_hash(message), signature)
+}
+
+/// Check that `signature` over the 32-byte `hash` was made by `expected`.
+///
+/// A signature recovering to the zero address counts as unrecoverable, so it can never match a
+/// zero `expected`.
+pub fn verify_eth_signature_prehashed(
+    expected: &H160,
+    hash: &[u8; 32],
+    signature: &[u8],
+) -> Result<(), EthSignatureError> {
+    match recover_eth_address_prehashed(hash, signature) {
+        Some(recovered) if recovered.is_zero() => Err(EthSignatureError::RecoveryFailed),
+        Some(recovered) if recovered != *expected => {
+            Err(EthSignatureError::AddressMismatch { recovered })
+        }
+        Some(_) => Ok(()),
+        None => Err(EthSignatureError::RecoveryFailed),
+    }
+}
+
+/// `name` of the EIP-712 domain users sign `SetUsername` under.
+pub const EIP712_DOMAIN_NAME: &str = "Username Registry";
+
+/// `version` of the EIP-712 domain users sign `SetUsername` under.
+pub const EIP712_DOMAIN_VERSION: &str = "1";
+
+/// A `u64` ABI-encoded as a 32-byte big-endian `uint256`.
+fn abi_uint256(value: u64) -> [u8; 32] {
+    let mut word = [0u8; 32];
+    word[24..].copy_from_slice(&value.to_be_bytes());
+    word
+}
+
+/// The EIP-712 domain separator for
+/// `EIP712Domain(string name,string version,uint256 chainId)`.
+pub fn eip712_domain_separator(chain_id: u64) -> [u8; 32] {
+    let mut encoded = Vec::with_capacity(4 * 32);
+    encoded.extend_from_slice(&keccak_256(
+        b"EIP712Domain(string name,string version,uint256 chainId)",
+    ));
+    encoded.extend_from_slice(&keccak_256(EIP712_DOMAIN_NAME.as_bytes()));
+    encoded.extend_from_slice(&keccak_256(EIP712_DOMAIN_VERSION.as_bytes()));
+    encoded.extend_from_slice(&abi_uint256(chain_id));
+    keccak_256(&encoded)
+}
+
+/// The EIP-712 digest of
+/// `SetUsername(address owner,string username,uint256 nonce)` under the domain for `chain_id`.
+///
+/// This is what a wallet signs for `eth_signTypedData_v4`, so the signature is checked with
+/// [`recover_eth_address_prehashed`].
+pub fn eip712_set_username_hash(
+    chain_id: u64,
+    owner: &H160,
+    username: &[u8],
+    nonce: u64,
+) -> [u8; 32] {
+    let mut encoded = Vec::with_capacity(4 * 32);
+    encoded.extend_from_slice(&keccak_256(
+        b"SetUsername(address owner,string username,uint256 nonce)",
+    ));
+    encoded.extend_from_slice(&[0u8; 12]);
+    encoded.extend_from_slice(owner.as_bytes());
+    encoded.extend_from_slice(&keccak_256(username));
+    encoded.extend_from_slice(&abi_uint256(nonce));
+    let struct_hash = keccak_256(&encoded);
+
+    let mut digest_input = Vec::with_capacity(2 + 2 * 32);
+    digest_input.extend_from_slice(b"\x19\x01");
+    digest_input.extend_from_slice(&eip712_domain_separator(chain_id));
+    digest_input.extend_from_slice(&struct_hash);
+    keccak_256(&digest_input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sp_core::{ecdsa, Pair};
+
+    /// Sign `message` with `pair` and return the raw signature with recovery id 0 or 1.
+    fn sign(pair: &ecdsa::Pair, message: &[u8]) -> [u8; 65] {
+        pair.sign_prehashed(&eth_message_hash(message)).0
+    }
+
+    /// Find a test key whose signature over `message` has the raw recovery id `id`.
+    fn pair_with_recovery_id(message: &[u8], id: u8) -> (ecdsa::Pair, [u8; 65]) {
+        (0u8..=255)
+            .map(|seed| ecdsa::Pair::from_seed(&[seed; 32]))
+            .map(|pair| {
+                let signature = sign(&pair, message);
+                (pair, signature)
+            })
+            .find(|(_, signature)| signature[64] == id)
+            .expect("half of all signatures use each recovery id")
+    }
+
+    fn address_of(pair: &ecdsa::Pair) -> H160 {
+        let signature = sign(pair, b"");
+        let pubkey =
+            sp_io::crypto::secp256k1_ecdsa_recover(&signature, &eth_message_hash(b"")).unwrap();
+        eth_address(&pubkey)
+    }
+
+    #[test]
+    fn recovers_with_recovery_id_27() {
+        let (pair, mut signature) = pair_with_recovery_id(b"hello", 0);
+        signature[64] += 27;
+
+        assert_eq!(
+            recover_eth_address(b"hello", &signature),
+            Some(address_of(&pair))
+        );
+    }
+
+    #[test]
+    fn recovers_with_recovery_id_28() {
+        let (pair, mut signature) = pair_with_recovery_id(b"hello", 1);
+        signature[64] += 27;
+
+        assert_eq!(
+            recover_eth_address(b"hello", &signature),
+            Some(address_of(&pair))
+        );
+    }
+
+    #[test]
+    fn raw_and_ethereum_recovery_ids_agree() {
+        for id in 0..=1 {
+            let (pair, raw) = pair_with_recovery_id(b"hello", id);
+            let mut ethereum = raw;
+            ethereum[64] += 27;
+
+            assert_eq!(recover_eth_address(b"hello", &raw), Some(address_of(&pair)));
+            assert_eq!(
+                recover_eth_address(b"hello", &raw),
+                recover_eth_address(b"hello", &ethereum)
+            );
+        }
+    }
+
+    #[test]
+    fn accepted_v_bytes_follow_the_constants() {
+        let (_, signature) = pair_with_recovery_id(b"hello", 1);
+        assert_eq!(signature.len(), SIGNATURE_LEN);
+        assert!(signature[SIGNATURE_LEN - 1] <= MAX_RECOVERY_ID);
+
+        for v in 0..=u8::MAX {
+            let expected = match v {
+                _ if v <= MAX_RECOVERY_ID => Some(v),
+                _ if (ETH_V_OFFSET..=ETH_V_OFFSET + MAX_RECOVERY_ID).contains(&v) => {
+                    Some(v - ETH_V_OFFSET)
+                }
+                _ => None,
+            };
+            assert_eq!(normalize_recovery_id(v), expected, "v = {v}");
+        }
+    }
+
+    #[test]
+    fn rejects_out_of_range_recovery_id_and_bad_length() {
+        let (_, mut signature) = pair_with_recovery_id(b"hello", 0);
+        for v in [4, 26, 31] {
+            signature[64] = v;
+            assert_eq!(recover_eth_address(b"hello", &signature), None);
+        }
+        assert_eq!(recover_eth_address(b"hello", &signature[..64]), None);
+    }
+
+    #[test]
+    fn verify_tells_a_bad_signature_from_the_wrong_signer() {
+        let (pair, signature) = pair_with_recovery_id(b"hello", 0);
+        let signer = address_of(&pair);
+        let other = H160::repeat_byte(0x11);
+
+        assert_eq!(verify_eth_signature(&signer, b"hello", &signature), Ok(()));
+        assert_eq!(
+            verify_eth_signature(&other, b"hello", &signature),
+            Err(EthSignatureError::AddressMismatch { recovered: signer })
+        );
+        assert_eq!(
+            verify_eth_signature(&signer, b"hello", &signature[..64]),
+            Err(EthSignatureError::RecoveryFailed)
+        );
+        let mut bad_v = signature;
+        bad_v[64] = 4;
+        assert_eq!(
+            verify_eth_signature(&signer, b"hello", &bad_v),
+            Err(EthSignatureError::RecoveryFailed)
+        );
+    }
+
+    #[test]
+    fn clear_username_message_uses_lowercase_address() {
+        let address = H160::repeat_byte(0xab);
+        assert_eq!(
+            clear_username_message(42, &address, 7),
+            format!("clear_username:42:0x{}:7", "ab".repeat(20)).into_bytes()
+        );
+    }
+
+    #[test]
+    fn eip712_known_answer() {
+        // The well-known private key from the web3.js documentation.
+        let pair = ecdsa::Pair::from_seed(&hex_literal::hex!(
+            "4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318"
+        ));
+        let owner = H160(hex_literal::hex!(
+            "2c7536e3605d9c16a7a3d7b1898e529396a65c23"
+        ));
+        assert_eq!(address_of(&pair), owner);
+
+        let digest = eip712_set_username_hash(42, &owner, b"alice", 0);
+        assert_eq!(
+            digest,
+            hex_literal::hex!("4d4799bd275ccaf480fbe86fb59e5bcf0e2eeaf5165e4cb937dd2cfb300194e3")
+        );
+
+        let mut signature = pair.sign_prehashed(&digest).0;
+        signature[64] += 27;
+        assert_eq!(
+            signature,
+            hex_literal::hex!(
+                "cb7c03f6cc901a612fab41cf27dfb17a5faa5b3eec9a393f588da9573fa18950"
+                "2a49174c4f7ca9e6242e4f7562a10ae4cddfef55cf01a5fd49cd4ec9f3ab64791b"
+            )
+        );
+        assert_eq!(
+            recover_eth_address_prehashed(&digest, &signature),
+            Some(owner)
+        );
+    }
+
+    #[test]
+    fn set_username_message_is_byte_exact() {
+        assert_eq!(
+            set_username_message(42, b"alice", 7),
+            b"set_username:42:alice:7".to_vec()
+        );
+        assert_eq!(
+            set_username_message(1, &[0xff, b'a'], 0),
+            [&b"set_username:1:"[..], &[0xff, b'a'], b":0"].concat()
+        );
+    }
+
+    #[test]
+    fn to_checksum_matches_eip55_vectors() {
+        // From the EIP-55 specification.
+        for (address, expected) in [
+            (
+                hex_literal::hex!("5aaeb6053f3e94c9b9a09f33669435e7ef1beaed"),
+                "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+            ),
+            (
+                hex_literal::hex!("fb6916095ca1df60bb79ce92ce3ea74c37c5d359"),
+                "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359",
+            ),
+            (
+                hex_literal::hex!("dbf03b407c01e7cd3cbea99509d93f8dddc8c6fb"),
+                "0xdbF03B407c01E7cD3CBea99509d93f8DDDC8C6FB",
+            ),
+            (
+                hex_literal::hex!("d1220a0cf47c7b9be7a2e6ba89f429762e7b9adb"),
+                "0xD1220A0cf47c7B9Be7A2E6BA89F429762e7b9aDb",
+            ),
+        ] {
+            assert_eq!(to_checksum(&H160(address)), expected);
+        }
+    }
+
+    #[test]
+    fn format_address_is_checksummed_unless_lowercase() {
+        let address = H160(hex_literal::hex!(
+            "5aaeb6053f3e94c9b9a09f33669435e7ef1beaed"
+        ));
+        assert_eq!(
+            format_address(&address, false),
+            "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed"
+        );
+        assert_eq!(
+            format_address(&address, true),
+            "0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed"
+        );
+    }
+
+    #[test]
+    fn eip712_digest_depends_on_chain_id() {
+        let owner = H160::repeat_byte(1);
+        assert_ne!(
+            eip712_set_username_hash(1, &owner, b"alice", 0),
+            eip712_set_username_hash(2, &owner, b"alice", 0)
+        );
+    }
+
+    #[test]
+    fn other_message_recovers_other_address() {
+        let (pair, mut signature) = pair_with_recovery_id(b"hello", 0);
+        signature[64] += 27;
+
+        assert_ne!(
+            recover_eth_address(b"goodbye", &signature),
+            Some(address_of(&pair))
+        );
+    }
+}
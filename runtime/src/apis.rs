@@ -24,7 +24,7 @@
 // For more information, please refer to <http://unlicense.org>
 
 // External crates imports
-use alloc::vec::Vec;
+use alloc::{vec, vec::Vec};
 use frame_support::{
     genesis_builder_helper::{build_state, get_preset},
     weights::Weight,
@@ -42,15 +42,73 @@ use sp_version::RuntimeVersion;
 
 // Local module imports
 use super::{
-    AccountId, Aura, Balance, Block, Executive, Grandpa, InherentDataExt, Nonce, Runtime,
-    RuntimeCall, RuntimeGenesisConfig, SessionKeys, System, TransactionPayment, VERSION,
+    AccountId, Aura, Balance, Block, BlockNumber, Executive, Grandpa, InherentDataExt, Nonce,
+    Runtime, RuntimeCall, RuntimeEvent, RuntimeGenesisConfig, SessionKeys, System,
+    TransactionPayment, VERSION,
 };
 use sp_core::H160;
 
 sp_api::decl_runtime_apis! {
+    /// Version 1 had only `get_username` and `get_nonce`. Version 2 added the other lookups,
+    /// with unpaged `list_usernames` and `search_usernames`. Version 3 pages both scans.
     pub trait UsernameApi {
         fn get_username(eth_address: H160) -> Option<Vec<u8>>;
         fn get_nonce(eth_address: H160) -> u64;
+        /// The username a Substrate account claimed without an Ethereum key.
+        #[api_version(2)]
+        fn get_substrate_username(account: AccountId) -> Option<Vec<u8>>;
+        #[api_version(2)]
+        fn get_usernames(addresses: Vec<H160>) -> Vec<(H160, Option<Vec<u8>>)>;
+        /// The username, nonce, registration block and metadata of `eth_address`, read at one
+        /// block.
+        #[api_version(2)]
+        fn get_profile(eth_address: H160) -> pallet_template::AddressProfile<BlockNumber>;
+        /// The block at which `eth_address` registered its current username.
+        #[api_version(2)]
+        fn get_registration_block(eth_address: H160) -> Option<BlockNumber>;
+        /// Recent username changes of `eth_address`, oldest first. An empty username marks a
+        /// removal.
+        #[api_version(2)]
+        fn get_username_history(eth_address: H160) -> Vec<(Vec<u8>, BlockNumber)>;
+        #[api_version(2)]
+        fn get_address(username: Vec<u8>) -> Option<H160>;
+        #[changed_in(3)]
+        fn list_usernames(start_key: Option<H160>, limit: u32) -> Vec<(H160, Vec<u8>)>;
+        /// Up to `limit` usernames after the address `start_key`. `limit` must be between 1 and
+        /// `pallet_template::MAX_RPC_SCAN`.
+        #[api_version(3)]
+        fn list_usernames(
+            start_key: Option<H160>,
+            limit: u32,
+        ) -> Result<pallet_template::ScanPage<H160>, pallet_template::ScanError>;
+        #[changed_in(3)]
+        fn search_usernames(prefix: Vec<u8>, limit: u32) -> Vec<(H160, Vec<u8>)>;
+        /// Up to `limit` usernames starting with `prefix`, ignoring ASCII case, after the
+        /// normalized username `start_key`. Visits at most `pallet_template::MAX_RPC_SCAN`
+        /// registrations per call.
+        #[api_version(3)]
+        fn search_usernames(
+            prefix: Vec<u8>,
+            start_key: Option<Vec<u8>>,
+            limit: u32,
+        ) -> Result<pallet_template::ScanPage<Vec<u8>>, pallet_template::ScanError>;
+        #[api_version(2)]
+        fn username_count() -> u32;
+        /// Username changes made in this block, read from its events: `Some(username)` for a
+        /// set, `None` for a clear or expiry.
+        #[api_version(2)]
+        fn username_changes() -> Vec<(H160, Option<Vec<u8>>)>;
+        #[api_version(2)]
+        fn is_username_available(username: Vec<u8>) -> bool;
+        /// `(min, max)` username length in bytes.
+        #[api_version(2)]
+        fn username_length_bounds() -> (u32, u32);
+        /// Whether `username` satisfies the runtime's character rules, ignoring its length.
+        #[api_version(2)]
+        fn is_valid_username(username: Vec<u8>) -> bool;
+        /// The length, charset and cooldown rules usernames are checked against.
+        #[api_version(2)]
+        fn username_config() -> pallet_template::UsernameConfig<BlockNumber>;
     }
 }
 
@@ -83,14 +141,96 @@ impl_runtime_apis! {
         }
     }
 
+    #[api_version(3)]
     impl self::UsernameApi<Block> for Runtime {
         fn get_username(eth_address: H160) -> Option<Vec<u8>> {
             crate::Template::get_username(eth_address)
         }
 
+        fn get_substrate_username(account: AccountId) -> Option<Vec<u8>> {
+            crate::Template::get_substrate_username(account)
+        }
+
         fn get_nonce(eth_address: H160) -> u64 {
             crate::Template::get_nonce(eth_address)
         }
+
+        fn get_usernames(addresses: Vec<H160>) -> Vec<(H160, Option<Vec<u8>>)> {
+            crate::Template::get_usernames(addresses)
+        }
+
+        fn get_profile(eth_address: H160) -> pallet_template::AddressProfile<BlockNumber> {
+            crate::Template::get_address_profile(eth_address)
+        }
+
+        fn get_registration_block(eth_address: H160) -> Option<BlockNumber> {
+            crate::Template::get_registration_block(eth_address)
+        }
+
+        fn get_username_history(eth_address: H160) -> Vec<(Vec<u8>, BlockNumber)> {
+            crate::Template::get_username_history(eth_address)
+        }
+
+        fn get_address(username: Vec<u8>) -> Option<H160> {
+            crate::Template::get_owner(username)
+        }
+
+        fn list_usernames(
+            start_key: Option<H160>,
+            limit: u32,
+        ) -> Result<pallet_template::ScanPage<H160>, pallet_template::ScanError> {
+            crate::Template::list_usernames(start_key, limit)
+        }
+
+        fn search_usernames(
+            prefix: Vec<u8>,
+            start_key: Option<Vec<u8>>,
+            limit: u32,
+        ) -> Result<pallet_template::ScanPage<Vec<u8>>, pallet_template::ScanError> {
+            crate::Template::search_usernames(prefix, start_key, limit)
+        }
+
+        fn username_count() -> u32 {
+            crate::Template::username_count()
+        }
+
+        fn username_changes() -> Vec<(H160, Option<Vec<u8>>)> {
+            use pallet_template::Event;
+
+            System::read_events_no_consensus()
+                .flat_map(|record| match record.event {
+                    RuntimeEvent::Template(Event::UsernameSet { eth_address, username, .. })
+                    | RuntimeEvent::Template(Event::UsernameForceSet { eth_address, username }) => {
+                        vec![(eth_address, Some(username.into_inner()))]
+                    }
+                    RuntimeEvent::Template(Event::UsernameCleared { eth_address })
+                    | RuntimeEvent::Template(Event::UsernameForceCleared { eth_address })
+                    | RuntimeEvent::Template(Event::UsernameExpired { eth_address, .. }) => {
+                        vec![(eth_address, None)]
+                    }
+                    RuntimeEvent::Template(Event::UsernameTransferred { from, to, username }) => {
+                        vec![(from, None), (to, Some(username.into_inner()))]
+                    }
+                    _ => vec![],
+                })
+                .collect()
+        }
+
+        fn is_username_available(username: Vec<u8>) -> bool {
+            crate::Template::is_username_available(username)
+        }
+
+        fn username_length_bounds() -> (u32, u32) {
+            crate::Template::username_length_bounds()
+        }
+
+        fn is_valid_username(username: Vec<u8>) -> bool {
+            crate::Template::is_valid_username(username)
+        }
+
+        fn username_config() -> pallet_template::UsernameConfig<BlockNumber> {
+            crate::Template::username_config()
+        }
     }
 
     impl frame_support::view_functions::runtime_api::RuntimeViewFunction<Block> for Runtime {
@@ -42,7 +42,7 @@ use sp_version::RuntimeVersion;
 use super::{
     AccountId, Aura, Balance, Balances, Block, BlockNumber, Hash, Nonce, PalletInfo, Runtime,
     RuntimeCall, RuntimeEvent, RuntimeFreezeReason, RuntimeHoldReason, RuntimeOrigin, RuntimeTask,
-    System, EXISTENTIAL_DEPOSIT, SLOT_DURATION, VERSION,
+    System, DAYS, EXISTENTIAL_DEPOSIT, MINUTES, SLOT_DURATION, UNIT, VERSION,
 };
 
 const NORMAL_DISPATCH_RATIO: Perbill = Perbill::from_percent(75);
@@ -157,9 +157,28 @@ impl pallet_sudo::Config for Runtime {
     type WeightInfo = pallet_sudo::weights::SubstrateWeight<Runtime>;
 }
 
+parameter_types! {
+    pub const UsernameTtl: Option<BlockNumber> = Some(365 * DAYS);
+}
+
 /// Configure the pallet-template in pallets/template.
 impl pallet_template::Config for Runtime {
     type RuntimeEvent = RuntimeEvent;
     type MaxUsernameLength = ConstU32<32>;
-    // type WeightInfo = pallet_template::weights::SubstrateWeight<Runtime>;
+    type MinUsernameLength = ConstU32<3>;
+    type UsernameValidator = pallet_template::AlphanumericUnderscore;
+    type ChainId = ConstU64<42>;
+    type ChangeCooldown = ConstU32<{ 10 * MINUTES }>;
+    type Currency = Balances;
+    type UsernameDeposit = ConstU128<UNIT>;
+    type UsernameTtl = UsernameTtl;
+    type MaxExpiriesPerBlock = ConstU32<50>;
+    type MaxAliases = ConstU32<8>;
+    type MaxHistory = ConstU32<5>;
+    type RequireAllowlist = ConstBool<false>;
+    type AllowNonceSkip = ConstBool<false>;
+    type OffchainIndexing = ConstBool<true>;
+    type MaxRegistrationsPerBlock = ConstU32<100>;
+    type EmitMessageHash = ConstBool<true>;
+    type WeightInfo = pallet_template::weights::SubstrateWeight<Runtime>;
 }
@@ -1,103 +1,481 @@
-use clap::Parser;
-use sp_core::{ecdsa, Pair, H160};
-use sp_io::hashing::keccak_256;
+use clap::{Parser, Subcommand};
+use sp_core::{ecdsa, hashing::blake2_256, Pair, H160};
+use tracing_subscriber::EnvFilter;
+use username_primitives::{
+    clear_username_message, eip712_set_username_hash, eth_message_hash, format_address,
+    recover_eth_address, recover_eth_address_prehashed, set_username_message, EIP712_DOMAIN_NAME,
+    EIP712_DOMAIN_VERSION, ETH_V_OFFSET, SIGNATURE_LEN,
+};
 
 #[derive(Parser, Debug)]
 #[command(name = "signature")]
 struct Args {
-    /// Username to register
-    #[arg(short, long)]
-    username: String,
+    #[command(subcommand)]
+    command: Command,
 
-    /// Nonce for replay protection
-    #[arg(short, long)]
-    nonce: u64,
+    /// Print a single JSON object instead of the human-readable output
+    #[arg(long, global = true)]
+    json: bool,
+
+    /// Print addresses as lowercase hex instead of the EIP-55 checksummed form
+    #[arg(long, global = true)]
+    lowercase: bool,
+
+    /// Show more log output: -v for info, -vv for debug, -vvv for trace. Without it,
+    /// `RUST_LOG` is honored
+    #[arg(short, long, global = true, action = clap::ArgAction::Count)]
+    verbose: u8,
+}
 
+/// The key to sign with and the chain to sign for.
+#[derive(clap::Args, Debug)]
+struct SignerArgs {
     #[arg(short, long)]
     private_key: Option<String>,
+
+    /// File holding the private key as hex, so it stays out of shell history and `ps`
+    #[arg(long, conflicts_with_all = ["private_key", "mnemonic"])]
+    private_key_file: Option<std::path::PathBuf>,
+
+    /// BIP-39 mnemonic to derive the key from, instead of a raw private key
+    #[arg(short, long, conflicts_with = "private_key")]
+    mnemonic: Option<String>,
+
+    /// Chain id of the target runtime, used as the signature's domain separator
+    #[arg(short, long, default_value_t = 42)]
+    chain_id: u64,
 }
 
-fn main() {
-    let args = Args::parse();
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Sign `set_username:{chain_id}:{username}:{nonce}`. Without a key, a fresh one is
+    /// generated and printed
+    Set {
+        /// Username to register
+        #[arg(short, long)]
+        username: String,
 
-    let pair = if let Some(pk_hex) = args.private_key {
-        let pk_bytes =
-            hex::decode(pk_hex.trim_start_matches("0x")).expect("Invalid private key hex");
+        /// Nonce for replay protection
+        #[arg(short, long)]
+        nonce: u64,
 
-        if pk_bytes.len() != 32 {
-            eprintln!("Private key must be 32 bytes");
-            return;
+        #[command(flatten)]
+        signer: SignerArgs,
+
+        /// Sign the EIP-712 `SetUsername` typed data instead of a `personal_sign` message.
+        /// Submit the result with `set_username_with_scheme` and the `EthEip712` scheme
+        #[arg(long)]
+        eip712: bool,
+    },
+    /// Sign `clear_username:{chain_id}:{eth_address}:{nonce}` to remove the username of the
+    /// key's address
+    Clear {
+        /// Address whose username is cleared. Defaults to the key's address; if given, it must
+        /// match it
+        #[arg(short, long)]
+        address: Option<String>,
+
+        /// Current nonce of the address
+        #[arg(short, long)]
+        nonce: u64,
+
+        #[command(flatten)]
+        signer: SignerArgs,
+    },
+    /// Check a `personal_sign` signature against an address. Exits with status 1 unless it matches
+    Verify {
+        /// Ethereum address the signature should recover to
+        #[arg(long)]
+        address: String,
+
+        /// The signed message, e.g. `set_username:42:alice:0`
+        #[arg(long)]
+        message: String,
+
+        /// 65-byte signature as hex, with `v` as 27/28 or 0/1
+        #[arg(long)]
+        signature: String,
+    },
+    /// Generate throwaway wallets and print them as a JSON array
+    Gen {
+        /// Number of wallets to generate
+        #[arg(long, default_value_t = 1)]
+        count: u32,
+
+        /// Derive the keys from this seed instead of the OS RNG, for reproducible fixtures
+        #[arg(long)]
+        seed: Option<String>,
+    },
+}
+
+/// The Ethereum address of `pair`, derived the same way the pallet does when verifying.
+fn eth_address_of(pair: &ecdsa::Pair) -> Option<H160> {
+    let probe_hash = [0u8; 32];
+    let mut probe = pair.sign_prehashed(&probe_hash).0;
+    probe[SIGNATURE_LEN - 1] += ETH_V_OFFSET;
+    recover_eth_address_prehashed(&probe_hash, &probe)
+}
+
+/// Prints `count` wallets as `[{ private_key, eth_address }]`. With a `seed`, wallet `i` uses
+/// the key `blake2_256("{seed}/{i}")`, so the same seed always yields the same wallets.
+fn generate_wallets(count: u32, seed: Option<&str>, lowercase: bool) {
+    let wallets: Vec<_> = (0..count)
+        .map(|i| {
+            let (pair, key) = match seed {
+                Some(seed) => {
+                    let key = blake2_256(format!("{}/{}", seed, i).as_bytes());
+                    (ecdsa::Pair::from_seed(&key), key)
+                }
+                None => ecdsa::Pair::generate(),
+            };
+            let eth_address = eth_address_of(&pair).expect("a fresh key always recovers");
+            serde_json::json!({
+                "private_key": format!("0x{}", hex::encode(key)),
+                "eth_address": format_address(&eth_address, lowercase),
+            })
+        })
+        .collect();
+
+    println!("{}", serde_json::Value::Array(wallets));
+}
+
+/// Parse a `0x`-prefixed or bare hex address, exiting nonzero if it isn't 20 bytes.
+fn parse_address(address: &str) -> H160 {
+    match hex::decode(address.trim_start_matches("0x")) {
+        Ok(bytes) if bytes.len() == 20 => H160::from_slice(&bytes),
+        _ => {
+            eprintln!("Address must be 20 bytes of hex");
+            std::process::exit(1);
         }
+    }
+}
 
-        let seed: [u8; 32] = pk_bytes.try_into().unwrap();
-        ecdsa::Pair::from_seed(&seed)
+/// Recovers the signer of `message` and compares it with `address`, exiting nonzero on
+/// malformed input or a mismatch.
+fn verify(address: &str, message: &str, signature: &str, json: bool, lowercase: bool) {
+    let address = parse_address(address);
+    let signature = match hex::decode(signature.trim_start_matches("0x")) {
+        Ok(bytes) => bytes,
+        Err(_) => {
+            eprintln!("Invalid signature hex");
+            std::process::exit(1);
+        }
+    };
+
+    let recovered = recover_eth_address(message.as_bytes(), &signature);
+    let valid = recovered == Some(address);
+
+    if json {
+        let output = serde_json::json!({
+            "valid": valid,
+            "recovered_address": recovered.map(|a| format_address(&a, lowercase)),
+        });
+        println!("{}", output);
     } else {
-        let (pair, seed) = ecdsa::Pair::generate();
-        println!("=== Generated Private Key ===");
-        println!("Private Key: 0x{}", hex::encode(&seed));
-        println!();
-        pair
+        match recovered {
+            Some(recovered) => {
+                println!(
+                    "Recovered Address: {}",
+                    format_address(&recovered, lowercase)
+                )
+            }
+            None => println!("Recovered Address: none (malformed or high-s signature)"),
+        }
+        println!("Valid: {}", valid);
+    }
+
+    if !valid {
+        std::process::exit(1);
+    }
+}
+
+/// The `submit_account set` invocation that registers `username` with this signature.
+fn submit_command(eth_address: &str, username: &str, nonce: u64, signature: &str) -> String {
+    format!(
+        "submit_account set --eth-address {} --username {} --nonce {} --signature {}",
+        eth_address, username, nonce, signature
+    )
+}
+
+/// The `submit_account clear` invocation that removes the username with this signature.
+fn submit_clear_command(eth_address: &str, nonce: u64, signature: &str) -> String {
+    format!(
+        "submit_account clear --eth-address {} --nonce {} --signature {}",
+        eth_address, nonce, signature
+    )
+}
+
+/// The `eth_signTypedData_v4` payload matching `eip712_set_username_hash`.
+fn typed_data(chain_id: u64, owner: &str, username: &str, nonce: u64) -> serde_json::Value {
+    serde_json::json!({
+        "types": {
+            "EIP712Domain": [
+                { "name": "name", "type": "string" },
+                { "name": "version", "type": "string" },
+                { "name": "chainId", "type": "uint256" },
+            ],
+            "SetUsername": [
+                { "name": "owner", "type": "address" },
+                { "name": "username", "type": "string" },
+                { "name": "nonce", "type": "uint256" },
+            ],
+        },
+        "primaryType": "SetUsername",
+        "domain": {
+            "name": EIP712_DOMAIN_NAME,
+            "version": EIP712_DOMAIN_VERSION,
+            "chainId": chain_id,
+        },
+        "message": {
+            "owner": owner,
+            "username": username,
+            "nonce": nonce,
+        },
+    })
+}
+
+/// Send log output to stderr: `RUST_LOG` decides what is shown, unless `-v` (info), `-vv`
+/// (debug) or `-vvv` (trace) is given. Only warnings are shown by default, so the regular
+/// output stays as it was.
+fn init_logging(verbose: u8) {
+    let filter = match verbose {
+        0 => EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("warn")),
+        1 => EnvFilter::new("info"),
+        2 => EnvFilter::new("debug"),
+        _ => EnvFilter::new("trace"),
     };
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(std::io::stderr)
+        .init();
+}
 
-    let username = args.username.as_bytes();
+/// Load the key from `--private-key`, `--private-key-file` or `--mnemonic`, or generate one.
+///
+/// `seed` is only returned when the key was generated or derived here, so it can be shown.
+/// Unless `quiet`, it is also printed right away.
+fn load_key(signer: &SignerArgs, quiet: bool) -> Result<(ecdsa::Pair, Option<[u8; 32]>), String> {
+    let private_key = match &signer.private_key_file {
+        Some(path) => match std::fs::read_to_string(path) {
+            Ok(contents) => Some(contents.trim().to_string()),
+            Err(e) => return Err(format!("Unable to read {}: {}", path.display(), e)),
+        },
+        None => signer.private_key.clone(),
+    };
 
-    // Message format: "set_username:{username}:{nonce}"
-    let mut message = b"set_username:".to_vec();
-    message.extend_from_slice(username);
-    message.push(b':');
-    message.extend_from_slice(args.nonce.to_string().as_bytes());
+    if let Some(pk_hex) = private_key {
+        let pk_bytes = hex::decode(pk_hex.trim_start_matches("0x"))
+            .map_err(|_| "Invalid private key hex".to_string())?;
 
-    let prefix = format!("\x19Ethereum Signed Message:\n{}", message.len());
-    let mut eth_message = prefix.as_bytes().to_vec();
-    eth_message.extend_from_slice(&message);
+        if pk_bytes.len() != 32 {
+            return Err("Private key must be 32 bytes".to_string());
+        }
+
+        let seed: [u8; 32] = pk_bytes.try_into().unwrap();
+        Ok((ecdsa::Pair::from_seed(&seed), None))
+    } else if let Some(phrase) = &signer.mnemonic {
+        let (pair, seed) = ecdsa::Pair::from_phrase(phrase, None)
+            .map_err(|e| format!("Invalid mnemonic: {:?}", e))?;
+        if !quiet {
+            println!("=== Derived Private Key ===");
+            println!("Private Key: 0x{}", hex::encode(&seed));
+            println!();
+        }
+        Ok((pair, Some(seed)))
+    } else {
+        let (pair, seed) = ecdsa::Pair::generate();
+        if !quiet {
+            println!("=== Generated Private Key ===");
+            println!("Private Key: 0x{}", hex::encode(&seed));
+            println!();
+        }
+        Ok((pair, Some(seed)))
+    }
+}
 
-    let message_hash = keccak_256(&eth_message);
-    let signature = pair.sign_prehashed(&message_hash);
+/// Sign `hash` with `pair`, returning `0x`-prefixed hex with `v` in the 27/28 convention.
+fn sign_hex(pair: &ecdsa::Pair, hash: &[u8; 32]) -> String {
+    let signature = pair.sign_prehashed(hash);
 
     // Convert Substrate format (0-3) to Ethereum format (27-30)
     let mut sig_bytes = signature.0.to_vec();
-    if sig_bytes[64] < 27 {
-        sig_bytes[64] += 27;
+    if sig_bytes[SIGNATURE_LEN - 1] < ETH_V_OFFSET {
+        sig_bytes[SIGNATURE_LEN - 1] += ETH_V_OFFSET;
     }
+    format!("0x{}", hex::encode(&sig_bytes))
+}
 
-    let mut recover_sig = [0u8; 65];
-    recover_sig.copy_from_slice(&sig_bytes);
-    if recover_sig[64] >= 27 {
-        recover_sig[64] -= 27;
+/// Signs the `clear_username` message for the key's address, which must be `address` if one
+/// is given.
+fn sign_clear_username(args: &Args, address: Option<&str>, nonce: u64, signer: &SignerArgs) {
+    // A fresh key has no username to clear.
+    if signer.private_key.is_none()
+        && signer.private_key_file.is_none()
+        && signer.mnemonic.is_none()
+    {
+        eprintln!(
+            "clear needs the owner's key: pass --private-key, --private-key-file or --mnemonic"
+        );
+        std::process::exit(1);
     }
+    let (pair, _) = load_key(signer, true).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    });
 
-    let recovered_pubkey = match sp_io::crypto::secp256k1_ecdsa_recover(&recover_sig, &message_hash)
-    {
-        Ok(key) => key,
-        Err(_) => {
-            eprintln!("Failed to recover public key");
-            return;
-        }
+    let Some(eth_address) = eth_address_of(&pair) else {
+        eprintln!("Failed to recover public key");
+        std::process::exit(1);
     };
+    if let Some(address) = address.map(parse_address) {
+        if address != eth_address {
+            eprintln!(
+                "The key belongs to {}, not {}",
+                format_address(&eth_address, args.lowercase),
+                format_address(&address, args.lowercase)
+            );
+            std::process::exit(1);
+        }
+    }
+    let display_address = format_address(&eth_address, args.lowercase);
 
-    // Derive Ethereum address from RECOVERED public key (skip first byte)
-    let addr_hash = keccak_256(&recovered_pubkey[1..]);
-    let eth_address = H160::from_slice(&addr_hash[12..32]);
-    let sig_hex = hex::encode(&sig_bytes);
+    let message = clear_username_message(signer.chain_id, &eth_address, nonce);
+    let signature_hex = sign_hex(&pair, &eth_message_hash(&message));
 
-    println!("=== Test Wallet Generated ===");
+    if args.json {
+        let output = serde_json::json!({
+            "eth_address": display_address,
+            "message": String::from_utf8_lossy(&message),
+            "signature": signature_hex,
+            "nonce": nonce,
+            "submit_command": submit_clear_command(&display_address, nonce, &signature_hex),
+        });
+        println!("{}", output);
+        return;
+    }
+
+    println!("=== Clear Signature ===");
+    println!("Ethereum Address: {}", display_address);
+    println!("Message: {}", String::from_utf8_lossy(&message));
+    println!("Signature: {}", signature_hex);
+    println!();
+    println!("=== Clear the username using submit_account binary ===");
     println!(
-        "Ethereum Address: 0x{}",
-        hex::encode(eth_address.as_bytes())
+        "{}",
+        submit_clear_command(&display_address, nonce, &signature_hex)
     );
-    println!("Message: {}", String::from_utf8_lossy(message.as_slice()));
-    println!("Signature: 0x{}", sig_hex);
+}
+
+/// Signs the `set_username` message, or the EIP-712 `SetUsername` typed data with `eip712`.
+fn sign_set_username(
+    args: &Args,
+    username_arg: &str,
+    nonce: u64,
+    signer: &SignerArgs,
+    eip712: bool,
+) {
+    let (pair, seed) = load_key(signer, args.json).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    });
+
+    let username = username_arg.as_bytes();
+
+    let message = set_username_message(signer.chain_id, username, nonce);
+
+    // The EIP-712 struct contains the address, so it has to be known before signing.
+    let Some(eth_address) = eth_address_of(&pair) else {
+        eprintln!("Failed to recover public key");
+        std::process::exit(1);
+    };
+    let display_address = format_address(&eth_address, args.lowercase);
+
+    let message_hash = if eip712 {
+        eip712_set_username_hash(signer.chain_id, &eth_address, username, nonce)
+    } else {
+        eth_message_hash(&message)
+    };
+    let signature_hex = sign_hex(&pair, &message_hash);
+
+    let typed_data =
+        eip712.then(|| typed_data(signer.chain_id, &display_address, username_arg, nonce));
+
+    if args.json {
+        let mut output = serde_json::json!({
+            "eth_address": display_address,
+            "message": String::from_utf8_lossy(message.as_slice()),
+            "signature": signature_hex,
+            "nonce": nonce,
+        });
+        // `submit_account` only submits `personal_sign` signatures.
+        match typed_data {
+            Some(typed_data) => output["typed_data"] = typed_data,
+            None => {
+                output["submit_command"] =
+                    submit_command(&display_address, username_arg, nonce, &signature_hex).into()
+            }
+        }
+        if let Some(seed) = seed {
+            output["private_key"] = format!("0x{}", hex::encode(seed)).into();
+        }
+        println!("{}", output);
+        return;
+    }
+
+    println!("=== Test Wallet Generated ===");
+    println!("Ethereum Address: {}", display_address);
+    match &typed_data {
+        Some(typed_data) => println!(
+            "Typed data (EIP-712): {}",
+            serde_json::to_string_pretty(typed_data).unwrap()
+        ),
+        None => println!("Message: {}", String::from_utf8_lossy(message.as_slice())),
+    }
+    println!("Signature: {}", signature_hex);
     println!();
     println!("=== First store username using submit_account binary ===");
-    println!("Address: 0x{}", hex::encode(eth_address.as_bytes()));
+    println!("Address: {}", display_address);
+    if eip712 {
+        println!("Note: submit through set_username_with_scheme with the EthEip712 scheme");
+    } else {
+        println!(
+            "{}",
+            submit_command(&display_address, username_arg, nonce, &signature_hex)
+        );
+    }
 
     println!();
 
     println!("=== Then test with curl ===");
     println!(r#"curl -H "Content-Type: application/json" \"#);
     println!(
-        r#"  -d '{{"id":1,"jsonrpc":"2.0","method":"username_get","params":["0x{}", null]}}' \"#,
-        hex::encode(eth_address.as_bytes())
+        r#"  -d '{{"id":1,"jsonrpc":"2.0","method":"username_get","params":["{}", null]}}' \"#,
+        display_address
     );
     println!(r#"  http://localhost:9944"#);
 }
+
+fn main() {
+    let args = Args::parse();
+    init_logging(args.verbose);
+
+    match &args.command {
+        Command::Set {
+            username,
+            nonce,
+            signer,
+            eip712,
+        } => sign_set_username(&args, username, *nonce, signer, *eip712),
+        Command::Clear {
+            address,
+            nonce,
+            signer,
+        } => sign_clear_username(&args, address.as_deref(), *nonce, signer),
+        Command::Verify {
+            address,
+            message,
+            signature,
+        } => verify(address, message, signature, args.json, args.lowercase),
+        Command::Gen { count, seed } => generate_wallets(*count, seed.as_deref(), args.lowercase),
+    }
+}
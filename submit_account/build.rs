@@ -0,0 +1,62 @@
+//! Generates the library's `polkadot` module from runtime metadata.
+//!
+//! By default the metadata comes from the runtime in this workspace, so the client is always
+//! built against the calls, storage and events it will talk to. Set `SUBMIT_METADATA_PATH` to a
+//! SCALE-encoded metadata file, e.g. one written by `subxt metadata`, to build the client for
+//! another chain instead. A relative path is taken from the `submit_account` directory. The
+//! runtime is only a build dependency with the default `runtime-metadata` feature, so a build
+//! with `--no-default-features` needs `SUBMIT_METADATA_PATH`.
+
+use codec::Decode;
+use std::{env, fs, path::PathBuf};
+use subxt_codegen::{CodegenBuilder, Metadata};
+
+/// The environment variable that points the build at a metadata file.
+const METADATA_PATH_VAR: &str = "SUBMIT_METADATA_PATH";
+
+/// The metadata version the client is generated from when it is read from the runtime.
+#[cfg(feature = "runtime-metadata")]
+const METADATA_VERSION: u32 = 15;
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-env-changed={METADATA_PATH_VAR}");
+
+    let bytes = match env::var_os(METADATA_PATH_VAR) {
+        Some(path) => {
+            let path = PathBuf::from(path);
+            println!("cargo:rerun-if-changed={}", path.display());
+            fs::read(&path).unwrap_or_else(|e| {
+                panic!("Unable to read {METADATA_PATH_VAR}={}: {e}", path.display())
+            })
+        }
+        None => runtime_metadata(),
+    };
+    let metadata = Metadata::decode(&mut &bytes[..])
+        .unwrap_or_else(|e| panic!("Unable to decode the runtime metadata: {e}"));
+
+    let mut codegen = CodegenBuilder::new();
+    codegen.set_target_module(syn::parse_quote!(
+        pub mod polkadot {}
+    ));
+    let code = codegen
+        .generate(metadata)
+        .unwrap_or_else(|e| panic!("Unable to generate the client code: {e}"));
+
+    let out_dir = PathBuf::from(env::var_os("OUT_DIR").expect("cargo sets OUT_DIR"));
+    fs::write(out_dir.join("polkadot.rs"), code.to_string())
+        .expect("Unable to write the generated client code");
+}
+
+/// The metadata of the runtime in this workspace.
+#[cfg(feature = "runtime-metadata")]
+fn runtime_metadata() -> Vec<u8> {
+    solochain_template_runtime::Runtime::metadata_at_version(METADATA_VERSION)
+        .unwrap_or_else(|| panic!("The runtime doesn't provide metadata V{METADATA_VERSION}"))
+        .to_vec()
+}
+
+#[cfg(not(feature = "runtime-metadata"))]
+fn runtime_metadata() -> Vec<u8> {
+    panic!("Set {METADATA_PATH_VAR} or build with the `runtime-metadata` feature")
+}
@@ -0,0 +1,571 @@
+//! Building blocks of the `submit_account` tool: connecting to a node, checking and submitting
+//! signed username calls, and reading the results back. The binary only parses its arguments
+//! and calls into these.
+
+use clap::ValueEnum;
+use sp_core::H160;
+use std::{str::FromStr, time::Duration};
+use subxt::{
+    blocks::{Block, ExtrinsicEvents},
+    ext::scale_value::{Composite, Value, ValueDef},
+    tx::Payload,
+    utils::H256,
+    OnlineClient,
+};
+use subxt_signer::{
+    sr25519::{dev, Keypair},
+    SecretUri,
+};
+pub use username_primitives::format_address;
+use username_primitives::SIGNATURE_LEN;
+
+// Generated by `build.rs` from the runtime's metadata, so it follows the runtime on every
+// build, or from the file `SUBMIT_METADATA_PATH` names.
+include!(concat!(env!("OUT_DIR"), "/polkadot.rs"));
+
+/// The chain configuration extrinsics are built and signed with. `PolkadotConfig` matches this
+/// repository's runtime; build with `--features substrate-config` to use `SubstrateConfig` for
+/// chains with its address and extrinsic types.
+#[cfg(not(feature = "substrate-config"))]
+pub type ChainConfig = subxt::PolkadotConfig;
+#[cfg(feature = "substrate-config")]
+pub type ChainConfig = subxt::SubstrateConfig;
+
+/// The point after submission at which the tool reports success.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum WaitFor {
+    /// Return once the extrinsic is included in a block, which may still be reverted
+    InBlock,
+    /// Return once the including block is finalized
+    Finalized,
+}
+
+/// Where a submitted extrinsic ended up.
+#[derive(Clone, Debug)]
+pub struct Inclusion {
+    /// Hash of the block that includes the extrinsic.
+    pub block_hash: H256,
+    /// Hash of the extrinsic itself.
+    pub tx_hash: H256,
+    /// The events `Template` emitted for the extrinsic.
+    pub events: Vec<TemplateEvent>,
+}
+
+/// An event of the `Template` pallet, with its fields rendered as text.
+#[derive(Clone, Debug)]
+pub struct TemplateEvent {
+    /// The event variant, e.g. `UsernameSet`.
+    pub name: String,
+    /// The event's fields as `(name, value)` pairs, in declaration order.
+    pub fields: Vec<(String, String)>,
+}
+
+/// The dispatchable a [`Submission`] calls.
+pub enum Action {
+    /// `set_username` with this username.
+    Set(String),
+    /// `clear_username`.
+    Clear,
+}
+
+impl std::fmt::Display for Action {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Action::Set(username) => write!(f, "set {username}"),
+            Action::Clear => write!(f, "clear"),
+        }
+    }
+}
+
+/// A single signed username call, decoded from the command line or a CSV row.
+pub struct Submission {
+    pub eth_address: H160,
+    pub action: Action,
+    pub nonce: u64,
+    pub signature: Vec<u8>,
+}
+
+pub const DEV_ACCOUNTS: &[&str] = &["alice", "bob", "charlie", "dave", "eve", "ferdie"];
+
+/// Resolve `--signer` to an sr25519 keypair.
+pub fn parse_signer(signer: &str) -> Result<Keypair, String> {
+    match signer.to_lowercase().as_str() {
+        "alice" => return Ok(dev::alice()),
+        "bob" => return Ok(dev::bob()),
+        "charlie" => return Ok(dev::charlie()),
+        "dave" => return Ok(dev::dave()),
+        "eve" => return Ok(dev::eve()),
+        "ferdie" => return Ok(dev::ferdie()),
+        _ => {}
+    }
+
+    // A single bare word can only have been meant as a dev account name.
+    if !signer.contains(char::is_whitespace)
+        && !signer.starts_with("//")
+        && !signer.starts_with("0x")
+    {
+        return Err(format!(
+            "Unknown dev account '{signer}', expected one of: {}",
+            DEV_ACCOUNTS.join(", ")
+        ));
+    }
+
+    let uri = SecretUri::from_str(signer).map_err(|e| format!("Invalid signer URI: {e}"))?;
+    Keypair::from_uri(&uri).map_err(|e| format!("Invalid signer: {e}"))
+}
+
+pub fn parse_eth_address(s: &str) -> Result<H160, String> {
+    let bytes = hex::decode(s.trim().trim_start_matches("0x"))
+        .map_err(|_| "Invalid Ethereum address hex")?;
+
+    if bytes.len() != 20 {
+        return Err("Ethereum address must be 20 bytes".into());
+    }
+
+    Ok(H160::from_slice(&bytes))
+}
+
+pub fn parse_signature(s: &str) -> Result<Vec<u8>, String> {
+    let signature =
+        hex::decode(s.trim().trim_start_matches("0x")).map_err(|_| "Invalid signature hex")?;
+
+    if signature.len() != SIGNATURE_LEN {
+        return Err(format!("Signature must be {SIGNATURE_LEN} bytes"));
+    }
+
+    Ok(signature)
+}
+
+/// Whether `username` uses the runtime's character set: ASCII letters, digits and `_`, with at
+/// least one letter or digit and no leading digit. Keep in step with
+/// `pallet_template::AlphanumericUnderscore`.
+pub fn is_valid_charset(username: &str) -> bool {
+    let bytes = username.as_bytes();
+    bytes
+        .iter()
+        .all(|&c| c.is_ascii_alphanumeric() || c == b'_')
+        && bytes.iter().any(|c| c.is_ascii_alphanumeric())
+        && bytes.first().is_some_and(|c| !c.is_ascii_digit())
+}
+
+/// Reject a username the pallet would refuse with `UsernameTooShort`, `UsernameTooLong` or
+/// `InvalidUsername`, before paying for a round-trip. The length bounds are the pallet
+/// constants in the node's metadata.
+pub fn check_username(api: &OnlineClient<ChainConfig>, username: &str) -> Result<(), String> {
+    let constants = polkadot::constants().template();
+    let max = api
+        .constants()
+        .at(&constants.max_username_length())
+        .map_err(|e| format!("Unable to read MaxUsernameLength: {e}"))?;
+    let min = api
+        .constants()
+        .at(&constants.min_username_length())
+        .map_err(|e| format!("Unable to read MinUsernameLength: {e}"))?;
+
+    let len = username.len();
+    if len > max as usize {
+        return Err(format!(
+            "Username '{username}' is {len} bytes, the chain allows at most {max}"
+        ));
+    }
+    if len < min as usize {
+        return Err(format!(
+            "Username '{username}' is {len} bytes, the chain requires at least {min}"
+        ));
+    }
+    if !is_valid_charset(username) {
+        return Err(format!(
+            "Username '{username}' may only use letters, digits and '_', and must not start \
+             with a digit"
+        ));
+    }
+
+    Ok(())
+}
+
+/// Run the local checks for `submission` that apply to its action.
+pub fn check_submission(
+    api: &OnlineClient<ChainConfig>,
+    submission: &Submission,
+) -> Result<(), String> {
+    match &submission.action {
+        Action::Set(username) => check_username(api, username),
+        Action::Clear => Ok(()),
+    }
+}
+
+/// Longest pause between two connection attempts.
+pub const MAX_BACKOFF: Duration = Duration::from_secs(10);
+
+/// Connect to `url`, making up to `retries` further attempts if an attempt fails or takes
+/// longer than `timeout`. The pause between attempts starts at one second and doubles.
+/// Each failed attempt is reported on stderr, so stdout stays clean for `--format json`.
+pub async fn connect(
+    url: &str,
+    timeout: Duration,
+    retries: u32,
+) -> Result<OnlineClient<ChainConfig>, String> {
+    let mut backoff = Duration::from_secs(1);
+    for attempt in 0..=retries {
+        let error = match tokio::time::timeout(timeout, OnlineClient::from_url(url)).await {
+            Ok(Ok(api)) => return Ok(api),
+            Ok(Err(e)) => e.to_string(),
+            Err(_) => format!("timed out after {}s", timeout.as_secs()),
+        };
+
+        if attempt == retries {
+            return Err(format!(
+                "Unable to connect to {url} after {} attempt(s): {error}",
+                retries + 1
+            ));
+        }
+        eprintln!(
+            "Connection attempt {} failed ({error}), retrying in {}s",
+            attempt + 1,
+            backoff.as_secs()
+        );
+        tokio::time::sleep(backoff).await;
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+    }
+    unreachable!("the last attempt always returns")
+}
+
+/// Read the current `Nonces` entry for `eth_address` from the latest block.
+pub async fn fetch_nonce(
+    api: &OnlineClient<ChainConfig>,
+    eth_address: H160,
+) -> Result<u64, subxt::Error> {
+    let query = polkadot::storage().template().nonces(eth_address);
+    api.storage()
+        .at_latest()
+        .await?
+        .fetch_or_default(&query)
+        .await
+}
+
+/// Read the username currently registered for `eth_address`, if any.
+pub async fn fetch_username(
+    api: &OnlineClient<ChainConfig>,
+    eth_address: H160,
+) -> Result<Option<String>, subxt::Error> {
+    let query = polkadot::storage().template().usernames(eth_address);
+    let info = api.storage().at_latest().await?.fetch(&query).await?;
+    Ok(info.map(|info| String::from_utf8_lossy(&info.username.0).into_owned()))
+}
+
+/// The events `Template` emitted in `events`. `UsernameSet` is decoded field by field, and the
+/// addresses in other events are recognized by their shape, so every address is formatted as
+/// [`format_address`] does. Other field values keep subxt's rendering.
+pub fn template_events(
+    events: &ExtrinsicEvents<ChainConfig>,
+    lowercase: bool,
+) -> Result<Vec<TemplateEvent>, subxt::Error> {
+    let mut template_events = Vec::new();
+    for event in events.iter() {
+        let event = event?;
+        if event.pallet_name() != "Template" {
+            continue;
+        }
+
+        let fields = match event.as_event::<polkadot::template::events::UsernameSet>()? {
+            Some(set) => vec![
+                (
+                    "eth_address".to_string(),
+                    format_address(&set.eth_address, lowercase),
+                ),
+                (
+                    "username".to_string(),
+                    String::from_utf8_lossy(&set.username.0).into_owned(),
+                ),
+                ("deposit".to_string(), set.deposit.to_string()),
+            ],
+            None => match event.field_values()? {
+                Composite::Named(fields) => fields
+                    .into_iter()
+                    .map(|(name, value)| (name, event_field(&value, lowercase)))
+                    .collect(),
+                Composite::Unnamed(values) => values
+                    .into_iter()
+                    .enumerate()
+                    .map(|(index, value)| (index.to_string(), event_field(&value, lowercase)))
+                    .collect(),
+            },
+        };
+        template_events.push(TemplateEvent {
+            name: event.variant_name().to_string(),
+            fields,
+        });
+    }
+
+    Ok(template_events)
+}
+
+/// `value` as [`format_address`] prints it if it has the shape of an `H160`, a single field
+/// holding 20 bytes, and as subxt renders it otherwise.
+fn event_field<T>(value: &Value<T>, lowercase: bool) -> String {
+    match address_value(value) {
+        Some(address) => format_address(&address, lowercase),
+        None => value.to_string(),
+    }
+}
+
+fn address_value<T>(value: &Value<T>) -> Option<H160> {
+    let ValueDef::Composite(Composite::Unnamed(inner)) = &value.value else {
+        return None;
+    };
+    let [bytes] = inner.as_slice() else {
+        return None;
+    };
+    let ValueDef::Composite(Composite::Unnamed(bytes)) = &bytes.value else {
+        return None;
+    };
+    let bytes = bytes
+        .iter()
+        .map(|byte| byte.as_u128().and_then(|byte| u8::try_from(byte).ok()))
+        .collect::<Option<Vec<u8>>>()?;
+    (bytes.len() == 20).then(|| H160::from_slice(&bytes))
+}
+
+/// Build, sign and submit `submission`, waiting as far as `wait` says, and return where it
+/// was included along with the pallet's events from the extrinsic, with addresses formatted
+/// as `lowercase` says. `url` is the node `api` is connected to, used to reconnect if the
+/// connection drops while waiting (see [`submit_payload`]).
+pub async fn submit(
+    api: &OnlineClient<ChainConfig>,
+    url: &str,
+    from: &Keypair,
+    submission: &Submission,
+    wait: WaitFor,
+    lowercase: bool,
+) -> Result<Inclusion, Box<dyn std::error::Error>> {
+    let template = polkadot::tx().template();
+    match &submission.action {
+        Action::Set(username) => {
+            let tx = template.set_username(
+                submission.eth_address,
+                username.as_bytes().to_vec(),
+                submission.nonce,
+                submission.signature.clone(),
+            );
+            submit_payload(api, url, from, &tx, wait, lowercase).await
+        }
+        Action::Clear => {
+            let tx = template.clear_username(
+                submission.eth_address,
+                submission.nonce,
+                submission.signature.clone(),
+            );
+            submit_payload(api, url, from, &tx, wait, lowercase).await
+        }
+    }
+}
+
+/// Number of connection attempts made after losing the connection to a submitted extrinsic.
+const RECONNECT_ATTEMPTS: u32 = 5;
+
+/// How many blocks back from the chain head a lost extrinsic is looked for after reconnecting.
+/// Extrinsics are mortal for 32 blocks by default, so it can't be included any earlier.
+const RECOVERY_DEPTH: u32 = 64;
+
+/// How many new blocks are watched for a lost extrinsic that isn't included yet.
+const RECOVERY_BLOCKS: u32 = 10;
+
+/// Sign and submit `tx` and wait for it as [`submit`] does.
+///
+/// If the connection drops before the extrinsic is confirmed, the node at `url` is reconnected
+/// to and the recent and next few blocks are searched for the extrinsic, so a flaky connection
+/// doesn't hide whether it landed. If that fails too, the error names the extrinsic hash to
+/// check by hand.
+pub async fn submit_payload<Call: Payload>(
+    api: &OnlineClient<ChainConfig>,
+    url: &str,
+    from: &Keypair,
+    tx: &Call,
+    wait: WaitFor,
+    lowercase: bool,
+) -> Result<Inclusion, Box<dyn std::error::Error>> {
+    let signed = api.tx().create_signed(tx, from, Default::default()).await?;
+    let tx_hash = signed.hash();
+
+    let watched = async {
+        let progress = signed.submit_and_watch().await?;
+        let in_block = match wait {
+            WaitFor::InBlock => progress.wait_for_in_block().await?,
+            WaitFor::Finalized => progress.wait_for_finalized().await?,
+        };
+        let events = in_block.wait_for_success().await?;
+        Ok::<_, subxt::Error>((in_block.block_hash(), events))
+    }
+    .await;
+
+    let (block_hash, events) = match watched {
+        Ok(watched) => watched,
+        Err(e) => {
+            // The node still answers, so the error is its verdict on the extrinsic.
+            if api.blocks().at_latest().await.is_ok() {
+                return Err(e.into());
+            }
+            eprintln!("Lost the connection while waiting for {tx_hash:?} ({e}), reconnecting");
+            match find_extrinsic(url, tx_hash, wait).await {
+                Ok(Some(found)) => found,
+                Ok(None) => {
+                    return Err(format!(
+                        "extrinsic {tx_hash:?} was not found within {RECOVERY_BLOCKS} blocks \
+                         after reconnecting; check the chain for it before resubmitting"
+                    )
+                    .into())
+                }
+                Err(e) => {
+                    return Err(format!(
+                        "lost the connection before extrinsic {tx_hash:?} was confirmed ({e}); \
+                         check the chain for it before resubmitting"
+                    )
+                    .into())
+                }
+            }
+        }
+    };
+
+    Ok(Inclusion {
+        block_hash,
+        tx_hash,
+        events: template_events(&events, lowercase)?,
+    })
+}
+
+/// Reconnect to `url` and look for the extrinsic `tx_hash` in the last [`RECOVERY_DEPTH`]
+/// blocks and the next [`RECOVERY_BLOCKS`] ones, following finalized or best blocks as `wait`
+/// says. Returns the including block and the extrinsic's events, or `None` if it wasn't found.
+async fn find_extrinsic(
+    url: &str,
+    tx_hash: H256,
+    wait: WaitFor,
+) -> Result<Option<(H256, ExtrinsicEvents<ChainConfig>)>, Box<dyn std::error::Error>> {
+    let api = connect(url, DEFAULT_CONNECT_TIMEOUT, RECONNECT_ATTEMPTS).await?;
+    let mut blocks = match wait {
+        WaitFor::InBlock => api.blocks().subscribe_best().await?,
+        WaitFor::Finalized => api.blocks().subscribe_finalized().await?,
+    };
+
+    // The first block is the current head; walk back from it to cover the blocks missed
+    // while disconnected.
+    let Some(head) = blocks.next().await.transpose()? else {
+        return Ok(None);
+    };
+    let mut block = head;
+    for _ in 0..RECOVERY_DEPTH {
+        if let Some(events) = extrinsic_events(&block, tx_hash).await? {
+            return Ok(Some((block.hash(), events)));
+        }
+        if block.number() == 0 {
+            break;
+        }
+        block = api.blocks().at(block.header().parent_hash).await?;
+    }
+
+    for _ in 0..RECOVERY_BLOCKS {
+        let Some(block) = blocks.next().await.transpose()? else {
+            break;
+        };
+        if let Some(events) = extrinsic_events(&block, tx_hash).await? {
+            return Ok(Some((block.hash(), events)));
+        }
+    }
+    Ok(None)
+}
+
+/// The events of the extrinsic `tx_hash` in `block`, or `None` if the block doesn't include it.
+/// Fails if the extrinsic is included but its dispatch failed.
+async fn extrinsic_events(
+    block: &Block<ChainConfig, OnlineClient<ChainConfig>>,
+    tx_hash: H256,
+) -> Result<Option<ExtrinsicEvents<ChainConfig>>, Box<dyn std::error::Error>> {
+    for extrinsic in block.extrinsics().await?.iter() {
+        if extrinsic.hash() != tx_hash {
+            continue;
+        }
+        let events = extrinsic.events().await?;
+        if events.has::<polkadot::system::events::ExtrinsicFailed>()? {
+            return Err(format!(
+                "extrinsic {tx_hash:?} was included in block {:?} but failed",
+                block.hash()
+            )
+            .into());
+        }
+        return Ok(Some(events));
+    }
+    Ok(None)
+}
+
+/// Validate `submission` against the node's metadata and print the encoded call
+/// without signing or submitting it.
+pub fn dry_run(
+    api: &OnlineClient<ChainConfig>,
+    submission: &Submission,
+    lowercase: bool,
+) -> Result<(), subxt::Error> {
+    let template = polkadot::tx().template();
+    let call_data = match &submission.action {
+        Action::Set(username) => {
+            let tx = template.set_username(
+                submission.eth_address,
+                username.as_bytes().to_vec(),
+                submission.nonce,
+                submission.signature.clone(),
+            );
+            api.tx().validate(&tx)?;
+            api.tx().call_data(&tx)?
+        }
+        Action::Clear => {
+            let tx = template.clear_username(
+                submission.eth_address,
+                submission.nonce,
+                submission.signature.clone(),
+            );
+            api.tx().validate(&tx)?;
+            api.tx().call_data(&tx)?
+        }
+    };
+
+    println!("=== Dry run: not submitted ===");
+    println!(
+        "Eth Address: {}",
+        format_address(&submission.eth_address, lowercase)
+    );
+    println!("Action: {}", submission.action);
+    println!("Nonce: {}", submission.nonce);
+    println!("Signature length: {} bytes", submission.signature.len());
+    println!("Call data: 0x{}", hex::encode(call_data));
+
+    Ok(())
+}
+
+/// How long [`submit_set_username`] and the reconnection in [`submit_payload`] wait for each
+/// connection attempt.
+const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Connect to `url` and register `username` for `eth_address` with `set_username`, signed and
+/// paid for by `signer`. `signature` is the owner's signature over
+/// `set_username:{chain_id}:{username}:{nonce}`.
+///
+/// The username is checked against the chain's rules before submitting. Returns the hash of the
+/// finalized block that includes the extrinsic.
+pub async fn submit_set_username(
+    url: &str,
+    signer: &Keypair,
+    eth_address: H160,
+    username: &str,
+    nonce: u64,
+    signature: Vec<u8>,
+) -> Result<H256, Box<dyn std::error::Error>> {
+    let api = connect(url, DEFAULT_CONNECT_TIMEOUT, 0).await?;
+    let submission = Submission {
+        eth_address,
+        action: Action::Set(username.to_string()),
+        nonce,
+        signature,
+    };
+    check_submission(&api, &submission)?;
+    let inclusion = submit(&api, url, signer, &submission, WaitFor::Finalized, false).await?;
+    Ok(inclusion.block_hash)
+}
@@ -1,91 +1,518 @@
 use clap::Parser;
 use sp_core::H160;
-use subxt::{OnlineClient, PolkadotConfig};
-use subxt_signer::sr25519::dev;
-
-#[subxt::subxt(runtime_metadata_path = "../artifacts/metadata.scale")]
-pub mod polkadot {}
+use std::{io::Read, time::Duration};
+use submit_account::{
+    check_submission, connect, dry_run, fetch_nonce, fetch_username, format_address,
+    parse_eth_address, parse_signature, parse_signer, submit, Action, ChainConfig, Inclusion,
+    Submission, TemplateEvent, WaitFor,
+};
+use subxt::OnlineClient;
+use subxt_signer::sr25519::Keypair;
+use tracing_subscriber::EnvFilter;
 
 #[derive(Parser, Debug)]
 #[command(name = "submit-username")]
-#[command(about = "Submit username to Substrate blockchain", long_about = None)]
-struct Args {
+#[command(about = "Submit username extrinsics to a Substrate blockchain", long_about = None)]
+struct Cli {
+    #[command(flatten)]
+    options: Options,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+/// Connection, signer and submission options shared by every subcommand.
+#[derive(clap::Args, Debug)]
+struct Options {
     /// Node RPC URL
-    #[arg(short, long, default_value = "ws://127.0.0.1:9944")]
+    #[arg(
+        short,
+        long,
+        global = true,
+        env = "SUBMIT_USERNAME_URL",
+        default_value = "ws://127.0.0.1:9944"
+    )]
     url: String,
 
+    /// Seconds to wait for each connection attempt before giving up on it
+    #[arg(long, global = true, default_value_t = 10)]
+    connect_timeout: u64,
+
+    /// Extra connection attempts after the first one fails, with exponential backoff
+    #[arg(long, global = true, default_value_t = 0)]
+    retries: u32,
+
+    /// Account that signs and pays for the extrinsic: a dev account name (alice, bob, ...),
+    /// a secret URI such as //Seed, or a mnemonic phrase
+    #[arg(long, global = true, default_value = "alice")]
+    signer: String,
+
+    /// Build and validate the extrinsic against the node's metadata and print it
+    /// instead of submitting it
+    #[arg(long, global = true)]
+    dry_run: bool,
+
+    /// Print the `Template` pallet events emitted by each submitted extrinsic, to confirm
+    /// the pallet accepted the call. With `--format json` they are an `events` array in the
+    /// result
+    #[arg(long, global = true)]
+    show_events: bool,
+
+    /// How long to wait after submitting: until the extrinsic is in a best block, or until
+    /// that block is finalized
+    #[arg(long, global = true, value_enum, default_value_t = WaitFor::Finalized)]
+    wait: WaitFor,
+
+    /// Print addresses as lowercase hex instead of the EIP-55 checksummed form
+    #[arg(long, global = true)]
+    lowercase: bool,
+
+    /// Show more log output: -v for info, -vv for debug, -vvv for trace. Without it,
+    /// `RUST_LOG` is honored
+    #[arg(short, long, global = true, action = clap::ArgAction::Count)]
+    verbose: u8,
+
+    /// How to print the result: readable text, or a single JSON object with the status, block
+    /// hash and extrinsic hash (and the events with `--show-events`) and nothing else
+    #[arg(
+        long,
+        global = true,
+        value_enum,
+        default_value_t = OutputFormat::Plain,
+        conflicts_with = "dry_run"
+    )]
+    format: OutputFormat,
+}
+
+/// How the result of a submission is printed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+enum OutputFormat {
+    /// Progress messages and a summary with ready-to-run follow-up commands
+    Plain,
+    /// Only `{ "status", "block_hash", "tx_hash" }`, plus `events` with `--show-events`, for
+    /// scripts
+    Json,
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum Command {
+    /// Register a username with `set_username`
+    Set(SetArgs),
+    /// Change the username of an address that already has one, with `set_username`
+    Update(UpdateArgs),
+    /// Remove the username of an address with `clear_username`
+    Clear(ClearArgs),
+}
+
+#[derive(clap::Args, Debug)]
+struct SetArgs {
+    /// Ethereum address
+    #[arg(short, long, required_unless_present = "file")]
+    eth_address: Option<String>,
+
+    /// Username to set
+    #[arg(short = 'n', long, required_unless_present = "file")]
+    username: Option<String>,
+
+    /// Ethereum signature (65 bytes hex with 0x prefix), or `-` to read it from stdin
+    #[arg(
+        short = 's',
+        long,
+        visible_alias = "signature",
+        required_unless_present = "file"
+    )]
+    eth_signature: Option<String>,
+
+    #[command(flatten)]
+    nonce: NonceArgs,
+
+    /// CSV file with one `eth_address,username,nonce,signature` row per submission.
+    /// Rows are submitted one after another; failures are reported and skipped
+    #[arg(
+        long,
+        conflicts_with_all = ["eth_address", "username", "eth_signature", "nonce", "auto_nonce"]
+    )]
+    file: Option<String>,
+}
+
+#[derive(clap::Args, Debug)]
+struct UpdateArgs {
     /// Ethereum address
     #[arg(short, long)]
     eth_address: String,
 
-    /// Username to set
+    /// New username
     #[arg(short = 'n', long)]
     username: String,
 
-    /// Ethereum signature (65 bytes hex with 0x prefix)
-    #[arg(short = 's', long)]
+    /// Ethereum signature over `set_username:{chain_id}:{username}:{nonce}`, or `-` to read it
+    /// from stdin
+    #[arg(short = 's', long, visible_alias = "signature")]
+    eth_signature: String,
+
+    #[command(flatten)]
+    nonce: NonceArgs,
+}
+
+#[derive(clap::Args, Debug)]
+struct ClearArgs {
+    /// Ethereum address
+    #[arg(short, long)]
+    eth_address: String,
+
+    /// Ethereum signature over `clear_username:{chain_id}:{eth_address}:{nonce}`, or `-` to
+    /// read it from stdin
+    #[arg(short = 's', long, visible_alias = "signature")]
     eth_signature: String,
 
+    #[command(flatten)]
+    nonce: NonceArgs,
+}
+
+#[derive(clap::Args, Debug)]
+struct NonceArgs {
+    /// Nonce the signature was produced for
     #[arg(short = 'o', long)]
-    nonce: u64,
+    nonce: Option<u64>,
+
+    /// Fetch the address's current nonce from the chain instead of passing --nonce.
+    /// Falls back to --nonce if the query fails
+    #[arg(long)]
+    auto_nonce: bool,
 }
 
-#[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let args = Args::parse();
-    println!("Connecting to node: {}", args.url);
-    let api = OnlineClient::<PolkadotConfig>::from_url(&args.url).await?;
-    println!("Connected");
+/// Parse one `eth_address,username,nonce,signature` CSV row.
+fn parse_row(line: &str) -> Result<Submission, String> {
+    let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+    let [eth_address, username, nonce, signature] = fields[..] else {
+        return Err(format!("expected 4 columns, found {}", fields.len()));
+    };
 
+    Ok(Submission {
+        eth_address: parse_eth_address(eth_address)?,
+        action: Action::Set(username.to_string()),
+        nonce: nonce
+            .parse()
+            .map_err(|_| format!("Invalid nonce '{nonce}'"))?,
+        signature: parse_signature(signature)?,
+    })
+}
+
+/// `--signature` as given, or for `-` the signature read from stdin, e.g. piped from
+/// `signature --json | jq -r .signature`. Surrounding whitespace is dropped and the `0x`
+/// prefix is optional either way.
+fn read_signature(arg: String) -> Result<String, String> {
+    if arg != "-" {
+        return Ok(arg);
+    }
+
+    let mut input = String::new();
+    std::io::stdin()
+        .read_to_string(&mut input)
+        .map_err(|e| format!("Failed to read the signature from stdin: {e}"))?;
+    let hex = input.trim().trim_start_matches("0x");
+    if hex.is_empty() {
+        return Err("No signature on stdin".into());
+    }
+    Ok(format!("0x{hex}"))
+}
+
+/// The nonce to sign with: fetched from the chain with `--auto-nonce`, otherwise `--nonce`.
+/// Where the nonce came from is printed unless `quiet`.
+async fn resolve_nonce(
+    api: &OnlineClient<ChainConfig>,
+    eth_address: H160,
+    args: &NonceArgs,
+    quiet: bool,
+) -> Result<u64, String> {
+    if !args.auto_nonce {
+        return args
+            .nonce
+            .ok_or_else(|| "--nonce is required unless --auto-nonce is set".into());
+    }
 
-    let eth_addr_hex = args.eth_address.trim_start_matches("0x");
-    let eth_addr_bytes = hex::decode(eth_addr_hex).map_err(|_| "Invalid Ethereum address hex")?;
+    match fetch_nonce(api, eth_address).await {
+        Ok(nonce) => {
+            if !quiet {
+                println!("Fetched nonce from chain: {nonce}");
+            }
+            Ok(nonce)
+        }
+        Err(e) => match args.nonce {
+            Some(nonce) => {
+                if !quiet {
+                    println!("Failed to fetch nonce ({e}), falling back to --nonce {nonce}");
+                }
+                Ok(nonce)
+            }
+            None => Err(format!("Failed to fetch nonce from chain: {e}")),
+        },
+    }
+}
 
-    if eth_addr_bytes.len() != 20 {
-        return Err("Ethereum address must be 20 bytes".into());
+/// The `--format json` result of a submission that was included as `wait` says, with its
+/// `Template` events if `show_events`.
+fn json_result(inclusion: &Inclusion, wait: WaitFor, show_events: bool) -> serde_json::Value {
+    let mut result = serde_json::json!({
+        "status": match wait {
+            WaitFor::Finalized => "finalized",
+            WaitFor::InBlock => "in_block",
+        },
+        "block_hash": format!("{:?}", inclusion.block_hash),
+        "tx_hash": format!("{:?}", inclusion.tx_hash),
+    });
+    if show_events {
+        let events = inclusion
+            .events
+            .iter()
+            .map(|event| {
+                let fields: serde_json::Map<String, serde_json::Value> = event
+                    .fields
+                    .iter()
+                    .map(|(name, value)| (name.clone(), serde_json::Value::String(value.clone())))
+                    .collect();
+                serde_json::json!({ "name": event.name, "fields": fields })
+            })
+            .collect();
+        result["events"] = serde_json::Value::Array(events);
     }
+    result
+}
 
-    let mut eth_address = [0u8; 20];
-    eth_address.copy_from_slice(&eth_addr_bytes);
-    let eth_address = H160(eth_address);
+/// Print `events` for `--show-events`, one event name per line followed by its fields.
+fn print_events(events: &[TemplateEvent]) {
+    println!("=== Template events ===");
+    for event in events {
+        println!("{}", event.name);
+        for (name, value) in &event.fields {
+            println!("  {name}: {value}");
+        }
+    }
+}
 
-    let nonce = args.nonce;
+/// Submit every row of `path` in order as `options` say, returning the number of failed rows.
+async fn submit_file(
+    api: &OnlineClient<ChainConfig>,
+    from: &Keypair,
+    path: &str,
+    options: &Options,
+) -> Result<usize, Box<dyn std::error::Error>> {
+    let lowercase = options.lowercase;
+    let contents = std::fs::read_to_string(path)?;
+    let (mut succeeded, mut failed) = (0usize, 0usize);
 
-    let username = args.username.as_bytes().to_vec();
+    for (index, line) in contents.lines().enumerate() {
+        let row = index + 1;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with("eth_address") {
+            continue;
+        }
 
-    // Parse signature
-    let sig_hex = args.eth_signature.trim_start_matches("0x");
-    let signature = hex::decode(sig_hex).map_err(|_| "Invalid signature hex")?;
+        let checked = parse_row(line)
+            .and_then(|submission| check_submission(api, &submission).map(|_| submission));
+        let result = match checked {
+            Ok(submission) if options.dry_run => dry_run(api, &submission, lowercase)
+                .map(|_| submission.action)
+                .map_err(|e| e.to_string()),
+            Ok(submission) => submit(
+                api,
+                &options.url,
+                from,
+                &submission,
+                options.wait,
+                lowercase,
+            )
+            .await
+            .map(|inclusion| {
+                if options.show_events {
+                    print_events(&inclusion.events);
+                }
+                submission.action
+            })
+            .map_err(|e| e.to_string()),
+            Err(e) => Err(e),
+        };
 
-    if signature.len() != 65 {
-        return Err("Signature must be 65 bytes".into());
+        match result {
+            Ok(action) => {
+                succeeded += 1;
+                println!("Row {row}: OK ({action})");
+            }
+            Err(e) => {
+                failed += 1;
+                println!("Row {row}: FAILED ({e})");
+            }
+        }
     }
 
-    println!("Submitting ...");
-    println!("Using nonce: {nonce}");
-    println!("Eth Address: 0x{}", hex::encode(eth_addr_bytes));
-    println!("Username: {}", String::from_utf8_lossy(&username));
-    println!("Signature: {}...", &args.eth_signature[..20]);
     println!();
+    println!("=== {succeeded} succeeded, {failed} failed ===");
+
+    Ok(failed)
+}
+
+/// Send log output to stderr: `RUST_LOG` decides what is shown, unless `-v` (info), `-vv`
+/// (debug) or `-vvv` (trace) is given. Only warnings are shown by default, so the regular
+/// output stays as it was.
+fn init_logging(verbose: u8) {
+    let filter = match verbose {
+        0 => EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("warn")),
+        1 => EnvFilter::new("info"),
+        2 => EnvFilter::new("debug"),
+        _ => EnvFilter::new("trace"),
+    };
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(std::io::stderr)
+        .init();
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let Cli { options, command } = Cli::parse();
+    init_logging(options.verbose);
+    let json = options.format == OutputFormat::Json;
+    if json && matches!(&command, Command::Set(SetArgs { file: Some(_), .. })) {
+        return Err(
+            "--format json reports a single submission and can't be used with --file".into(),
+        );
+    }
+
+    let from = parse_signer(&options.signer)?;
+    if !json {
+        println!("Connecting to node: {}", options.url);
+    }
+    let api = connect(
+        &options.url,
+        Duration::from_secs(options.connect_timeout),
+        options.retries,
+    )
+    .await?;
+    if !json {
+        println!("Connected");
+    }
+
+    let (eth_address, action, eth_signature, nonce_args) = match command {
+        Command::Set(SetArgs {
+            file: Some(path), ..
+        }) => {
+            let failed = submit_file(&api, &from, &path, &options).await?;
+            if failed > 0 {
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+        // clap guarantees these are present when --file is not given.
+        Command::Set(args) => (
+            parse_eth_address(&args.eth_address.unwrap_or_default())?,
+            Action::Set(args.username.unwrap_or_default()),
+            args.eth_signature.unwrap_or_default(),
+            args.nonce,
+        ),
+        Command::Update(args) => {
+            let eth_address = parse_eth_address(&args.eth_address)?;
+            match fetch_username(&api, eth_address).await? {
+                Some(current) if !json => println!("Current username: {current}"),
+                Some(_) => {}
+                None => {
+                    return Err(format!(
+                        "{} has no username yet, register one with `set`",
+                        format_address(&eth_address, options.lowercase)
+                    )
+                    .into())
+                }
+            }
+            (
+                eth_address,
+                Action::Set(args.username),
+                args.eth_signature,
+                args.nonce,
+            )
+        }
+        Command::Clear(args) => (
+            parse_eth_address(&args.eth_address)?,
+            Action::Clear,
+            args.eth_signature,
+            args.nonce,
+        ),
+    };
 
-    let tx = polkadot::tx()
-        .template()
-        .set_username(eth_address, username, nonce, signature);
+    let eth_signature = read_signature(eth_signature)?;
+    let nonce = resolve_nonce(&api, eth_address, &nonce_args, json).await?;
+    let submission = Submission {
+        eth_address,
+        action,
+        nonce,
+        signature: parse_signature(&eth_signature)?,
+    };
 
-    let from = dev::alice();
+    check_submission(&api, &submission)?;
 
-    api.tx()
-        .sign_and_submit_then_watch_default(&tx, &from)
-        .await?
-        .wait_for_finalized_success()
+    if options.dry_run {
+        dry_run(&api, &submission, options.lowercase)?;
+        return Ok(());
+    }
+
+    if json {
+        let inclusion = submit(
+            &api,
+            &options.url,
+            &from,
+            &submission,
+            options.wait,
+            options.lowercase,
+        )
         .await?;
+        println!(
+            "{}",
+            json_result(&inclusion, options.wait, options.show_events)
+        );
+        return Ok(());
+    }
 
-    println!("=== Transaction finalized in block! ===");
+    let display_address = format_address(&submission.eth_address, options.lowercase);
+    println!("Submitting ...");
+    println!("Using nonce: {nonce}");
+    println!("Eth Address: {display_address}");
+    println!("Action: {}", submission.action);
+    println!("Signature: {}...", &eth_signature[..20]);
+    println!();
+
+    let inclusion = submit(
+        &api,
+        &options.url,
+        &from,
+        &submission,
+        options.wait,
+        options.lowercase,
+    )
+    .await?;
+    if options.show_events {
+        print_events(&inclusion.events);
+    }
+    let block_hash = inclusion.block_hash;
+
+    match options.wait {
+        WaitFor::Finalized => println!("=== Transaction finalized in block {block_hash:?}! ==="),
+        WaitFor::InBlock => {
+            println!("=== Transaction included in block {block_hash:?} ===");
+            println!("Note: this block is not finalized yet and could still be reverted");
+        }
+    }
+    println!("Submitted with:");
+    let subcommand = match &submission.action {
+        Action::Set(username) => format!("set --username {username}"),
+        Action::Clear => "clear".to_string(),
+    };
+    println!(
+        "submit_account --url {} {subcommand} --eth-address {display_address} --nonce {} --signature {}",
+        options.url, nonce, eth_signature
+    );
+    println!();
     println!("Query with:");
     println!(
-        r#"curl -H "Content-Type: application/json" -d '{{"id":1,"jsonrpc":"2.0","method":"username_get","params":["0x{}", null]}}' {}"#,
-        hex::encode(eth_address.as_bytes()),
-        args.url.replace("ws://", "http://").replace("9944", "9944")
+        r#"curl -H "Content-Type: application/json" -d '{{"id":1,"jsonrpc":"2.0","method":"username_get","params":["{display_address}", null]}}' {}"#,
+        options.url.replace("ws://", "http://")
     );
 
     Ok(())
@@ -0,0 +1,48 @@
+//! Runs against a dev node at `ws://127.0.0.1:9944`, e.g. one started with
+//! `./target/release/solochain-template-node --dev`, so it is ignored by default:
+//!
+//! ```sh
+//! cargo test -p submit_account -- --ignored
+//! ```
+
+use sp_core::{ecdsa, hashing::blake2_256, Pair};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use submit_account::{connect, fetch_nonce, fetch_username, submit_set_username};
+use subxt_signer::sr25519::dev;
+use username_primitives::{eth_message_hash, recover_eth_address_prehashed, set_username_message};
+
+const URL: &str = "ws://127.0.0.1:9944";
+
+/// Chain id of the runtime in this repository.
+const CHAIN_ID: u64 = 42;
+
+#[tokio::test]
+#[ignore = "needs a running dev node"]
+async fn submit_set_username_registers_the_name() {
+    // A fresh owner on every run, so earlier runs against the same node can't get in the way.
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+    let pair = ecdsa::Pair::from_seed(&blake2_256(&now.as_nanos().to_le_bytes()));
+    let probe_hash = [0u8; 32];
+    let probe = pair.sign_prehashed(&probe_hash).0;
+    let eth_address = recover_eth_address_prehashed(&probe_hash, &probe).unwrap();
+    let username = format!("itest_{}", &hex::encode(eth_address.as_bytes())[..8]);
+
+    let api = connect(URL, Duration::from_secs(10), 0)
+        .await
+        .expect("a dev node should be listening on ws://127.0.0.1:9944");
+    let nonce = fetch_nonce(&api, eth_address).await.unwrap();
+    assert_eq!(nonce, 0);
+    let message = set_username_message(CHAIN_ID, username.as_bytes(), nonce);
+    let mut signature = pair.sign_prehashed(&eth_message_hash(&message)).0.to_vec();
+    signature[64] += 27;
+
+    submit_set_username(URL, &dev::alice(), eth_address, &username, nonce, signature)
+        .await
+        .unwrap_or_else(|e| panic!("set_username for {username} failed: {e}"));
+
+    assert_eq!(
+        fetch_username(&api, eth_address).await.unwrap(),
+        Some(username)
+    );
+    assert_eq!(fetch_nonce(&api, eth_address).await.unwrap(), nonce + 1);
+}